@@ -0,0 +1,24 @@
+use tauri::State;
+
+use crate::error::AppResult;
+use crate::jobs::JobQueue;
+use crate::storage::{self, Store};
+use crate::types::AppDataBackup;
+
+#[tauri::command]
+pub fn list_app_data_backups() -> AppResult<Vec<AppDataBackup>> {
+    storage::list_backups()
+}
+
+/// Why the snapshot taken when the app started failed, if it did
+#[tauri::command]
+pub fn get_app_data_backup_error() -> AppResult<Option<String>> {
+    Store::open()?.last_backup_error()
+}
+
+/// Restores a backup over the current database, also recovering from a corrupt file.
+/// Refused while clones are queued or running, since they write their history to it.
+#[tauri::command]
+pub fn restore_app_data_backup(queue: State<'_, JobQueue>, name: String) -> AppResult<()> {
+    queue.while_idle(|| storage::restore_backup(&name))
+}
@@ -0,0 +1,3 @@
+fn main() {
+    std::process::exit(app_clone_db_lib::run_cli())
+}
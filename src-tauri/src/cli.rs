@@ -0,0 +1,501 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::engine::{execute_schema_download, prepare_clone, run_clone, ProgressSink};
+use crate::error::{AppError, AppResult};
+use crate::storage::Store;
+use crate::types::{
+    CloneOptions, CloneStatus, CloneType, ConnectionProfile, HistoryFilter, HistoryPage,
+    SchemaExportOptions,
+};
+
+// Exit codes; 0 is success and 2 is also used by clap for usage errors
+const EXIT_FAILED: i32 = 1;
+const EXIT_INVALID: i32 = 2;
+const EXIT_NOT_FOUND: i32 = 3;
+const EXIT_TOOL_NOT_FOUND: i32 = 4;
+const EXIT_CONNECTION: i32 = 5;
+const EXIT_PARTIAL_FAILURE: i32 = 6;
+const EXIT_STORAGE: i32 = 7;
+
+/// Runs clones and exports with the profiles and saved operations of the app.
+/// Results are printed to stdout as JSON, log lines and errors go to stderr.
+#[derive(Parser)]
+#[command(name = "db-clone", version)]
+struct Cli {
+    /// Leave log lines out of stderr
+    #[arg(long, short, global = true)]
+    quiet: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Connection profiles
+    #[command(subcommand)]
+    Profiles(ProfilesCommand),
+    /// Clone the database of one profile into another
+    Clone(CloneArgs),
+    /// Run a saved operation, found by name or id
+    RunOperation { operation: String },
+    /// Database schemas
+    #[command(subcommand)]
+    Schema(SchemaCommand),
+    /// Recorded clones, newest first
+    History(HistoryArgs),
+}
+
+#[derive(Subcommand)]
+enum ProfilesCommand {
+    /// List the profiles, without their passwords
+    List,
+}
+
+#[derive(Args)]
+struct CloneArgs {
+    /// Source profile name or id
+    #[arg(long)]
+    source: String,
+    /// Destination profile name or id
+    #[arg(long = "dest")]
+    destination: String,
+    /// What to clone (default: both)
+    #[arg(long = "type", value_enum)]
+    clone_type: Option<CliCloneType>,
+    /// Drop the destination's objects before restoring
+    #[arg(long)]
+    clean: bool,
+    /// Back up the destination before changing it
+    #[arg(long)]
+    backup: bool,
+    /// Table pattern left out of the clone; can be repeated
+    #[arg(long = "exclude-table")]
+    exclude_tables: Vec<String>,
+    /// JSON file with any other clone options (selection, row filters, subset,
+    /// masking, fan-out, ownership), as the app records them; flags take precedence
+    #[arg(long = "options", value_name = "FILE")]
+    options_file: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CliCloneType {
+    Structure,
+    Data,
+    Both,
+}
+
+impl From<CliCloneType> for CloneType {
+    fn from(clone_type: CliCloneType) -> Self {
+        match clone_type {
+            CliCloneType::Structure => CloneType::Structure,
+            CliCloneType::Data => CloneType::Data,
+            CliCloneType::Both => CloneType::Both,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum SchemaCommand {
+    /// Write the schema of a profile's database to a SQL file
+    Export {
+        /// Profile name or id
+        #[arg(long)]
+        profile: String,
+        #[arg(long, short)]
+        output: PathBuf,
+        /// Schema to export; can be repeated (default: all)
+        #[arg(long = "schema")]
+        schemas: Vec<String>,
+        /// Table to export as schema.table; can be repeated (default: all)
+        #[arg(long = "table")]
+        tables: Vec<String>,
+    },
+}
+
+#[derive(Args)]
+struct HistoryArgs {
+    /// Only clones from or to this profile (name or id)
+    #[arg(long)]
+    profile: Option<String>,
+    #[arg(long, value_enum)]
+    status: Option<CliCloneStatus>,
+    #[arg(long, default_value_t = 20)]
+    limit: u32,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CliCloneStatus {
+    Running,
+    Success,
+    Error,
+    Cancelled,
+    Interrupted,
+}
+
+impl From<CliCloneStatus> for CloneStatus {
+    fn from(status: CliCloneStatus) -> Self {
+        match status {
+            CliCloneStatus::Running => CloneStatus::Running,
+            CliCloneStatus::Success => CloneStatus::Success,
+            CliCloneStatus::Error => CloneStatus::Error,
+            CliCloneStatus::Cancelled => CloneStatus::Cancelled,
+            CliCloneStatus::Interrupted => CloneStatus::Interrupted,
+        }
+    }
+}
+
+/// Prints log lines to stderr; progress is left out since the log covers it
+struct TerminalSink {
+    quiet: bool,
+}
+
+impl<P> ProgressSink<P> for TerminalSink {
+    fn progress(&self, _progress: P) {}
+
+    fn log(&self, line: &str) {
+        if !self.quiet {
+            eprintln!("{}", line);
+        }
+    }
+}
+
+fn exit_code(error: &AppError) -> i32 {
+    match error {
+        AppError::InvalidInput(_) => EXIT_INVALID,
+        AppError::ProfileNotFound { .. }
+        | AppError::SavedOperationNotFound { .. }
+        | AppError::HistoryEntryNotFound { .. }
+        | AppError::JobNotFound { .. } => EXIT_NOT_FOUND,
+        AppError::ToolNotFound { .. } => EXIT_TOOL_NOT_FOUND,
+        AppError::ConnectionFailed { .. } | AppError::AuthFailed { .. } => EXIT_CONNECTION,
+        AppError::PartialFailure { .. } => EXIT_PARTIAL_FAILURE,
+        AppError::StorageFailed { .. } | AppError::StorageCorrupt { .. } => EXIT_STORAGE,
+        _ => EXIT_FAILED,
+    }
+}
+
+/// Finds a profile by id or, failing that, by its unique name
+fn find_profile(store: &Store, key: &str) -> AppResult<ConnectionProfile> {
+    let profiles = store.list_profiles()?;
+    if let Some(profile) = profiles.iter().find(|p| p.id == key) {
+        return Ok(profile.clone());
+    }
+
+    let mut named = profiles.into_iter().filter(|p| p.name == key);
+    match (named.next(), named.next()) {
+        (Some(profile), None) => Ok(profile),
+        (Some(_), Some(_)) => Err(AppError::InvalidInput(format!(
+            "Several profiles are named {}; use the profile id",
+            key
+        ))),
+        (None, _) => Err(AppError::ProfileNotFound {
+            id: key.to_string(),
+        }),
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> AppResult<Value> {
+    serde_json::to_value(value).map_err(|e| AppError::Internal(e.to_string()))
+}
+
+/// Runs a clone without the job queue and returns its history entry, which is
+/// printed whether the clone succeeded or not
+async fn clone(sink: Arc<TerminalSink>, options: CloneOptions) -> (Option<Value>, AppResult<()>) {
+    let prepared = match prepare_clone(options) {
+        Ok(prepared) => prepared,
+        Err(e) => return (None, Err(e)),
+    };
+    let (entry, result) = run_clone(sink, prepared).await;
+    (to_json(&entry).ok(), result)
+}
+
+fn clone_options(args: CloneArgs) -> AppResult<CloneOptions> {
+    let store = Store::open()?;
+    let source = find_profile(&store, &args.source)?;
+    let destination = find_profile(&store, &args.destination)?;
+
+    let mut options = CloneOptions::new(source.id, destination.id, CloneType::Both);
+    if let Some(path) = &args.options_file {
+        options = read_options(path, options)?;
+    }
+    if let Some(clone_type) = args.clone_type {
+        options.clone_type = clone_type.into();
+    }
+    options.clean_destination |= args.clean;
+    options.create_backup |= args.backup;
+    options.exclude_tables.extend(args.exclude_tables);
+    Ok(options)
+}
+
+/// Lays the options of a JSON file over `base`. The profiles always come from `base`.
+fn read_options(path: &Path, base: CloneOptions) -> AppResult<CloneOptions> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| AppError::storage(&format!("Failed to read {}", path.display()), e))?;
+    let invalid = |e: serde_json::Error| {
+        AppError::InvalidInput(format!(
+            "Invalid clone options in {}: {}",
+            path.display(),
+            e
+        ))
+    };
+    let Value::Object(fields) = serde_json::from_str(&content).map_err(invalid)? else {
+        return Err(AppError::InvalidInput(format!(
+            "{} must hold a JSON object of clone options",
+            path.display()
+        )));
+    };
+
+    let mut value = to_json(&base)?;
+    if let Some(options) = value.as_object_mut() {
+        options.extend(fields);
+    }
+    let options: CloneOptions = serde_json::from_value(value).map_err(invalid)?;
+    Ok(CloneOptions {
+        source_id: base.source_id,
+        destination_id: base.destination_id,
+        ..options
+    })
+}
+
+fn operation_options(key: &str) -> AppResult<CloneOptions> {
+    let operations = Store::open()?.list_saved_operations()?;
+    operations
+        .iter()
+        .find(|o| o.id == key)
+        .or_else(|| operations.iter().find(|o| o.name == key))
+        .map(|o| o.clone_options())
+        .ok_or_else(|| AppError::SavedOperationNotFound {
+            id: key.to_string(),
+        })
+}
+
+fn list_profiles() -> AppResult<Value> {
+    let profiles = Store::open()?.list_profiles()?;
+    let mut value = to_json(&profiles)?;
+    if let Some(profiles) = value.as_array_mut() {
+        for profile in profiles.iter_mut().filter_map(Value::as_object_mut) {
+            profile.remove("password");
+        }
+    }
+    Ok(value)
+}
+
+fn history(args: HistoryArgs) -> AppResult<Value> {
+    let store = Store::open()?;
+    let profile_id = args
+        .profile
+        .map(|key| find_profile(&store, &key).map(|p| p.id))
+        .transpose()?;
+    let filter = HistoryFilter {
+        profile_id,
+        status: args.status.map(Into::into),
+        limit: Some(args.limit),
+        ..Default::default()
+    };
+
+    to_json(&HistoryPage {
+        entries: store.list_history(&filter)?,
+        total: store.count_history(&filter)?,
+    })
+}
+
+async fn export_schema(
+    sink: &TerminalSink,
+    profile: &str,
+    output: PathBuf,
+    schemas: Vec<String>,
+    tables: Vec<String>,
+) -> AppResult<Value> {
+    let profile = find_profile(&Store::open()?, profile)?;
+    let options: SchemaExportOptions = serde_json::from_value(serde_json::json!({
+        "profileId": profile.id,
+        "schemas": schemas,
+        "tables": tables,
+    }))
+    .map_err(|e| AppError::Internal(e.to_string()))?;
+
+    let schema = execute_schema_download(sink, &profile, &options).await?;
+    std::fs::write(&output, &schema)
+        .map_err(|e| AppError::storage(&format!("Failed to write {}", output.display()), e))?;
+
+    Ok(serde_json::json!({
+        "profile": profile.name,
+        "path": output,
+        "bytes": schema.len(),
+    }))
+}
+
+async fn execute(command: Command, sink: Arc<TerminalSink>) -> (Option<Value>, AppResult<()>) {
+    let output = match command {
+        Command::Profiles(ProfilesCommand::List) => list_profiles(),
+        Command::Clone(args) => {
+            return match clone_options(args) {
+                Ok(options) => clone(sink, options).await,
+                Err(e) => (None, Err(e)),
+            };
+        }
+        Command::RunOperation { operation } => {
+            return match operation_options(&operation) {
+                Ok(options) => clone(sink, options).await,
+                Err(e) => (None, Err(e)),
+            };
+        }
+        Command::Schema(SchemaCommand::Export {
+            profile,
+            output,
+            schemas,
+            tables,
+        }) => export_schema(&sink, &profile, output, schemas, tables).await,
+        Command::History(args) => history(args),
+    };
+
+    match output {
+        Ok(value) => (Some(value), Ok(())),
+        Err(e) => (None, Err(e)),
+    }
+}
+
+/// Entry point of the `db-clone` binary; returns the process exit code
+pub fn run() -> i32 {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            return e.exit_code();
+        }
+    };
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            let error = AppError::Internal(format!("Failed to start the async runtime: {}", e));
+            eprintln!("{}", serde_json::to_string(&error).unwrap_or_default());
+            return EXIT_FAILED;
+        }
+    };
+    let sink = Arc::new(TerminalSink { quiet: cli.quiet });
+    let (output, result) = runtime.block_on(execute(cli.command, sink));
+
+    if let Some(output) = output {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output).unwrap_or_default()
+        );
+    }
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", serde_json::to_string(&e).unwrap_or_default());
+            exit_code(&e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_clone_arguments() {
+        let cli = Cli::try_parse_from([
+            "db-clone",
+            "clone",
+            "--source",
+            "prod",
+            "--dest",
+            "local",
+            "--type",
+            "structure",
+            "--exclude-table",
+            "audit_*",
+            "--exclude-table",
+            "logs",
+            "--quiet",
+        ])
+        .unwrap();
+
+        assert!(cli.quiet);
+        let Command::Clone(args) = cli.command else {
+            panic!("expected the clone command");
+        };
+        assert_eq!(args.source, "prod");
+        assert_eq!(args.destination, "local");
+        assert!(matches!(
+            args.clone_type.map(CloneType::from),
+            Some(CloneType::Structure)
+        ));
+        assert_eq!(args.exclude_tables, vec!["audit_*", "logs"]);
+        assert!(!args.clean);
+
+        assert!(Cli::try_parse_from(["db-clone", "clone", "--source", "prod"]).is_err());
+    }
+
+    #[test]
+    fn reads_clone_options_from_a_file() {
+        let path =
+            std::env::temp_dir().join(format!("db-clone-options-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            r#"{
+                "sourceId": "ignored",
+                "includeSchemas": ["billing"],
+                "rowFilters": [{ "table": "orders", "whereClause": "total > 100" }],
+                "destinationIds": ["qa"],
+                "keepOwnership": true
+            }"#,
+        )
+        .unwrap();
+        let base = CloneOptions::new("prod".to_string(), "local".to_string(), CloneType::Data);
+
+        let options = read_options(&path, base.clone()).unwrap();
+        assert_eq!(options.source_id, "prod");
+        assert!(matches!(options.clone_type, CloneType::Data));
+        assert_eq!(options.include_schemas, ["billing"]);
+        assert_eq!(options.row_filters[0].where_clause, "total > 100");
+        assert_eq!(options.destination_ids, ["qa"]);
+        assert!(options.keep_ownership);
+
+        std::fs::write(&path, "[]").unwrap();
+        assert!(matches!(
+            read_options(&path, base.clone()),
+            Err(AppError::InvalidInput(_))
+        ));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            read_options(&path, base),
+            Err(AppError::StorageFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn maps_errors_to_exit_codes() {
+        let not_found = AppError::ProfileNotFound {
+            id: "prod".to_string(),
+        };
+        assert_eq!(exit_code(&not_found), EXIT_NOT_FOUND);
+        assert_eq!(
+            exit_code(&AppError::ToolNotFound { tool: "pg_dump" }),
+            EXIT_TOOL_NOT_FOUND
+        );
+        assert_eq!(
+            exit_code(&AppError::InvalidInput("bad".to_string())),
+            EXIT_INVALID
+        );
+        assert_eq!(
+            exit_code(&AppError::storage(
+                "Failed to write schema.sql",
+                "disk full"
+            )),
+            EXIT_STORAGE
+        );
+        assert_eq!(
+            exit_code(&AppError::Internal("boom".to_string())),
+            EXIT_FAILED
+        );
+    }
+}
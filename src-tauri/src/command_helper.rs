@@ -8,6 +8,7 @@ const CREATE_NO_WINDOW: u32 = 0x08000000;
 
 /// Creates a Command with platform-specific settings to hide console windows on Windows
 pub fn create_command(program: &str) -> Command {
+    #[cfg_attr(not(windows), allow(unused_mut))]
     let mut cmd = Command::new(program);
 
     #[cfg(windows)]
@@ -15,3 +16,8 @@ pub fn create_command(program: &str) -> Command {
 
     cmd
 }
+
+/// Same as `create_command`, for a child process awaited without blocking the runtime
+pub fn create_async_command(program: &str) -> tokio::process::Command {
+    tokio::process::Command::from(create_command(program))
+}
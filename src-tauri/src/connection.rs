@@ -1,14 +1,106 @@
-use crate::command_helper::create_command;
-use crate::pg_tools::{check_tools_available, find_psql};
-use crate::storage::load_app_data;
+use tokio_postgres::Client;
+
+use crate::error::{AppError, AppResult};
+use crate::pg_client::{connect, connect_profile};
+use crate::pg_tools::check_tools_available;
+use crate::storage::Store;
 use crate::types::{ConnectionProfile, DatabaseInfo, DatabaseStructure, SchemaInfo, TableInfo};
 
-#[tauri::command]
-pub fn check_pg_tools() -> Result<bool, String> {
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn check_pg_tools() -> AppResult<bool> {
     Ok(check_tools_available())
 }
 
-#[tauri::command]
+const TABLES_QUERY: &str = r#"
+    SELECT
+        t.table_name::text,
+        t.table_schema::text,
+        COALESCE(s.n_live_tup, 0)::bigint as row_count,
+        COALESCE(pg_total_relation_size(quote_ident(t.table_schema) || '.' || quote_ident(t.table_name)), 0)::bigint as size
+    FROM information_schema.tables t
+    LEFT JOIN pg_stat_user_tables s ON t.table_name = s.relname AND t.table_schema = s.schemaname
+    WHERE t.table_schema NOT IN ('pg_catalog', 'information_schema')
+    AND t.table_type = 'BASE TABLE'
+    ORDER BY t.table_schema, t.table_name;
+"#;
+
+/// Lists every user table with its estimated row count and total size
+pub async fn fetch_tables(client: &Client) -> AppResult<Vec<TableInfo>> {
+    let rows = client
+        .query(TABLES_QUERY, &[])
+        .await
+        .map_err(|e| AppError::query("Failed to get tables", &e))?;
+
+    Ok(rows
+        .iter()
+        .map(|row| TableInfo {
+            name: row.get(0),
+            schema: row.get(1),
+            row_count: row.get(2),
+            size: row.get(3),
+        })
+        .collect())
+}
+
+const FOREIGN_KEYS_QUERY: &str = r#"
+    SELECT
+        child_ns.nspname::text,
+        child.relname::text,
+        parent_ns.nspname::text,
+        parent.relname::text,
+        ARRAY(
+            SELECT a.attname::text
+            FROM unnest(c.conkey) WITH ORDINALITY AS k(attnum, ord)
+            JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum
+            ORDER BY k.ord
+        ),
+        ARRAY(
+            SELECT a.attname::text
+            FROM unnest(c.confkey) WITH ORDINALITY AS k(attnum, ord)
+            JOIN pg_attribute a ON a.attrelid = c.confrelid AND a.attnum = k.attnum
+            ORDER BY k.ord
+        )
+    FROM pg_constraint c
+    JOIN pg_class child ON child.oid = c.conrelid
+    JOIN pg_namespace child_ns ON child_ns.oid = child.relnamespace
+    JOIN pg_class parent ON parent.oid = c.confrelid
+    JOIN pg_namespace parent_ns ON parent_ns.oid = parent.relnamespace
+    WHERE c.contype = 'f'
+    AND child_ns.nspname NOT IN ('pg_catalog', 'information_schema');
+"#;
+
+/// A foreign key from `columns` of the child table to `referenced_columns` of the parent
+#[derive(Debug, Clone)]
+pub struct ForeignKey {
+    pub schema: String,
+    pub table: String,
+    pub columns: Vec<String>,
+    pub referenced_schema: String,
+    pub referenced_table: String,
+    pub referenced_columns: Vec<String>,
+}
+
+/// Lists every foreign key between user tables
+pub async fn fetch_foreign_keys(client: &Client) -> AppResult<Vec<ForeignKey>> {
+    let rows = client
+        .query(FOREIGN_KEYS_QUERY, &[])
+        .await
+        .map_err(|e| AppError::query("Failed to get foreign keys", &e))?;
+
+    Ok(rows
+        .iter()
+        .map(|row| ForeignKey {
+            schema: row.get(0),
+            table: row.get(1),
+            referenced_schema: row.get(2),
+            referenced_table: row.get(3),
+            columns: row.get(4),
+            referenced_columns: row.get(5),
+        })
+        .collect())
+}
+
+#[cfg_attr(feature = "gui", tauri::command)]
 pub async fn test_connection(
     host: String,
     port: u16,
@@ -16,86 +108,25 @@ pub async fn test_connection(
     user: String,
     password: String,
     ssl: bool,
-) -> Result<DatabaseInfo, String> {
-    let psql = find_psql().ok_or("psql not found. Please install PostgreSQL client tools.")?;
-
-    // Build connection string
-    let conn_str = format!(
-        "host={} port={} dbname={} user={}",
-        host, port, database, user
-    );
+) -> AppResult<DatabaseInfo> {
+    let client = connect(&host, port, &database, &user, &password, ssl).await?;
 
     // First, test basic connection and get version
-    let version_output = create_command(&psql)
-        .env("PGPASSWORD", &password)
-        .env("PGSSLMODE", if ssl { "require" } else { "prefer" })
-        .args(["-d", &conn_str, "-t", "-c", "SELECT version();"])
-        .output()
-        .map_err(|e| format!("Failed to execute psql: {}", e))?;
-
-    if !version_output.status.success() {
-        let stderr = String::from_utf8_lossy(&version_output.stderr);
-        return Err(format!("Connection failed: {}", stderr));
-    }
-
-    let version = String::from_utf8_lossy(&version_output.stdout)
-        .trim()
-        .to_string();
+    let version: String = client
+        .query_one("SELECT version();", &[])
+        .await
+        .map_err(|e| AppError::query("Failed to get server version", &e))?
+        .get(0);
 
     // Get table information
-    let tables_query = r#"
-        SELECT
-            t.table_name,
-            t.table_schema,
-            COALESCE(s.n_live_tup, 0)::bigint as row_count,
-            COALESCE(pg_total_relation_size(quote_ident(t.table_schema) || '.' || quote_ident(t.table_name)), 0)::bigint as size
-        FROM information_schema.tables t
-        LEFT JOIN pg_stat_user_tables s ON t.table_name = s.relname AND t.table_schema = s.schemaname
-        WHERE t.table_schema NOT IN ('pg_catalog', 'information_schema')
-        AND t.table_type = 'BASE TABLE'
-        ORDER BY t.table_schema, t.table_name;
-    "#;
-
-    let tables_output = create_command(&psql)
-        .env("PGPASSWORD", &password)
-        .env("PGSSLMODE", if ssl { "require" } else { "prefer" })
-        .args(["-d", &conn_str, "-t", "-A", "-F", "|", "-c", tables_query])
-        .output()
-        .map_err(|e| format!("Failed to get table info: {}", e))?;
-
-    let tables_str = String::from_utf8_lossy(&tables_output.stdout);
-    let mut tables = Vec::new();
-
-    for line in tables_str.lines() {
-        let parts: Vec<&str> = line.split('|').collect();
-        if parts.len() >= 4 {
-            tables.push(TableInfo {
-                name: parts[0].to_string(),
-                schema: parts[1].to_string(),
-                row_count: parts[2].parse().unwrap_or(0),
-                size: parts[3].parse().unwrap_or(0),
-            });
-        }
-    }
+    let tables = fetch_tables(&client).await?;
 
     // Get total database size
-    let size_output = create_command(&psql)
-        .env("PGPASSWORD", &password)
-        .env("PGSSLMODE", if ssl { "require" } else { "prefer" })
-        .args([
-            "-d",
-            &conn_str,
-            "-t",
-            "-c",
-            &format!("SELECT pg_database_size('{}');", database),
-        ])
-        .output()
-        .map_err(|e| format!("Failed to get database size: {}", e))?;
-
-    let total_size: i64 = String::from_utf8_lossy(&size_output.stdout)
-        .trim()
-        .parse()
-        .unwrap_or(0);
+    let total_size: i64 = client
+        .query_one("SELECT pg_database_size(current_database());", &[])
+        .await
+        .map_err(|e| AppError::query("Failed to get database size", &e))?
+        .get(0);
 
     Ok(DatabaseInfo {
         tables,
@@ -104,14 +135,9 @@ pub async fn test_connection(
     })
 }
 
-#[tauri::command]
-pub async fn test_connection_by_id(id: String) -> Result<DatabaseInfo, String> {
-    let data = load_app_data();
-    let profile = data
-        .profiles
-        .into_iter()
-        .find(|p| p.id == id)
-        .ok_or("Profile not found")?;
+#[cfg_attr(feature = "gui", tauri::command)]
+pub async fn test_connection_by_id(id: String) -> AppResult<DatabaseInfo> {
+    let profile = get_profile_by_id(&id)?;
 
     test_connection(
         profile.host,
@@ -124,25 +150,21 @@ pub async fn test_connection_by_id(id: String) -> Result<DatabaseInfo, String> {
     .await
 }
 
-pub fn get_profile_by_id(id: &str) -> Option<ConnectionProfile> {
-    let data = load_app_data();
-    data.profiles.into_iter().find(|p| p.id == id)
+pub fn get_profile_by_id(id: &str) -> AppResult<ConnectionProfile> {
+    Store::open()?
+        .get_profile(id)?
+        .ok_or_else(|| AppError::ProfileNotFound { id: id.to_string() })
 }
 
-#[tauri::command]
-pub async fn get_database_structure(profile_id: String) -> Result<DatabaseStructure, String> {
-    let profile = get_profile_by_id(&profile_id).ok_or("Profile not found")?;
-    let psql = find_psql().ok_or("psql not found. Please install PostgreSQL client tools.")?;
-
-    let conn_str = format!(
-        "host={} port={} dbname={} user={}",
-        profile.host, profile.port, profile.database, profile.user
-    );
+#[cfg_attr(feature = "gui", tauri::command)]
+pub async fn get_database_structure(profile_id: String) -> AppResult<DatabaseStructure> {
+    let profile = get_profile_by_id(&profile_id)?;
+    let client = connect_profile(&profile).await?;
 
     // Get schemas
     let schemas_query = r#"
         SELECT
-            n.nspname as schema_name,
+            n.nspname::text as schema_name,
             COUNT(c.relname)::integer as table_count
         FROM pg_namespace n
         LEFT JOIN pg_class c ON c.relnamespace = n.oid AND c.relkind = 'r'
@@ -153,66 +175,35 @@ pub async fn get_database_structure(profile_id: String) -> Result<DatabaseStruct
         ORDER BY n.nspname;
     "#;
 
-    let schemas_output = create_command(&psql)
-        .env("PGPASSWORD", &profile.password)
-        .env("PGSSLMODE", if profile.ssl { "require" } else { "prefer" })
-        .args(["-d", &conn_str, "-t", "-A", "-F", "|", "-c", schemas_query])
-        .output()
-        .map_err(|e| format!("Failed to get schemas: {}", e))?;
-
-    if !schemas_output.status.success() {
-        let stderr = String::from_utf8_lossy(&schemas_output.stderr);
-        return Err(format!("Failed to get schemas: {}", stderr));
-    }
-
-    let schemas_str = String::from_utf8_lossy(&schemas_output.stdout);
-    let mut schemas = Vec::new();
-
-    for line in schemas_str.lines() {
-        let parts: Vec<&str> = line.split('|').collect();
-        if parts.len() >= 2 {
-            schemas.push(SchemaInfo {
-                name: parts[0].to_string(),
-                table_count: parts[1].parse().unwrap_or(0),
-            });
-        }
-    }
+    let schemas = client
+        .query(schemas_query, &[])
+        .await
+        .map_err(|e| AppError::query("Failed to get schemas", &e))?
+        .iter()
+        .map(|row| SchemaInfo {
+            name: row.get(0),
+            table_count: row.get(1),
+        })
+        .collect();
 
     // Get tables with schema info
-    let tables_query = r#"
-        SELECT
-            t.table_name,
-            t.table_schema,
-            COALESCE(s.n_live_tup, 0)::bigint as row_count,
-            COALESCE(pg_total_relation_size(quote_ident(t.table_schema) || '.' || quote_ident(t.table_name)), 0)::bigint as size
-        FROM information_schema.tables t
-        LEFT JOIN pg_stat_user_tables s ON t.table_name = s.relname AND t.table_schema = s.schemaname
-        WHERE t.table_schema NOT IN ('pg_catalog', 'information_schema')
-        AND t.table_type = 'BASE TABLE'
-        ORDER BY t.table_schema, t.table_name;
-    "#;
-
-    let tables_output = create_command(&psql)
-        .env("PGPASSWORD", &profile.password)
-        .env("PGSSLMODE", if profile.ssl { "require" } else { "prefer" })
-        .args(["-d", &conn_str, "-t", "-A", "-F", "|", "-c", tables_query])
-        .output()
-        .map_err(|e| format!("Failed to get tables: {}", e))?;
-
-    let tables_str = String::from_utf8_lossy(&tables_output.stdout);
-    let mut tables = Vec::new();
-
-    for line in tables_str.lines() {
-        let parts: Vec<&str> = line.split('|').collect();
-        if parts.len() >= 4 {
-            tables.push(TableInfo {
-                name: parts[0].to_string(),
-                schema: parts[1].to_string(),
-                row_count: parts[2].parse().unwrap_or(0),
-                size: parts[3].parse().unwrap_or(0),
-            });
-        }
-    }
+    let tables = fetch_tables(&client).await?;
 
     Ok(DatabaseStructure { schemas, tables })
 }
+
+/// Databases on the profile's server that accept connections, templates excluded
+#[cfg_attr(feature = "gui", tauri::command)]
+pub async fn list_databases(profile_id: String) -> AppResult<Vec<String>> {
+    let profile = get_profile_by_id(&profile_id)?;
+    let client = connect_profile(&profile).await?;
+
+    let rows = client
+        .query(
+            "SELECT datname::text FROM pg_database WHERE NOT datistemplate AND datallowconn ORDER BY datname",
+            &[],
+        )
+        .await
+        .map_err(|e| AppError::query("Failed to get databases", &e))?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
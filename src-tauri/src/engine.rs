@@ -0,0 +1,16 @@
+mod clone;
+mod schema;
+mod sink;
+
+pub use clone::{
+    prepare_clone, prepare_clone_between, recover_interrupted_clones, run_clone, CloneSink,
+    PreparedClone,
+};
+pub use schema::{execute_schema_download, SchemaSink};
+pub use sink::ProgressSink;
+
+pub use crate::error::{AppError, AppResult};
+pub use crate::types::{
+    CloneHistoryEntry, CloneOptions, CloneProgress, CloneStatus, CloneType, ConnectionProfile,
+    SchemaExportOptions, SchemaProgress,
+};
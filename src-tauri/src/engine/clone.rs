@@ -0,0 +1,1783 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, LockResult, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use futures_util::future::join_all;
+use tokio::sync::Semaphore;
+use tokio_postgres::Client;
+
+use crate::command_helper::create_async_command;
+use crate::connection::{fetch_tables, get_profile_by_id};
+use crate::error::{AppError, AppResult};
+use crate::instance;
+use crate::masking::Masking;
+use crate::ownership;
+use crate::pg_client::{connect_profile, describe_error};
+use crate::pg_tools::{
+    find_pg_dump, find_pg_dumpall, find_pg_restore, find_psql, get_tool_version,
+};
+use crate::schema_rename;
+use crate::selection::{self, is_cleaned, is_data_selected, is_selected, RowFilter};
+use crate::storage::Store;
+use crate::subset::{self, SubsetPlan};
+use crate::table_copy::{copy_table, quote_ident, resolve_table, TableName};
+use crate::types::{
+    CloneEnvironment, CloneHistoryEntry, CloneOptions, CloneProgress, CloneStatus, CloneType,
+    ConnectionProfile, DatabaseCloneResult, DestinationCloneResult, MaskStrategy, TableRowCount,
+    ToolInfo,
+};
+
+use super::ProgressSink;
+
+/// Prefix of the dump and script files a clone writes to the temp dir
+const TEMP_FILE_PREFIX: &str = "pg_clone_";
+/// First key of the advisory locks taken on destination databases ("dbcl")
+const LOCK_CLASS: i32 = 0x6462_636c;
+/// Database of each destination server the locks are taken in
+const LOCK_DATABASE: &str = "postgres";
+/// How long a clone waits for another clone into the same database by default
+const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+/// Pause between attempts to take a lock another clone holds
+const LOCK_RETRY: Duration = Duration::from_secs(2);
+
+/// Get optimal number of parallel jobs based on CPU cores
+fn get_parallel_jobs() -> usize {
+    std::thread::available_parallelism()
+        .map(|p| p.get())
+        .unwrap_or(4)
+        // At least 2 for meaningful parallelism, at most 8 to avoid overwhelming the database
+        .clamp(2, 8)
+}
+
+/// Where a running clone reports its progress and log lines
+pub type CloneSink = dyn ProgressSink<CloneProgress>;
+
+/// A clone whose options have been checked, ready to run
+pub struct PreparedClone {
+    source: ConnectionProfile,
+    destination: ConnectionProfile,
+    /// The destination followed by any fan-out destinations
+    destinations: Vec<ConnectionProfile>,
+    pg_dump: String,
+    psql: String,
+    pg_restore: String,
+    options: CloneOptions,
+    entry: CloneHistoryEntry,
+    store: Option<PathBuf>,
+    lock_timeout: Duration,
+}
+
+impl PreparedClone {
+    /// History entry the clone reports into
+    pub fn entry(&self) -> &CloneHistoryEntry {
+        &self.entry
+    }
+
+    /// Saves the history into the database at `path` instead of the app database
+    pub fn with_store(mut self, path: impl Into<PathBuf>) -> Self {
+        self.store = Some(path.into());
+        self
+    }
+
+    /// Waits at most `timeout` for other clones into the same databases to finish
+    pub fn with_lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    /// Every destination database the clone writes to
+    pub fn destination_databases(&self) -> Vec<(&ConnectionProfile, String)> {
+        self.destinations
+            .iter()
+            .flat_map(|d| {
+                let databases = if self.options.databases.is_empty() {
+                    vec![d.database.clone()]
+                } else {
+                    self.options.databases.clone()
+                };
+                databases.into_iter().map(move |database| (d, database))
+            })
+            .collect()
+    }
+}
+
+fn tool_info(path: &str) -> ToolInfo {
+    ToolInfo {
+        path: path.to_string(),
+        version: get_tool_version(path),
+    }
+}
+
+/// The entry of a running clone and the database it is saved to
+struct History {
+    entry: Mutex<CloneHistoryEntry>,
+    store: Option<PathBuf>,
+}
+
+impl History {
+    fn lock(&self) -> LockResult<MutexGuard<'_, CloneHistoryEntry>> {
+        self.entry.lock()
+    }
+
+    fn open_store(&self) -> AppResult<Store> {
+        match &self.store {
+            Some(path) => Store::open_at(path),
+            None => Store::open(),
+        }
+    }
+
+    /// Saves the entry as it stands, so a crash mid-clone still leaves a record
+    fn save(&self) {
+        let entry = match self.lock() {
+            Ok(entry) => entry.clone(),
+            Err(_) => return,
+        };
+        let _ = self
+            .open_store()
+            .and_then(|store| store.save_history_entry(&entry));
+    }
+}
+
+/// Temp file named after this process, so recovery can tell whose it is
+fn temp_file_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{}{}_{}", TEMP_FILE_PREFIX, instance::id(), name))
+}
+
+/// Marks clones cut off by an app or CLI process that has exited as interrupted and
+/// deletes their temp files. Clones of processes still running are left alone.
+pub fn recover_interrupted_clones() {
+    let _ = Store::open().and_then(|store| store.mark_interrupted_history(instance::is_alive));
+
+    if let Ok(dir) = std::fs::read_dir(std::env::temp_dir()) {
+        for file in dir.flatten() {
+            let name = file.file_name().to_string_lossy().to_string();
+            let Some(rest) = name.strip_prefix(TEMP_FILE_PREFIX) else {
+                continue;
+            };
+            // Files named before owners were recorded never match a running process
+            let owner = rest.split('_').next().unwrap_or_default();
+            if !instance::is_alive(owner) {
+                let _ = std::fs::remove_file(file.path());
+            }
+        }
+    }
+    instance::remove_exited();
+}
+
+/// Identifier of the cluster behind `client`, the same for every address it is
+/// reached at. `None` when the role may not read it.
+async fn system_identifier(client: &Client) -> Option<i64> {
+    client
+        .query_one("SELECT system_identifier FROM pg_control_system()", &[])
+        .await
+        .ok()
+        .map(|row| row.get(0))
+}
+
+/// Server version reported by `SHOW server_version`, if the server is reachable
+async fn server_version(profile: &ConnectionProfile) -> Option<String> {
+    let client = connect_profile(profile).await.ok()?;
+    client
+        .query_one("SHOW server_version", &[])
+        .await
+        .ok()
+        .map(|row| row.get(0))
+}
+
+/// Loads the profiles named in the options and prepares the clone
+pub fn prepare_clone(options: CloneOptions) -> AppResult<PreparedClone> {
+    let source = get_profile_by_id(&options.source_id)?;
+    let destinations = std::iter::once(&options.destination_id)
+        .chain(&options.destination_ids)
+        .map(|id| get_profile_by_id(id))
+        .collect::<AppResult<Vec<_>>>()?;
+
+    prepare_clone_between(source, destinations, options)
+}
+
+/// Checks the options, finds the PostgreSQL tools and creates the history entry.
+/// `destinations` holds the destination followed by any fan-out destinations; the
+/// profile ids in `options` are not looked up again.
+pub fn prepare_clone_between(
+    source: ConnectionProfile,
+    destinations: Vec<ConnectionProfile>,
+    options: CloneOptions,
+) -> AppResult<PreparedClone> {
+    let destination = destinations
+        .first()
+        .cloned()
+        .ok_or_else(|| AppError::InvalidInput("A clone needs a destination".to_string()))?;
+
+    let pg_dump = find_pg_dump().ok_or(AppError::ToolNotFound { tool: "pg_dump" })?;
+    let psql = find_psql().ok_or(AppError::ToolNotFound { tool: "psql" })?;
+    let pg_restore = find_pg_restore().ok_or(AppError::ToolNotFound { tool: "pg_restore" })?;
+
+    if options
+        .row_filters
+        .iter()
+        .any(|f| f.table.trim().is_empty() || f.where_clause.trim().is_empty())
+    {
+        return Err(AppError::InvalidInput(
+            "Each row filter needs a table and a WHERE clause".to_string(),
+        ));
+    }
+    if let Some(subset) = &options.subset {
+        if !options.row_filters.is_empty() {
+            return Err(AppError::InvalidInput(
+                "Use either row filters or a subset, not both".to_string(),
+            ));
+        }
+        if subset.roots.is_empty() || subset.roots.iter().any(|r| r.table.trim().is_empty()) {
+            return Err(AppError::InvalidInput(
+                "A subset needs at least one root table".to_string(),
+            ));
+        }
+        if subset
+            .roots
+            .iter()
+            .any(|r| r.percent.is_some_and(|p| !(p > 0.0 && p <= 100.0)))
+        {
+            return Err(AppError::InvalidInput(
+                "Subset percentages must be between 0 and 100".to_string(),
+            ));
+        }
+    }
+    if options.keep_ownership && find_pg_dumpall().is_none() {
+        return Err(AppError::ToolNotFound { tool: "pg_dumpall" });
+    }
+    if options
+        .role_map
+        .iter()
+        .any(|m| m.from.trim().is_empty() || m.to.trim().is_empty())
+    {
+        return Err(AppError::InvalidInput(
+            "Each role mapping needs a source and a destination role".to_string(),
+        ));
+    }
+    if destinations
+        .iter()
+        .enumerate()
+        .any(|(i, d)| d.id == source.id || destinations[..i].iter().any(|e| e.id == d.id))
+    {
+        return Err(AppError::InvalidInput(
+            "Each destination must be listed once and differ from the source".to_string(),
+        ));
+    }
+    if destinations.len() > 1 && !options.databases.is_empty() {
+        return Err(AppError::InvalidInput(
+            "A server clone goes to a single destination server".to_string(),
+        ));
+    }
+    if options.databases.iter().any(|d| d.trim().is_empty()) {
+        return Err(AppError::InvalidInput(
+            "Database names cannot be empty".to_string(),
+        ));
+    }
+    if !options.databases.is_empty()
+        && source.host == destination.host
+        && source.port == destination.port
+    {
+        return Err(AppError::InvalidInput(
+            "A server clone needs a destination on another server".to_string(),
+        ));
+    }
+    let renames = &options.schema_renames;
+    if renames
+        .iter()
+        .any(|r| r.from.trim().is_empty() || r.to.trim().is_empty() || r.from == r.to)
+    {
+        return Err(AppError::InvalidInput(
+            "Each schema rename needs a source and a different target schema".to_string(),
+        ));
+    }
+    for (i, rename) in renames.iter().enumerate() {
+        if renames[..i]
+            .iter()
+            .any(|r| r.from == rename.from || r.to == rename.to)
+            || renames.iter().any(|r| r.from == rename.to)
+        {
+            return Err(AppError::InvalidInput(format!(
+                "Schema {} is renamed more than once or to another renamed schema",
+                rename.from
+            )));
+        }
+    }
+    if let Some(masking) = &options.masking {
+        if masking
+            .rules
+            .iter()
+            .any(|r| r.table.trim().is_empty() || r.column.trim().is_empty())
+        {
+            return Err(AppError::InvalidInput(
+                "Each masking rule needs a table and a column".to_string(),
+            ));
+        }
+        if masking.rules.iter().any(
+            |r| matches!(&r.strategy, MaskStrategy::Expression { sql } if sql.trim().is_empty()),
+        ) {
+            return Err(AppError::InvalidInput(
+                "Custom masking expressions cannot be empty".to_string(),
+            ));
+        }
+    }
+
+    // Create history entry
+    let mut entry = CloneHistoryEntry::new(&source, &destination, &options);
+    if destinations.len() > 1 {
+        entry.destination_name = destinations
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+    }
+    entry.environment = Some(CloneEnvironment {
+        parallel_jobs: None,
+        pg_dump: tool_info(&pg_dump),
+        pg_restore: tool_info(&pg_restore),
+        psql: tool_info(&psql),
+        source_server_version: None,
+        destination_server_version: None,
+    });
+
+    Ok(PreparedClone {
+        source,
+        destination,
+        destinations,
+        pg_dump,
+        psql,
+        pg_restore,
+        options,
+        entry,
+        store: None,
+        lock_timeout: DEFAULT_LOCK_TIMEOUT,
+    })
+}
+
+/// Takes an advisory lock on every destination database, so clones from other app
+/// instances and CLI runs never write to the same database at once. A lock lives as
+/// long as its session: keep the returned clients until the clone is done. Locks
+/// are taken in the `postgres` database of each destination server, since advisory
+/// locks only reach the sessions of one database. A held lock is retried until the
+/// clone's lock timeout; a server that cannot be locked is logged and cloned anyway.
+async fn lock_destinations(sink: &CloneSink, prepared: &PreparedClone) -> AppResult<Vec<Client>> {
+    let deadline = Instant::now() + prepared.lock_timeout;
+    let mut sessions: Vec<(String, Option<Client>)> = Vec::new();
+    let mut locked_keys = HashSet::new();
+    for (profile, database) in prepared.destination_databases() {
+        let server = format!("{}:{}", profile.host, profile.port);
+        // Profiles can share a database; a second attempt would wait on the first
+        if !locked_keys.insert(format!("{}/{}", server, database)) {
+            continue;
+        }
+        if !sessions.iter().any(|(key, _)| *key == server) {
+            let maintenance = ConnectionProfile {
+                database: LOCK_DATABASE.to_string(),
+                ..profile.clone()
+            };
+            let client = match connect_profile(&maintenance).await {
+                Ok(client) => Some(client),
+                Err(e) => {
+                    sink.log(&format!(
+                        "[WARNING] Could not lock databases on {}, cloning without a lock: {}",
+                        profile.name, e
+                    ));
+                    None
+                }
+            };
+            sessions.push((server.clone(), client));
+        }
+        let Some((_, Some(client))) = sessions.iter().find(|(key, _)| *key == server) else {
+            continue;
+        };
+
+        let mut waiting = false;
+        loop {
+            let locked = client
+                .query_one(
+                    "SELECT pg_try_advisory_lock($1, hashtext($2))",
+                    &[&LOCK_CLASS, &database],
+                )
+                .await
+                .map(|row| row.get::<_, bool>(0));
+            match locked {
+                Ok(true) => break,
+                Ok(false) if Instant::now() >= deadline => {
+                    return Err(AppError::DestinationBusy {
+                        database,
+                        server: profile.name.clone(),
+                    });
+                }
+                Ok(false) => {
+                    if !waiting {
+                        waiting = true;
+                        sink.log(&format!(
+                            "[INFO] Waiting for another clone into {} on {} to finish...",
+                            database, profile.name
+                        ));
+                    }
+                    tokio::time::sleep(
+                        LOCK_RETRY.min(deadline.saturating_duration_since(Instant::now())),
+                    )
+                    .await;
+                }
+                Err(e) => {
+                    sink.log(&format!(
+                        "[WARNING] Could not lock {} on {}, cloning without a lock: {}",
+                        database,
+                        profile.name,
+                        describe_error(&e)
+                    ));
+                    break;
+                }
+            }
+        }
+    }
+    Ok(sessions
+        .into_iter()
+        .filter_map(|(_, client)| client)
+        .collect())
+}
+
+/// Runs a prepared clone to the end and saves it to the history. Returns the
+/// finished entry along with the outcome.
+pub async fn run_clone(
+    sink: Arc<CloneSink>,
+    prepared: PreparedClone,
+) -> (CloneHistoryEntry, AppResult<()>) {
+    let (_locks, locked) = match lock_destinations(sink.as_ref(), &prepared).await {
+        Ok(locks) => (locks, Ok(())),
+        Err(e) => (Vec::new(), Err(e)),
+    };
+    let PreparedClone {
+        source,
+        destination,
+        destinations,
+        pg_dump,
+        psql,
+        pg_restore,
+        options,
+        mut entry,
+        store,
+        lock_timeout: _,
+    } = prepared;
+
+    // The entry is saved when the clone starts, so time spent waiting is not counted
+    entry.started_at = Utc::now();
+    let history = Arc::new(History {
+        entry: Mutex::new(entry),
+        store,
+    });
+    history.save();
+
+    let result = if let Err(e) = locked {
+        sink.log(&format!("[ERROR] {}", e));
+        Err(e)
+    } else if destinations.len() > 1 {
+        execute_fan_out(
+            sink.as_ref(),
+            &pg_dump,
+            &psql,
+            &pg_restore,
+            &source,
+            &destinations,
+            &options,
+            &history,
+        )
+        .await
+    } else if options.databases.is_empty() {
+        execute_clone(
+            sink.as_ref(),
+            &pg_dump,
+            &psql,
+            &pg_restore,
+            &source,
+            &destination,
+            &options,
+            &history,
+        )
+        .await
+    } else {
+        execute_server_clone(
+            sink.as_ref(),
+            &pg_dump,
+            &psql,
+            &pg_restore,
+            &source,
+            &destination,
+            &options,
+            &history,
+        )
+        .await
+    };
+
+    // A stage that panicked while logging still leaves an entry worth saving
+    let mut entry = history
+        .lock()
+        .map(|entry| entry.clone())
+        .unwrap_or_else(|poisoned| poisoned.into_inner().clone());
+
+    match &result {
+        Ok(_) => {
+            entry.complete(CloneStatus::Success, None);
+            sink.progress(CloneProgress::completed("Clone completed successfully!"));
+        }
+        Err(e) => {
+            entry.complete(CloneStatus::Error, Some(e));
+            sink.progress(CloneProgress::error(e));
+        }
+    }
+
+    // Save history
+    let _ = history.open_store().and_then(|store| {
+        store.save_history_entry(&entry)?;
+        store.apply_history_retention()
+    });
+
+    (entry, result)
+}
+
+/// Runs pg_restore on the dump, limited to `sections` when given
+async fn run_pg_restore(
+    pg_restore: &str,
+    destination: &ConnectionProfile,
+    dest_conn_str: &str,
+    dump_path: &Path,
+    parallel_jobs: usize,
+    sections: &[&str],
+    add_log: &(dyn Fn(&str) + Sync),
+) -> AppResult<()> {
+    let mut restore_args = vec![
+        "-d".to_string(),
+        dest_conn_str.to_string(),
+        "-j".to_string(),
+        parallel_jobs.to_string(),
+        // Owners and grants are applied separately, so roles can be mapped
+        "--no-owner".to_string(),
+        "--no-privileges".to_string(),
+        "-v".to_string(),
+    ];
+    restore_args.extend(sections.iter().map(|s| s.to_string()));
+    restore_args.push(dump_path.to_str().unwrap().to_string());
+
+    let restore_process = create_async_command(pg_restore)
+        .env("PGPASSWORD", &destination.password)
+        .env(
+            "PGSSLMODE",
+            if destination.ssl { "require" } else { "prefer" },
+        )
+        .args(&restore_args)
+        .stderr(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| AppError::process("Failed to start restore", e))?;
+
+    let output = restore_process
+        .wait_with_output()
+        .await
+        .map_err(|e| AppError::process("Failed to wait for restore", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.to_lowercase().contains("error") && !stderr.contains("pg_restore: warning") {
+            add_log(&format!("[ERROR] Restore errors: {}", stderr));
+            return Err(AppError::RestoreFailed {
+                stderr: stderr.to_string(),
+            });
+        } else if !stderr.is_empty() {
+            let warning_count = stderr.matches("warning").count();
+            if warning_count > 0 {
+                add_log(&format!(
+                    "[WARNING] Restore completed with {} warnings",
+                    warning_count
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Copies filtered, subsetted or masked rows straight from the source into the destination
+#[allow(clippy::too_many_arguments)]
+async fn copy_partial_data(
+    sink: &CloneSink,
+    source: &ConnectionProfile,
+    destination: &ConnectionProfile,
+    filters: &[RowFilter],
+    subset: Option<&SubsetPlan>,
+    masking: &Masking,
+    disable_triggers: bool,
+    history: &History,
+    add_log: &(dyn Fn(&str) + Sync),
+) -> AppResult<()> {
+    sink.progress(
+        CloneProgress::new("filtering", 80, "Copying partial data...").for_destination(destination),
+    );
+
+    let source_client = connect_profile(source).await?;
+    let destination_client = connect_profile(destination).await?;
+
+    if disable_triggers {
+        destination_client
+            .batch_execute("SET session_replication_role = 'replica'")
+            .await
+            .map_err(|e| AppError::query("Failed to disable triggers", &e))?;
+    }
+
+    let mut copied = Vec::new();
+    let mut done = Vec::new();
+    for filter in filters {
+        let condition = format!("({})", filter.where_clause);
+        let rows = copy_table(
+            &source_client,
+            &destination_client,
+            &filter.table,
+            Some(&condition),
+            masking,
+        )
+        .await?;
+        add_log(&format!(
+            "[SUCCESS] Copied {} filtered rows into {}",
+            rows,
+            filter.table.qualified()
+        ));
+        copied.push(TableRowCount {
+            table: filter.table.qualified(),
+            rows,
+        });
+        done.push(filter.table.clone());
+    }
+    if let Some(plan) = subset {
+        copied.extend(
+            subset::copy(plan, &source_client, &destination_client, masking, add_log).await?,
+        );
+        done.extend(plan.tables.iter().cloned());
+    }
+    // Masked tables with no filter are copied in full
+    for table in masking.tables().filter(|t| !done.contains(t)) {
+        let rows = copy_table(&source_client, &destination_client, table, None, masking).await?;
+        add_log(&format!(
+            "[SUCCESS] Copied {} masked rows into {}",
+            rows,
+            table.qualified()
+        ));
+        copied.push(TableRowCount {
+            table: table.qualified(),
+            rows,
+        });
+    }
+
+    if let Ok(mut entry) = history.lock() {
+        entry.copied_rows = Some(copied);
+    }
+    Ok(())
+}
+
+/// Creates `database` on the server `client` is connected to unless it exists
+async fn create_database_if_missing(
+    client: &Client,
+    database: &str,
+    add_log: &(dyn Fn(&str) + Sync),
+) -> AppResult<()> {
+    let exists = client
+        .query_opt("SELECT 1 FROM pg_database WHERE datname = $1", &[&database])
+        .await
+        .map_err(|e| AppError::query("Failed to check databases", &e))?
+        .is_some();
+    if !exists {
+        client
+            .batch_execute(&format!("CREATE DATABASE {}", quote_ident(database)))
+            .await
+            .map_err(|e| AppError::query("Failed to create database", &e))?;
+        add_log(&format!("[SUCCESS] Created database {}", database));
+    }
+    Ok(())
+}
+
+/// Clones each selected database into its namesake on the destination server. One
+/// failure does not stop the rest; every database gets a result on the history entry.
+#[allow(clippy::too_many_arguments)]
+async fn execute_server_clone(
+    sink: &CloneSink,
+    pg_dump: &str,
+    psql: &str,
+    pg_restore: &str,
+    source: &ConnectionProfile,
+    destination: &ConnectionProfile,
+    options: &CloneOptions,
+    history: &Arc<History>,
+) -> AppResult<()> {
+    let add_log = |msg: &str| {
+        sink.log(msg);
+        if let Ok(mut entry) = history.lock() {
+            entry.add_log(msg.to_string());
+        }
+    };
+
+    // The destination profile's own database is where new databases are created from
+    let admin = connect_profile(destination).await?;
+
+    // Host names can differ for one server, e.g. localhost and 127.0.0.1
+    let source_client = connect_profile(source).await?;
+    if let (Some(source_id), Some(destination_id)) = (
+        system_identifier(&source_client).await,
+        system_identifier(&admin).await,
+    ) {
+        if source_id == destination_id {
+            return Err(AppError::InvalidInput(
+                "A server clone needs a destination on another server".to_string(),
+            ));
+        }
+    }
+    drop(source_client);
+
+    let total = options.databases.len();
+    let mut failed = 0;
+    let mut verified = 0;
+
+    for (i, database) in options.databases.iter().enumerate() {
+        add_log(&format!(
+            "[INFO] Database {} ({} of {})",
+            database,
+            i + 1,
+            total
+        ));
+        let started = Instant::now();
+        let source_db = ConnectionProfile {
+            database: database.clone(),
+            ..source.clone()
+        };
+        let destination_db = ConnectionProfile {
+            database: database.clone(),
+            ..destination.clone()
+        };
+
+        let result = async {
+            create_database_if_missing(&admin, database, &add_log).await?;
+            execute_clone(
+                sink,
+                pg_dump,
+                psql,
+                pg_restore,
+                &source_db,
+                &destination_db,
+                options,
+                history,
+            )
+            .await
+        }
+        .await;
+        if let Err(e) = &result {
+            failed += 1;
+            add_log(&format!(
+                "[ERROR] Clone of database {} failed: {}",
+                database, e
+            ));
+        }
+
+        if let Ok(mut entry) = history.lock() {
+            let verified_tables = entry.verified_tables.take();
+            verified += verified_tables.unwrap_or(0);
+            entry
+                .database_results
+                .get_or_insert_with(Vec::new)
+                .push(DatabaseCloneResult {
+                    database: database.clone(),
+                    status: if result.is_ok() {
+                        CloneStatus::Success
+                    } else {
+                        CloneStatus::Error
+                    },
+                    duration: started.elapsed().as_secs() as i64,
+                    error_message: result.as_ref().err().map(|e| e.to_string()),
+                    error_code: result.as_ref().err().map(|e| e.code().to_string()),
+                    verified_tables,
+                });
+        }
+        history.save();
+    }
+
+    if let Ok(mut entry) = history.lock() {
+        entry.verified_tables = Some(verified);
+    }
+    if failed > 0 {
+        return Err(AppError::PartialFailure {
+            failed,
+            total,
+            items: "databases",
+        });
+    }
+    add_log(&format!("[SUCCESS] Cloned {} databases", total));
+    Ok(())
+}
+
+/// Names the destination in a log line of a fan-out clone, after its level tag
+fn destination_log(destination: &ConnectionProfile, msg: &str) -> String {
+    match msg.split_once("] ") {
+        Some((level, rest)) if level.starts_with('[') => {
+            format!("{}] {}: {}", level, destination.name, rest)
+        }
+        _ => format!("{}: {}", destination.name, msg),
+    }
+}
+
+/// Runs `restore` for every destination, at most `concurrency` at a time. The
+/// restores share the calling task and await their pg_restore and psql processes,
+/// so they overlap without a thread each. Results are in destination order.
+async fn for_each_destination<'a, T, F, Fut>(
+    destinations: &'a [ConnectionProfile],
+    concurrency: usize,
+    restore: F,
+) -> Vec<T>
+where
+    F: Fn(&'a ConnectionProfile) -> Fut,
+    Fut: Future<Output = T>,
+{
+    let limit = Semaphore::new(concurrency.max(1));
+    join_all(destinations.iter().map(|destination| {
+        let restore = &restore;
+        let limit = &limit;
+        async move {
+            let _permit = limit.acquire().await;
+            restore(destination).await
+        }
+    }))
+    .await
+}
+
+/// Dumps the source once and restores the dump into every destination, at most
+/// `restore_concurrency` at a time. Each destination gets a result on the history entry.
+#[allow(clippy::too_many_arguments)]
+async fn execute_fan_out(
+    sink: &CloneSink,
+    pg_dump: &str,
+    psql: &str,
+    pg_restore: &str,
+    source: &ConnectionProfile,
+    destinations: &[ConnectionProfile],
+    options: &CloneOptions,
+    history: &Arc<History>,
+) -> AppResult<()> {
+    let add_log = |msg: &str| {
+        sink.log(msg);
+        if let Ok(mut entry) = history.lock() {
+            entry.add_log(msg.to_string());
+        }
+    };
+
+    sink.progress(CloneProgress::new(
+        "preparing",
+        5,
+        "Preparing clone operation...",
+    ));
+    add_log(&format!(
+        "[INFO] Starting clone from '{}' to {} destinations",
+        source.name,
+        destinations.len()
+    ));
+    add_log(&format!("[INFO] Clone type: {:?}", options.clone_type));
+    add_log(&format!(
+        "[INFO] Restoring into {} destinations at a time",
+        options.restore_concurrency.max(1)
+    ));
+
+    let source_version = server_version(source).await;
+    add_log(&format!(
+        "[INFO] Source server version: {}",
+        source_version.as_deref().unwrap_or("unknown")
+    ));
+    if let Ok(mut entry) = history.lock() {
+        if let Some(environment) = entry.environment.as_mut() {
+            environment.source_server_version = source_version;
+        }
+    }
+
+    let dump = dump_source(sink, pg_dump, source, options, history, &add_log).await?;
+
+    let results = for_each_destination(
+        destinations,
+        options.restore_concurrency,
+        |destination| async {
+            let add_log = |msg: &str| {
+                let msg = destination_log(destination, msg);
+                sink.log(&msg);
+                if let Ok(mut entry) = history.lock() {
+                    entry.add_log(msg);
+                }
+            };
+
+            let started = Instant::now();
+            let version = server_version(destination).await;
+            add_log(&format!(
+                "[INFO] Server version: {}",
+                version.as_deref().unwrap_or("unknown")
+            ));
+            let result = async {
+                prepare_destination(sink, pg_dump, destination, options, history, &add_log).await?;
+                restore_dump(
+                    sink,
+                    psql,
+                    pg_restore,
+                    source,
+                    destination,
+                    options,
+                    &dump,
+                    history,
+                    &add_log,
+                )
+                .await
+            }
+            .await;
+
+            match &result {
+                Ok(_) => sink.progress(
+                    CloneProgress::completed("Clone completed").for_destination(destination),
+                ),
+                Err(e) => {
+                    add_log(&format!("[ERROR] Clone failed: {}", e));
+                    sink.progress(CloneProgress::error(e).for_destination(destination));
+                }
+            }
+            if let Ok(mut entry) = history.lock() {
+                entry.destination_results.get_or_insert_with(Vec::new).push(
+                    DestinationCloneResult {
+                        destination_id: destination.id.clone(),
+                        destination_name: destination.name.clone(),
+                        status: if result.is_ok() {
+                            CloneStatus::Success
+                        } else {
+                            CloneStatus::Error
+                        },
+                        duration: started.elapsed().as_secs() as i64,
+                        error_message: result.as_ref().err().map(|e| e.to_string()),
+                        error_code: result.as_ref().err().map(|e| e.code().to_string()),
+                        verified_tables: result.as_ref().ok().copied(),
+                        server_version: version,
+                    },
+                );
+            }
+            history.save();
+            result.ok()
+        },
+    )
+    .await;
+
+    // Every destination version, in the environment shown for the whole run
+    if let Ok(mut entry) = history.lock() {
+        let mut versions: Vec<String> = entry
+            .destination_results
+            .iter()
+            .flatten()
+            .filter_map(|r| r.server_version.clone())
+            .collect();
+        versions.sort();
+        versions.dedup();
+        if let Some(environment) = entry.environment.as_mut() {
+            environment.destination_server_version =
+                Some(versions.join(", ")).filter(|v| !v.is_empty());
+        }
+    }
+
+    let total = results.len();
+    let verified = results.iter().flatten().sum();
+    let failed = results.iter().filter(|r| r.is_none()).count();
+    let _ = std::fs::remove_file(&dump.path);
+
+    if let Ok(mut entry) = history.lock() {
+        entry.verified_tables = Some(verified);
+    }
+    if failed > 0 {
+        return Err(AppError::PartialFailure {
+            failed,
+            total,
+            items: "destinations",
+        });
+    }
+    add_log(&format!("[SUCCESS] Restored into {} destinations", total));
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn execute_clone(
+    sink: &CloneSink,
+    pg_dump: &str,
+    psql: &str,
+    pg_restore: &str,
+    source: &ConnectionProfile,
+    destination: &ConnectionProfile,
+    options: &CloneOptions,
+    history: &Arc<History>,
+) -> AppResult<()> {
+    let add_log = |msg: &str| {
+        sink.log(msg);
+        if let Ok(mut entry) = history.lock() {
+            entry.add_log(msg.to_string());
+        }
+    };
+
+    // Stage 1: Preparing
+    sink.progress(CloneProgress::new(
+        "preparing",
+        5,
+        "Preparing clone operation...",
+    ));
+    add_log(&format!(
+        "[INFO] Starting clone from '{}' to '{}'",
+        source.name, destination.name
+    ));
+    add_log(&format!("[INFO] Clone type: {:?}", options.clone_type));
+
+    let source_version = server_version(source).await;
+    let destination_version = server_version(destination).await;
+    add_log(&format!(
+        "[INFO] Server versions: source {}, destination {}",
+        source_version.as_deref().unwrap_or("unknown"),
+        destination_version.as_deref().unwrap_or("unknown")
+    ));
+    if let Ok(mut entry) = history.lock() {
+        if let Some(environment) = entry.environment.as_mut() {
+            environment.source_server_version = source_version;
+            environment.destination_server_version = destination_version;
+        }
+    }
+
+    prepare_destination(sink, pg_dump, destination, options, history, &add_log).await?;
+    let dump = dump_source(sink, pg_dump, source, options, history, &add_log).await?;
+    let restored = restore_dump(
+        sink,
+        psql,
+        pg_restore,
+        source,
+        destination,
+        options,
+        &dump,
+        history,
+        &add_log,
+    )
+    .await;
+
+    // Clean up temp file
+    let _ = std::fs::remove_file(&dump.path);
+
+    let table_count = restored?;
+    if let Ok(mut entry) = history.lock() {
+        entry.verified_tables = Some(table_count);
+    }
+    Ok(())
+}
+
+/// Backs up and cleans the destination ahead of the restore, as the options ask
+async fn prepare_destination(
+    sink: &CloneSink,
+    pg_dump: &str,
+    destination: &ConnectionProfile,
+    options: &CloneOptions,
+    history: &History,
+    add_log: &(dyn Fn(&str) + Sync),
+) -> AppResult<()> {
+    // Stage 2: Backup (if enabled)
+    if options.create_backup {
+        sink.progress(
+            CloneProgress::new("backup", 15, "Creating backup of destination...")
+                .for_destination(destination),
+        );
+        add_log("[INFO] Creating backup of destination database...");
+
+        // Create backup filename
+        let backup_name = format!(
+            "{}_backup_{}.sql",
+            destination.database,
+            chrono::Utc::now().format("%Y%m%d_%H%M%S")
+        );
+
+        let backup_path = dirs::data_local_dir()
+            .map(|d| d.join("db-clone-app").join("backups").join(&backup_name))
+            .ok_or_else(|| {
+                AppError::Internal("Could not determine backup directory".to_string())
+            })?;
+
+        if let Some(parent) = backup_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| AppError::process("Failed to create backup directory", e))?;
+        }
+
+        let conn_str = format!(
+            "host={} port={} dbname={} user={}",
+            destination.host, destination.port, destination.database, destination.user
+        );
+
+        let backup_output = create_async_command(pg_dump)
+            .env("PGPASSWORD", &destination.password)
+            .env(
+                "PGSSLMODE",
+                if destination.ssl { "require" } else { "prefer" },
+            )
+            .args(["-d", &conn_str, "-f", backup_path.to_str().unwrap()])
+            .output()
+            .await
+            .map_err(|e| AppError::process("Failed to create backup", e))?;
+
+        if !backup_output.status.success() {
+            let stderr = String::from_utf8_lossy(&backup_output.stderr);
+            add_log(&format!("[WARNING] Backup warning: {}", stderr));
+        } else {
+            add_log(&format!(
+                "[SUCCESS] Backup created: {}",
+                backup_path.display()
+            ));
+        }
+        history.save();
+    }
+
+    // Stage 3: Clean destination (if enabled)
+    if options.clean_destination {
+        let client = connect_profile(destination).await?;
+
+        // Only tables the clone will bring back are removed; renamed schemas are
+        // matched by their source name
+        let tables: Vec<String> = fetch_tables(&client)
+            .await?
+            .into_iter()
+            .filter(|t| {
+                schema_rename::source_schema(&options.schema_renames, &t.schema)
+                    .is_some_and(|schema| is_cleaned(options, &schema, &t.name))
+            })
+            .map(|t| {
+                TableName {
+                    schema: t.schema,
+                    name: t.name,
+                }
+                .qualified()
+            })
+            .collect();
+
+        // For data-only mode, use TRUNCATE to preserve table structure
+        // For structure/both modes, use DROP to remove everything
+        let is_data_only = matches!(options.clone_type, CloneType::Data);
+
+        if tables.is_empty() {
+            add_log("[INFO] No destination tables to clean");
+        } else if is_data_only {
+            sink.progress(
+                CloneProgress::new("cleaning", 25, "Truncating destination tables...")
+                    .for_destination(destination),
+            );
+            add_log(&format!(
+                "[INFO] Truncating {} destination tables (preserving structure)...",
+                tables.len()
+            ));
+
+            // Triggers are disabled for a faster truncate; TRUNCATE also resets sequences
+            let truncate_query = format!(
+                "SET session_replication_role = 'replica'; TRUNCATE TABLE {} CASCADE; SET session_replication_role = 'origin';",
+                tables.join(", ")
+            );
+
+            match client.batch_execute(&truncate_query).await {
+                Ok(_) => add_log("[SUCCESS] Destination tables truncated"),
+                Err(e) => add_log(&format!(
+                    "[WARNING] Truncate warning: {}",
+                    describe_error(&e)
+                )),
+            }
+        } else {
+            sink.progress(
+                CloneProgress::new("cleaning", 25, "Cleaning destination database...")
+                    .for_destination(destination),
+            );
+            add_log(&format!(
+                "[INFO] Dropping {} destination tables...",
+                tables.len()
+            ));
+
+            let drop_query = format!("DROP TABLE IF EXISTS {} CASCADE;", tables.join(", "));
+
+            match client.batch_execute(&drop_query).await {
+                Ok(_) => add_log("[SUCCESS] Destination database cleaned"),
+                Err(e) => add_log(&format!("[WARNING] Clean warning: {}", describe_error(&e))),
+            }
+        }
+        history.save();
+    }
+    Ok(())
+}
+
+/// A dump of the source and the rows to copy into each destination after restoring it
+struct SourceDump {
+    path: PathBuf,
+    use_custom_format: bool,
+    parallel_jobs: usize,
+    row_filters: Vec<RowFilter>,
+    subset_plan: Option<SubsetPlan>,
+    masking: Masking,
+}
+
+impl SourceDump {
+    fn has_partial_data(&self) -> bool {
+        !self.row_filters.is_empty()
+            || self.subset_plan.is_some()
+            || self.masking.tables().next().is_some()
+    }
+}
+
+/// Dumps the source once, leaving out the rows copied separately after the restore
+async fn dump_source(
+    sink: &CloneSink,
+    pg_dump: &str,
+    source: &ConnectionProfile,
+    options: &CloneOptions,
+    history: &History,
+    add_log: &(dyn Fn(&str) + Sync),
+) -> AppResult<SourceDump> {
+    // Stage 4: Dump source
+    let row_filters = if matches!(options.clone_type, CloneType::Structure) {
+        if !options.row_filters.is_empty() {
+            add_log("[INFO] Row filters are ignored for schema-only clones");
+        }
+        Vec::new()
+    } else if options.row_filters.is_empty() {
+        Vec::new()
+    } else {
+        // Resolved once, so the dump, the selection and the copy agree on each table
+        let client = connect_profile(source).await?;
+        let mut filters = Vec::new();
+        for filter in &options.row_filters {
+            filters.push(RowFilter {
+                table: resolve_table(&client, &filter.table).await?,
+                where_clause: filter.where_clause.clone(),
+            });
+        }
+        selection::row_filters(options, filters)?
+    };
+
+    let subset_plan = match &options.subset {
+        Some(subset) if !matches!(options.clone_type, CloneType::Structure) => {
+            let client = connect_profile(source).await?;
+            let mut plan = subset::plan(&client, subset).await?;
+            plan.exclude_data(|t| !is_data_selected(options, &t.schema, &t.name));
+            let names: Vec<String> = plan.tables.iter().map(|t| t.qualified()).collect();
+            add_log(&format!(
+                "[INFO] Subset covers {} tables: {}",
+                names.len(),
+                names.join(", ")
+            ));
+            let unreached: Vec<String> = plan.unreached_tables().map(|t| t.qualified()).collect();
+            if !unreached.is_empty() {
+                add_log(&format!(
+                    "[WARNING] No rows will be copied into {}: they only reference rows of the subset. Add them as subset roots to copy some.",
+                    unreached.join(", ")
+                ));
+            }
+            Some(plan)
+        }
+        _ => None,
+    };
+
+    // Rules are resolved up front so a typo fails the clone before anything is dumped
+    let masking = match &options.masking {
+        Some(masking)
+            if !matches!(options.clone_type, CloneType::Structure) && !masking.rules.is_empty() =>
+        {
+            let client = connect_profile(source).await?;
+            let mut masking = Masking::resolve(&client, masking).await?;
+            masking.exclude_data(|t| !is_data_selected(options, &t.schema, &t.name));
+            add_log(&format!(
+                "[INFO] Masking columns in {} tables",
+                masking.tables().count()
+            ));
+            masking
+        }
+        _ => Masking::default(),
+    };
+
+    let parallel_jobs = get_parallel_jobs();
+    let dump_start = Instant::now();
+    sink.progress(CloneProgress::new(
+        "dumping",
+        40,
+        "Dumping source database...",
+    ));
+
+    let source_conn_str = format!(
+        "host={} port={} dbname={} user={}",
+        source.host, source.port, source.database, source.user
+    );
+
+    // For data-only mode, we use plain SQL format since pg_restore with --data-only
+    // requires tables to exist. For structure and both, we use custom format for parallel restore.
+    let use_custom_format = !matches!(options.clone_type, CloneType::Data);
+
+    if use_custom_format {
+        add_log("[INFO] Using custom format with parallel restore...");
+        add_log(&format!(
+            "[INFO] Will use {} parallel jobs for restore",
+            parallel_jobs
+        ));
+        if let Ok(mut entry) = history.lock() {
+            if let Some(environment) = entry.environment.as_mut() {
+                environment.parallel_jobs = Some(parallel_jobs);
+            }
+        }
+    } else {
+        add_log("[INFO] Using plain SQL format for data-only clone...");
+    }
+
+    let mut dump_args = vec!["-d".to_string(), source_conn_str];
+
+    if use_custom_format {
+        // Custom format for parallel restore
+        dump_args.push("-Fc".to_string());
+        dump_args.push("-Z".to_string());
+        dump_args.push("1".to_string()); // Light compression (faster for remote)
+    } else {
+        // Plain format for data-only (will be piped directly)
+        dump_args.push("-Fp".to_string()); // Plain format
+    }
+
+    // Add clone type options
+    match options.clone_type {
+        CloneType::Structure => {
+            dump_args.push("--schema-only".to_string());
+            add_log("[INFO] Dumping schema only");
+        }
+        CloneType::Data => {
+            dump_args.push("--data-only".to_string());
+            dump_args.push("--disable-triggers".to_string()); // Faster data restore
+            add_log("[INFO] Dumping data only");
+        }
+        CloneType::Both => {
+            add_log("[INFO] Dumping schema and data");
+        }
+    }
+
+    // Add schema and table selection
+    for schema in &options.include_schemas {
+        add_log(&format!("[INFO] Including schema: {}", schema));
+    }
+    for schema in &options.exclude_schemas {
+        add_log(&format!("[INFO] Excluding schema: {}", schema));
+    }
+    for table in &options.include_tables {
+        add_log(&format!("[INFO] Including table: {}", table));
+    }
+    for table in &options.exclude_tables {
+        add_log(&format!("[INFO] Excluding table: {}", table));
+    }
+    for table in &options.exclude_table_data {
+        add_log(&format!("[INFO] Excluding data of table: {}", table));
+    }
+    dump_args.extend(selection::dump_args(options));
+
+    // Filtered tables are dumped without data; their rows are copied after the restore
+    for filter in &row_filters {
+        dump_args.push("--exclude-table-data".to_string());
+        dump_args.push(filter.table.qualified());
+        add_log(&format!(
+            "[INFO] Filtering rows of {}: WHERE {}",
+            filter.table.qualified(),
+            filter.where_clause
+        ));
+    }
+    for table in subset_plan
+        .iter()
+        .flat_map(|plan| &plan.tables)
+        .chain(masking.tables())
+    {
+        dump_args.push("--exclude-table-data".to_string());
+        dump_args.push(table.qualified());
+    }
+
+    // Create temp file for dump
+    let dump_ext = if use_custom_format { "dump" } else { "sql" };
+    let dump_path = temp_file_path(&format!("{}.{}", uuid::Uuid::new_v4(), dump_ext));
+    dump_args.push("-f".to_string());
+    dump_args.push(dump_path.to_str().unwrap().to_string());
+
+    let dump_output = create_async_command(pg_dump)
+        .env("PGPASSWORD", &source.password)
+        .env("PGSSLMODE", if source.ssl { "require" } else { "prefer" })
+        .args(&dump_args)
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .map_err(|e| AppError::process("Failed to dump source", e))?;
+
+    if !dump_output.status.success() {
+        let stderr = String::from_utf8_lossy(&dump_output.stderr);
+        add_log(&format!("[ERROR] Dump failed: {}", stderr));
+        return Err(AppError::DumpFailed {
+            stderr: stderr.to_string(),
+        });
+    }
+
+    let dump_duration = dump_start.elapsed();
+    add_log(&format!(
+        "[SUCCESS] Source database dumped in {:.1}s",
+        dump_duration.as_secs_f64()
+    ));
+
+    // Get dump file size
+    if let Ok(metadata) = std::fs::metadata(&dump_path) {
+        let size_mb = metadata.len() as f64 / 1024.0 / 1024.0;
+        add_log(&format!("[INFO] Dump file size: {:.2} MB", size_mb));
+    }
+    history.save();
+
+    Ok(SourceDump {
+        path: dump_path,
+        use_custom_format,
+        parallel_jobs,
+        row_filters,
+        subset_plan,
+        masking,
+    })
+}
+
+/// Restores `dump` into the destination and verifies it. Returns the number of
+/// tables found afterwards.
+#[allow(clippy::too_many_arguments)]
+async fn restore_dump(
+    sink: &CloneSink,
+    psql: &str,
+    pg_restore: &str,
+    source: &ConnectionProfile,
+    destination: &ConnectionProfile,
+    options: &CloneOptions,
+    dump: &SourceDump,
+    history: &History,
+    add_log: &(dyn Fn(&str) + Sync),
+) -> AppResult<i64> {
+    // Stage 5: Restore to destination
+    let restore_start = Instant::now();
+    let (use_custom_format, parallel_jobs, dump_path) =
+        (dump.use_custom_format, dump.parallel_jobs, &dump.path);
+    let (row_filters, subset_plan, masking) = (&dump.row_filters, &dump.subset_plan, &dump.masking);
+    let has_partial_data = dump.has_partial_data();
+
+    let dest_conn_str = format!(
+        "host={} port={} dbname={} user={}",
+        destination.host, destination.port, destination.database, destination.user
+    );
+
+    // Roles must exist before anything can be given to them
+    let keep_ownership = options.keep_ownership && use_custom_format;
+    if keep_ownership {
+        let pg_dumpall = find_pg_dumpall().ok_or(AppError::ToolNotFound { tool: "pg_dumpall" })?;
+        let source_client = connect_profile(source).await?;
+        let destination_client = connect_profile(destination).await?;
+        ownership::create_missing_roles(
+            &pg_dumpall,
+            source,
+            &source_client,
+            &destination_client,
+            &options.role_map,
+            add_log,
+        )
+        .await?;
+    } else if options.keep_ownership {
+        add_log("[INFO] Ownership is kept as it is for data-only clones");
+    }
+
+    // Renamed schemas go by their source names until everything is restored
+    let renames = &options.schema_renames;
+    if !renames.is_empty() {
+        let client = connect_profile(destination).await?;
+        schema_rename::swap_in(
+            &client,
+            renames,
+            &options.clone_type,
+            options.clean_destination,
+        )
+        .await?;
+        for rename in renames {
+            add_log(&format!(
+                "[INFO] Restoring schema {} as {}",
+                rename.from, rename.to
+            ));
+        }
+    }
+
+    let restored: AppResult<()> = async {
+        if use_custom_format {
+            // Use pg_restore with parallel jobs for custom format
+            sink.progress(
+                CloneProgress::new(
+                    "restoring",
+                    70,
+                    &format!("Restoring with {} parallel jobs...", parallel_jobs),
+                )
+                .for_destination(destination),
+            );
+            add_log(&format!(
+                "[INFO] Restoring with pg_restore ({} parallel jobs)...",
+                parallel_jobs
+            ));
+
+            if !has_partial_data {
+                run_pg_restore(
+                    pg_restore,
+                    destination,
+                    &dest_conn_str,
+                    dump_path,
+                    parallel_jobs,
+                    &[],
+                    add_log,
+                )
+                .await?;
+            } else {
+                // Partial rows go in after the data section, before constraints and indexes exist
+                run_pg_restore(
+                    pg_restore,
+                    destination,
+                    &dest_conn_str,
+                    dump_path,
+                    parallel_jobs,
+                    &["--section=pre-data", "--section=data"],
+                    add_log,
+                )
+                .await?;
+                copy_partial_data(
+                    sink,
+                    source,
+                    destination,
+                    row_filters,
+                    subset_plan.as_ref(),
+                    masking,
+                    false,
+                    history,
+                    add_log,
+                )
+                .await?;
+                run_pg_restore(
+                    pg_restore,
+                    destination,
+                    &dest_conn_str,
+                    dump_path,
+                    parallel_jobs,
+                    &["--section=post-data"],
+                    add_log,
+                )
+                .await?;
+            }
+
+            if keep_ownership {
+                let client = connect_profile(destination).await?;
+                ownership::apply(pg_restore, dump_path, &client, &options.role_map, add_log)
+                    .await?;
+            }
+        } else {
+            // Use psql for plain SQL format (data-only)
+            sink.progress(
+                CloneProgress::new("restoring", 70, "Restoring data...")
+                    .for_destination(destination),
+            );
+            add_log("[INFO] Restoring with psql (optimized settings)...");
+
+            // Create optimized restore script with performance settings
+            let optimized_path = temp_file_path(&format!("optimized_{}.sql", uuid::Uuid::new_v4()));
+
+            // Performance settings to prepend
+            let perf_settings = r#"-- Performance optimizations for faster restore
+SET synchronous_commit = off;
+SET work_mem = '256MB';
+SET maintenance_work_mem = '512MB';
+SET max_parallel_workers_per_gather = 0;
+SET session_replication_role = 'replica';
+
+"#;
+
+            // Read dump content and prepend settings
+            let dump_content = std::fs::read_to_string(dump_path)
+                .map_err(|e| AppError::process("Failed to read dump file", e))?;
+
+            // Add reset at the end
+            let reset_settings = r#"
+
+-- Reset settings
+SET session_replication_role = 'origin';
+SET synchronous_commit = on;
+"#;
+
+            let optimized_content = format!("{}{}{}", perf_settings, dump_content, reset_settings);
+            std::fs::write(&optimized_path, optimized_content)
+                .map_err(|e| AppError::process("Failed to write optimized script", e))?;
+
+            let restore_process = create_async_command(psql)
+                .env("PGPASSWORD", &destination.password)
+                .env(
+                    "PGSSLMODE",
+                    if destination.ssl { "require" } else { "prefer" },
+                )
+                .args(["-d", &dest_conn_str, "-f", optimized_path.to_str().unwrap()])
+                .stderr(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .map_err(|e| AppError::process("Failed to start restore", e))?;
+
+            let output = restore_process
+                .wait_with_output()
+                .await
+                .map_err(|e| AppError::process("Failed to wait for restore", e))?;
+
+            // Clean up optimized file
+            let _ = std::fs::remove_file(&optimized_path);
+
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                if stderr.contains("ERROR") {
+                    add_log(&format!("[ERROR] Restore errors: {}", stderr));
+                    return Err(AppError::RestoreFailed {
+                        stderr: stderr.to_string(),
+                    });
+                } else if !stderr.is_empty() {
+                    add_log(&format!("[WARNING] Restore warnings: {}", stderr));
+                }
+            }
+        }
+
+        // Data-only clones restore into existing tables, so triggers and foreign keys are live
+        if !use_custom_format && has_partial_data {
+            copy_partial_data(
+                sink,
+                source,
+                destination,
+                row_filters,
+                subset_plan.as_ref(),
+                masking,
+                true,
+                history,
+                add_log,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+    .await;
+
+    // Names are put back even when the restore failed
+    let swapped: AppResult<()> = async {
+        if !renames.is_empty() {
+            let client = connect_profile(destination).await?;
+            schema_rename::swap_out(&client, renames, add_log).await?;
+        }
+        Ok(())
+    }
+    .await;
+    restored?;
+    swapped?;
+
+    let restore_duration = restore_start.elapsed();
+    add_log(&format!(
+        "[SUCCESS] Database restored in {:.1}s",
+        restore_duration.as_secs_f64()
+    ));
+    history.save();
+
+    // Stage 6: Verify
+    sink.progress(
+        CloneProgress::new("verifying", 90, "Verifying clone...").for_destination(destination),
+    );
+    add_log("[INFO] Verifying clone...");
+
+    // Quick verification - count tables in the schemas the clone brought over,
+    // under their destination names
+    let source_client = connect_profile(source).await?;
+    let mut schemas: Vec<String> = fetch_tables(&source_client)
+        .await?
+        .into_iter()
+        .filter(|t| is_selected(options, &t.schema, &t.name))
+        .map(|t| schema_rename::destination_schema(&options.schema_renames, &t.schema))
+        .collect();
+    schemas.sort();
+    schemas.dedup();
+
+    let verify_query = "SELECT COUNT(*) FROM information_schema.tables WHERE table_schema::text = ANY($1) AND table_type = 'BASE TABLE';";
+
+    let verify_client = connect_profile(destination).await?;
+
+    let table_count: i64 = verify_client
+        .query_one(verify_query, &[&schemas])
+        .await
+        .map(|row| row.get(0))
+        .unwrap_or(0);
+
+    add_log(&format!(
+        "[SUCCESS] Verification complete. Tables in destination: {}",
+        table_count
+    ));
+
+    Ok(table_count)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn profile(name: &str) -> ConnectionProfile {
+        ConnectionProfile::new(
+            name.to_string(),
+            "localhost".to_string(),
+            5432,
+            name.to_string(),
+            "postgres".to_string(),
+            String::new(),
+            false,
+            None,
+        )
+    }
+
+    /// A pg_restore stand-in that takes a second and succeeds
+    fn slow_pg_restore() -> PathBuf {
+        let path = temp_file_path(&format!("pg_restore_{}.sh", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "#!/bin/sh\nsleep 1\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    async fn restore_all(pg_restore: &str, destinations: &[ConnectionProfile], limit: usize) {
+        let results = for_each_destination(destinations, limit, |destination| async move {
+            run_pg_restore(
+                pg_restore,
+                destination,
+                &destination.database,
+                Path::new("dump"),
+                1,
+                &[],
+                &|_| {},
+            )
+            .await
+        })
+        .await;
+        assert!(results.iter().all(Result::is_ok));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn restores_destinations_at_the_same_time() {
+        let pg_restore = slow_pg_restore();
+        let pg_restore = pg_restore.to_str().unwrap();
+        let destinations = [profile("qa1"), profile("qa2")];
+
+        let started = Instant::now();
+        restore_all(pg_restore, &destinations, 2).await;
+        let together = started.elapsed();
+
+        let started = Instant::now();
+        restore_all(pg_restore, &destinations, 1).await;
+        let one_at_a_time = started.elapsed();
+
+        let _ = std::fs::remove_file(pg_restore);
+        // Both restores overlap on a single thread, unless the limit keeps them apart
+        assert!(together.as_secs_f64() < 1.8, "took {:?}", together);
+        assert!(
+            one_at_a_time.as_secs_f64() >= 2.0,
+            "took {:?}",
+            one_at_a_time
+        );
+    }
+}
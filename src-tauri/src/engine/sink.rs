@@ -0,0 +1,6 @@
+/// Receives the progress and log lines of a long-running operation, so the same
+/// code reports to the app window, a terminal or a test
+pub trait ProgressSink<P>: Send + Sync {
+    fn progress(&self, progress: P);
+    fn log(&self, line: &str);
+}
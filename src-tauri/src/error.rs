@@ -0,0 +1,315 @@
+use std::fmt::Display;
+
+use serde::{Serialize, Serializer};
+use tokio_postgres::error::SqlState;
+
+use crate::pg_client::describe_error;
+
+/// Why a connection attempt failed, so the UI can suggest a fix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConnectionFailureKind {
+    Refused,
+    Timeout,
+    HostNotFound,
+    Tls,
+    DatabaseNotFound,
+    Other,
+}
+
+/// Error returned by every command.
+/// Serialized as `{ code, message, kind, stderr }` where `code` is stable and safe to match on.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("{tool} not found. Please install PostgreSQL client tools.")]
+    ToolNotFound { tool: &'static str },
+
+    #[error("Connection failed: {message}")]
+    ConnectionFailed {
+        kind: ConnectionFailureKind,
+        message: String,
+    },
+
+    #[error("Authentication failed: {message}")]
+    AuthFailed { message: String },
+
+    #[error("{context}: {message}")]
+    QueryFailed { context: String, message: String },
+
+    #[error("Failed to dump source database: {stderr}")]
+    DumpFailed { stderr: String },
+
+    #[error("Failed to restore to destination: {stderr}")]
+    RestoreFailed { stderr: String },
+
+    #[error("{context}: {message}")]
+    ProcessFailed { context: String, message: String },
+
+    /// Some parts of an operation over several databases failed
+    #[error("{failed} of {total} {items} failed")]
+    PartialFailure {
+        failed: usize,
+        total: usize,
+        items: &'static str,
+    },
+
+    /// Another clone held the destination for longer than the clone would wait
+    #[error("Another clone into {database} on {server} did not finish in time")]
+    DestinationBusy { database: String, server: String },
+
+    #[error("Profile not found: {id}")]
+    ProfileNotFound { id: String },
+
+    #[error("Tag not found: {id}")]
+    TagNotFound { id: String },
+
+    #[error("Saved operation not found: {id}")]
+    SavedOperationNotFound { id: String },
+
+    #[error("History entry not found: {id}")]
+    HistoryEntryNotFound { id: String },
+
+    #[error("Job not found: {id}")]
+    JobNotFound { id: String },
+
+    #[error("{0}")]
+    InvalidInput(String),
+
+    #[error("App data file is corrupt ({message}). A copy was kept at {path}")]
+    StorageCorrupt { path: String, message: String },
+
+    #[error("{context}: {message}")]
+    StorageFailed { context: String, message: String },
+
+    #[error("{0}")]
+    Internal(String),
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    /// Stable identifier for scripts and the frontend
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::ToolNotFound { .. } => "TOOL_NOT_FOUND",
+            AppError::ConnectionFailed { .. } => "CONNECTION_FAILED",
+            AppError::AuthFailed { .. } => "AUTH_FAILED",
+            AppError::QueryFailed { .. } => "QUERY_FAILED",
+            AppError::DumpFailed { .. } => "DUMP_FAILED",
+            AppError::RestoreFailed { .. } => "RESTORE_FAILED",
+            AppError::ProcessFailed { .. } => "PROCESS_FAILED",
+            AppError::PartialFailure { .. } => "PARTIAL_FAILURE",
+            AppError::DestinationBusy { .. } => "DESTINATION_BUSY",
+            AppError::ProfileNotFound { .. } => "PROFILE_NOT_FOUND",
+            AppError::TagNotFound { .. } => "TAG_NOT_FOUND",
+            AppError::SavedOperationNotFound { .. } => "SAVED_OPERATION_NOT_FOUND",
+            AppError::HistoryEntryNotFound { .. } => "HISTORY_ENTRY_NOT_FOUND",
+            AppError::JobNotFound { .. } => "JOB_NOT_FOUND",
+            AppError::InvalidInput(_) => "INVALID_INPUT",
+            AppError::StorageCorrupt { .. } => "STORAGE_CORRUPT",
+            AppError::StorageFailed { .. } => "STORAGE_FAILED",
+            AppError::Internal(_) => "INTERNAL",
+        }
+    }
+
+    /// Raw stderr of the PostgreSQL tool that failed, if any
+    pub fn stderr(&self) -> Option<&str> {
+        match self {
+            AppError::DumpFailed { stderr } | AppError::RestoreFailed { stderr } => Some(stderr),
+            _ => None,
+        }
+    }
+
+    pub fn query(context: &str, error: &tokio_postgres::Error) -> Self {
+        AppError::QueryFailed {
+            context: context.to_string(),
+            message: describe_error(error),
+        }
+    }
+
+    pub fn process(context: &str, error: impl Display) -> Self {
+        AppError::ProcessFailed {
+            context: context.to_string(),
+            message: error.to_string(),
+        }
+    }
+
+    pub fn storage(context: &str, error: impl Display) -> Self {
+        AppError::StorageFailed {
+            context: context.to_string(),
+            message: error.to_string(),
+        }
+    }
+
+    /// Classifies an error raised while opening a connection
+    pub fn from_connect_error(error: &tokio_postgres::Error) -> Self {
+        let message = describe_error(error);
+
+        if let Some(db_error) = error.as_db_error() {
+            return AppError::from_server_refusal(db_error.code(), message);
+        }
+
+        let io_kind = std::error::Error::source(error)
+            .and_then(|source| source.downcast_ref::<std::io::Error>())
+            .map(|io| io.kind());
+        AppError::ConnectionFailed {
+            kind: failure_kind(io_kind, &message),
+            message,
+        }
+    }
+
+    /// The server answered but would not let the connection in
+    fn from_server_refusal(code: &SqlState, message: String) -> Self {
+        if *code == SqlState::INVALID_PASSWORD
+            || *code == SqlState::INVALID_AUTHORIZATION_SPECIFICATION
+        {
+            return AppError::AuthFailed { message };
+        }
+        let kind = if *code == SqlState::INVALID_CATALOG_NAME {
+            ConnectionFailureKind::DatabaseNotFound
+        } else {
+            ConnectionFailureKind::Other
+        };
+        AppError::ConnectionFailed { kind, message }
+    }
+}
+
+/// Why the server could not be reached, from the I/O error behind the failure or
+/// failing that its message
+fn failure_kind(io_kind: Option<std::io::ErrorKind>, message: &str) -> ConnectionFailureKind {
+    let lowered = message.to_lowercase();
+    match io_kind {
+        Some(std::io::ErrorKind::ConnectionRefused) => ConnectionFailureKind::Refused,
+        Some(std::io::ErrorKind::TimedOut) => ConnectionFailureKind::Timeout,
+        _ if lowered.contains("timed out") => ConnectionFailureKind::Timeout,
+        _ if lowered.contains("lookup address") || lowered.contains("name or service") => {
+            ConnectionFailureKind::HostNotFound
+        }
+        _ if lowered.contains("tls") => ConnectionFailureKind::Tls,
+        _ => ConnectionFailureKind::Other,
+    }
+}
+
+#[derive(Serialize)]
+struct SerializedError<'a> {
+    code: &'static str,
+    message: String,
+    kind: Option<ConnectionFailureKind>,
+    stderr: Option<&'a str>,
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let kind = match self {
+            AppError::ConnectionFailed { kind, .. } => Some(*kind),
+            _ => None,
+        };
+
+        SerializedError {
+            code: self.code(),
+            message: self.to_string(),
+            kind,
+            stderr: self.stderr(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn classifies_server_refusals() {
+        let auth = AppError::from_server_refusal(
+            &SqlState::INVALID_PASSWORD,
+            "password authentication failed".to_string(),
+        );
+        assert!(matches!(auth, AppError::AuthFailed { .. }));
+        let role = AppError::from_server_refusal(
+            &SqlState::INVALID_AUTHORIZATION_SPECIFICATION,
+            "role \"nobody\" does not exist".to_string(),
+        );
+        assert!(matches!(role, AppError::AuthFailed { .. }));
+
+        let database = AppError::from_server_refusal(
+            &SqlState::INVALID_CATALOG_NAME,
+            "database \"shop\" does not exist".to_string(),
+        );
+        assert!(matches!(
+            database,
+            AppError::ConnectionFailed {
+                kind: ConnectionFailureKind::DatabaseNotFound,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn classifies_unreachable_servers() {
+        let cases = [
+            (
+                Some(ErrorKind::ConnectionRefused),
+                "error connecting to server: Connection refused (os error 111)",
+                ConnectionFailureKind::Refused,
+            ),
+            (
+                Some(ErrorKind::TimedOut),
+                "error connecting to server",
+                ConnectionFailureKind::Timeout,
+            ),
+            (
+                None,
+                "error connecting to server: timed out",
+                ConnectionFailureKind::Timeout,
+            ),
+            (
+                None,
+                "error connecting to server: failed to lookup address information: Name or service not known",
+                ConnectionFailureKind::HostNotFound,
+            ),
+            (
+                None,
+                "error performing TLS handshake: invalid peer certificate",
+                ConnectionFailureKind::Tls,
+            ),
+            (
+                None,
+                "unexpected message from server",
+                ConnectionFailureKind::Other,
+            ),
+        ];
+        for (io_kind, message, expected) in cases {
+            assert_eq!(failure_kind(io_kind, message), expected, "{}", message);
+        }
+    }
+
+    #[test]
+    fn serializes_code_message_kind_and_stderr() {
+        let refused = AppError::ConnectionFailed {
+            kind: ConnectionFailureKind::Refused,
+            message: "Connection refused".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&refused).unwrap(),
+            json!({
+                "code": "CONNECTION_FAILED",
+                "message": "Connection failed: Connection refused",
+                "kind": "refused",
+                "stderr": null,
+            })
+        );
+
+        let dump = AppError::DumpFailed {
+            stderr: "pg_dump: error: permission denied".to_string(),
+        };
+        let value = serde_json::to_value(&dump).unwrap();
+        assert_eq!(value["code"], "DUMP_FAILED");
+        assert_eq!(value["kind"], json!(null));
+        assert_eq!(value["stderr"], "pg_dump: error: permission denied");
+    }
+}
@@ -0,0 +1,515 @@
+use std::fmt::Write;
+
+use crate::error::{AppError, AppResult};
+use crate::storage::Store;
+use crate::types::{CloneHistoryEntry, ExportFormat, MaskingOptions, SubsetOptions, ToolInfo};
+
+/// Writes the given history entries, with their full logs, to `path`
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn export_history(ids: Vec<String>, format: ExportFormat, path: String) -> AppResult<()> {
+    if ids.is_empty() {
+        return Err(AppError::InvalidInput(
+            "Select at least one history entry to export".to_string(),
+        ));
+    }
+
+    let store = Store::open()?;
+    let entries = ids
+        .iter()
+        .map(|id| {
+            store
+                .get_history_entry(id)?
+                .ok_or_else(|| AppError::HistoryEntryNotFound { id: id.clone() })
+        })
+        .collect::<AppResult<Vec<_>>>()?;
+
+    let content = render(&entries, format)?;
+    std::fs::write(&path, content)
+        .map_err(|e| AppError::storage(&format!("Failed to write {}", path), e))
+}
+
+fn render(entries: &[CloneHistoryEntry], format: ExportFormat) -> AppResult<String> {
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(entries)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize history: {}", e))),
+        ExportFormat::Csv => render_csv(entries),
+        ExportFormat::Markdown => Ok(render_markdown(entries)),
+        ExportFormat::Html => Ok(render_html(entries)),
+    }
+}
+
+fn label<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn optional<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(T::to_string).unwrap_or_default()
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_string()
+}
+
+fn tool(info: &ToolInfo) -> String {
+    match &info.version {
+        Some(version) => format!("{} ({})", info.path, version),
+        None => info.path.clone(),
+    }
+}
+
+fn renames<'a>(pairs: impl Iterator<Item = (&'a str, &'a str)>) -> String {
+    pairs
+        .map(|(from, to)| format!("{} → {}", from, to))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn subset(options: &SubsetOptions) -> String {
+    let mut roots: Vec<String> = options
+        .roots
+        .iter()
+        .map(|root| {
+            let narrowed: Vec<String> = root
+                .where_clause
+                .iter()
+                .cloned()
+                .chain(root.percent.map(|p| format!("{}%", p)))
+                .collect();
+            if narrowed.is_empty() {
+                root.table.clone()
+            } else {
+                format!("{}: {}", root.table, narrowed.join(", "))
+            }
+        })
+        .collect();
+    if options.include_referencing {
+        roots.push("with referencing rows".to_string());
+    }
+    roots.join("; ")
+}
+
+/// Masked columns and their strategy; the seed is left out of reports
+fn masking(options: &MaskingOptions) -> String {
+    options
+        .rules
+        .iter()
+        .map(|rule| {
+            let strategy = serde_json::to_value(&rule.strategy)
+                .ok()
+                .and_then(|v| v["kind"].as_str().map(str::to_string))
+                .unwrap_or_default();
+            format!("{}.{}: {}", rule.table, rule.column, strategy)
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Clone options, environment and partial copy results, empty when not recorded.
+/// Databases and destinations fall back to the requested ones when no results were kept.
+fn settings(entry: &CloneHistoryEntry) -> Vec<(&'static str, String)> {
+    let options = entry.options.as_ref();
+    let environment = entry.environment.as_ref();
+    vec![
+        (
+            "Clean destination",
+            optional(&options.map(|o| yes_no(o.clean_destination))),
+        ),
+        (
+            "Create backup",
+            optional(&options.map(|o| yes_no(o.create_backup))),
+        ),
+        (
+            "Included schemas",
+            optional(&options.map(|o| o.include_schemas.join(", "))),
+        ),
+        (
+            "Excluded schemas",
+            optional(&options.map(|o| o.exclude_schemas.join(", "))),
+        ),
+        (
+            "Included tables",
+            optional(&options.map(|o| o.include_tables.join(", "))),
+        ),
+        (
+            "Excluded tables",
+            optional(&options.map(|o| o.exclude_tables.join(", "))),
+        ),
+        (
+            "Tables without data",
+            optional(&options.map(|o| o.exclude_table_data.join(", "))),
+        ),
+        (
+            "Schema renames",
+            optional(&options.map(|o| {
+                renames(
+                    o.schema_renames
+                        .iter()
+                        .map(|r| (r.from.as_str(), r.to.as_str())),
+                )
+            })),
+        ),
+        (
+            "Keep ownership",
+            optional(&options.map(|o| yes_no(o.keep_ownership))),
+        ),
+        (
+            "Role map",
+            optional(
+                &options
+                    .map(|o| renames(o.role_map.iter().map(|r| (r.from.as_str(), r.to.as_str())))),
+            ),
+        ),
+        (
+            "Row filters",
+            optional(&options.map(|o| {
+                o.row_filters
+                    .iter()
+                    .map(|f| format!("{}: {}", f.table, f.where_clause))
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            })),
+        ),
+        (
+            "Subset",
+            optional(&options.and_then(|o| o.subset.as_ref()).map(subset)),
+        ),
+        (
+            "Masking",
+            optional(&options.and_then(|o| o.masking.as_ref()).map(masking)),
+        ),
+        (
+            "Restore concurrency",
+            optional(
+                &options
+                    .filter(|o| !o.destination_ids.is_empty())
+                    .map(|o| o.restore_concurrency),
+            ),
+        ),
+        (
+            "Parallel jobs",
+            optional(&environment.and_then(|e| e.parallel_jobs)),
+        ),
+        ("pg_dump", optional(&environment.map(|e| tool(&e.pg_dump)))),
+        (
+            "pg_restore",
+            optional(&environment.map(|e| tool(&e.pg_restore))),
+        ),
+        ("psql", optional(&environment.map(|e| tool(&e.psql)))),
+        (
+            "Source server",
+            optional(&environment.and_then(|e| e.source_server_version.clone())),
+        ),
+        (
+            "Destination server",
+            optional(&environment.and_then(|e| e.destination_server_version.clone())),
+        ),
+        (
+            "Copied rows",
+            optional(&entry.copied_rows.as_ref().map(|counts| {
+                counts
+                    .iter()
+                    .map(|c| format!("{}: {}", c.table, c.rows))
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            })),
+        ),
+        (
+            "Databases",
+            match &entry.database_results {
+                Some(results) => results
+                    .iter()
+                    .map(|r| format!("{}: {}", r.database, label(&r.status)))
+                    .collect::<Vec<_>>()
+                    .join("; "),
+                None => optional(&options.map(|o| o.databases.join(", "))),
+            },
+        ),
+        (
+            "Destinations",
+            match &entry.destination_results {
+                Some(results) => results
+                    .iter()
+                    .map(|r| format!("{}: {}", r.destination_name, label(&r.status)))
+                    .collect::<Vec<_>>()
+                    .join("; "),
+                None => optional(&options.map(|o| o.destination_ids.join(", "))),
+            },
+        ),
+    ]
+}
+
+/// One row per entry; log lines are joined into a single multi-line cell
+fn render_csv(entries: &[CloneHistoryEntry]) -> AppResult<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let to_error = |e: csv::Error| AppError::Internal(format!("Failed to write CSV: {}", e));
+
+    for (i, entry) in entries.iter().enumerate() {
+        let fields: Vec<(&str, String)> = [
+            ("id", entry.id.clone()),
+            ("source", entry.source_name.clone()),
+            ("destination", entry.destination_name.clone()),
+            ("clone_type", label(&entry.clone_type)),
+            ("status", label(&entry.status)),
+            ("started_at", entry.started_at.to_rfc3339()),
+            (
+                "completed_at",
+                optional(&entry.completed_at.map(|t| t.to_rfc3339())),
+            ),
+            ("duration_seconds", optional(&entry.duration)),
+            ("verified_tables", optional(&entry.verified_tables)),
+            ("error_code", optional(&entry.error_code)),
+            ("error_message", optional(&entry.error_message)),
+        ]
+        .into_iter()
+        .chain(settings(entry))
+        .chain([("logs", entry.logs.join("\n"))])
+        .collect();
+
+        // Setting labels become snake case column names
+        if i == 0 {
+            writer
+                .write_record(
+                    fields
+                        .iter()
+                        .map(|(name, _)| name.to_lowercase().replace(' ', "_")),
+                )
+                .map_err(to_error)?;
+        }
+        writer
+            .write_record(fields.iter().map(|(_, value)| value))
+            .map_err(to_error)?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| AppError::Internal(format!("Failed to write CSV: {}", e)))?;
+    String::from_utf8(bytes).map_err(|e| AppError::Internal(e.to_string()))
+}
+
+/// Label/value pairs shown for every entry in the readable reports
+fn summary(entry: &CloneHistoryEntry) -> Vec<(&'static str, String)> {
+    let mut rows = vec![
+        ("Source", entry.source_name.clone()),
+        ("Destination", entry.destination_name.clone()),
+        ("Clone type", label(&entry.clone_type)),
+        ("Status", label(&entry.status)),
+        ("Started", entry.started_at.to_rfc3339()),
+        (
+            "Completed",
+            optional(&entry.completed_at.map(|t| t.to_rfc3339())),
+        ),
+        (
+            "Duration",
+            entry
+                .duration
+                .map(|d| format!("{}s", d))
+                .unwrap_or_default(),
+        ),
+        ("Verified tables", optional(&entry.verified_tables)),
+        ("Error", optional(&entry.error_message)),
+    ];
+    rows.extend(settings(entry));
+    rows
+}
+
+/// A code fence longer than any run of backticks in the logs
+fn code_fence(logs: &[String]) -> String {
+    let longest = logs
+        .iter()
+        .flat_map(|line| line.split(|c| c != '`'))
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn render_markdown(entries: &[CloneHistoryEntry]) -> String {
+    let mut out = String::from("# Clone history report\n");
+
+    for entry in entries {
+        let _ = writeln!(
+            out,
+            "\n## {} → {} ({})\n",
+            entry.source_name,
+            entry.destination_name,
+            entry.started_at.format("%Y-%m-%d %H:%M UTC")
+        );
+        out.push_str("| Field | Value |\n|---|---|\n");
+        for (name, value) in summary(entry) {
+            let value = value.replace('|', "\\|").replace('\n', " ");
+            let _ = writeln!(out, "| {} | {} |", name, value);
+        }
+
+        let fence = code_fence(&entry.logs);
+        let _ = writeln!(out, "\n### Logs\n\n{}", fence);
+        for line in &entry.logs {
+            let _ = writeln!(out, "{}", line);
+        }
+        let _ = writeln!(out, "{}", fence);
+    }
+
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+const HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2rem;color:#1f2937}\
+table{border-collapse:collapse;margin-bottom:1rem}\
+td,th{border:1px solid #d1d5db;padding:.25rem .75rem;text-align:left}\
+pre{background:#f3f4f6;padding:1rem;overflow-x:auto;font-size:.85rem}";
+
+fn render_html(entries: &[CloneHistoryEntry]) -> String {
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Clone history report</title>\n<style>{}</style>\n</head>\n<body>\n\
+         <h1>Clone history report</h1>\n",
+        HTML_STYLE
+    );
+
+    for entry in entries {
+        let _ = writeln!(
+            out,
+            "<h2>{} &rarr; {} ({})</h2>\n<table>",
+            escape_html(&entry.source_name),
+            escape_html(&entry.destination_name),
+            entry.started_at.format("%Y-%m-%d %H:%M UTC")
+        );
+        for (name, value) in summary(entry) {
+            let _ = writeln!(
+                out,
+                "<tr><th>{}</th><td>{}</td></tr>",
+                name,
+                escape_html(&value)
+            );
+        }
+        let _ = writeln!(
+            out,
+            "</table>\n<h3>Logs</h3>\n<pre>{}</pre>",
+            escape_html(&entry.logs.join("\n"))
+        );
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        CloneOptions, CloneStatus, CloneType, ConnectionProfile, MaskStrategy, MaskingRule,
+        RoleMapping, SchemaRename, SubsetRoot, TableRowFilter,
+    };
+
+    fn entry() -> CloneHistoryEntry {
+        let profile = |name: &str| {
+            ConnectionProfile::new(
+                name.to_string(),
+                "localhost".to_string(),
+                5432,
+                "shop".to_string(),
+                "postgres".to_string(),
+                String::new(),
+                false,
+                None,
+            )
+        };
+        let (source, destination) = (profile("Prod <eu>"), profile("Staging"));
+        let options = CloneOptions {
+            clean_destination: true,
+            exclude_tables: vec!["audit_log".to_string(), "sessions".to_string()],
+            include_schemas: vec!["public".to_string(), "billing".to_string()],
+            exclude_table_data: vec!["events".to_string()],
+            schema_renames: vec![SchemaRename {
+                from: "billing".to_string(),
+                to: "billing_copy".to_string(),
+            }],
+            keep_ownership: true,
+            role_map: vec![RoleMapping {
+                from: "app".to_string(),
+                to: "staging_app".to_string(),
+            }],
+            row_filters: vec![TableRowFilter {
+                table: "orders".to_string(),
+                where_clause: "total > 100".to_string(),
+            }],
+            subset: Some(SubsetOptions {
+                roots: vec![SubsetRoot {
+                    table: "customers".to_string(),
+                    where_clause: Some("country = 'PE'".to_string()),
+                    percent: Some(10.0),
+                }],
+                include_referencing: true,
+            }),
+            masking: Some(MaskingOptions {
+                rules: vec![MaskingRule {
+                    table: "customers".to_string(),
+                    column: "email".to_string(),
+                    strategy: MaskStrategy::FakeEmail,
+                }],
+                seed: "secret seed".to_string(),
+            }),
+            ..CloneOptions::new(source.id.clone(), destination.id.clone(), CloneType::Both)
+        };
+        let mut entry = CloneHistoryEntry::new(&source, &destination, &options);
+        entry.add_log("[INFO] Starting clone, \"quoted\"".to_string());
+        entry.add_log("[ERROR] ```".to_string());
+        entry.complete(CloneStatus::Error, None);
+        entry
+    }
+
+    #[test]
+    fn csv_keeps_logs_in_one_cell() {
+        let csv = render_csv(&[entry()]).unwrap();
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        let cell = |column: &str| {
+            let index = headers.iter().position(|h| h == column).unwrap();
+            rows[0][index].to_string()
+        };
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(cell("source"), "Prod <eu>");
+        assert_eq!(cell("status"), "error");
+        assert_eq!(cell("clean_destination"), "yes");
+        assert_eq!(cell("included_schemas"), "public, billing");
+        assert_eq!(cell("excluded_tables"), "audit_log, sessions");
+        assert_eq!(cell("tables_without_data"), "events");
+        assert_eq!(cell("schema_renames"), "billing → billing_copy");
+        assert_eq!(cell("keep_ownership"), "yes");
+        assert_eq!(cell("role_map"), "app → staging_app");
+        assert_eq!(cell("row_filters"), "orders: total > 100");
+        assert_eq!(
+            cell("subset"),
+            "customers: country = 'PE', 10%; with referencing rows"
+        );
+        assert_eq!(cell("masking"), "customers.email: fakeEmail");
+        assert!(!csv.contains("secret seed"));
+        assert_eq!(
+            cell("logs"),
+            "[INFO] Starting clone, \"quoted\"\n[ERROR] ```"
+        );
+    }
+
+    #[test]
+    fn reports_escape_user_text() {
+        let entries = [entry()];
+
+        let html = render_html(&entries);
+        assert!(html.contains("Prod &lt;eu&gt;"));
+        assert!(!html.contains("Prod <eu>"));
+
+        let markdown = render_markdown(&entries);
+        assert!(markdown.contains("\n````\n[INFO]"));
+    }
+}
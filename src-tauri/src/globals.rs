@@ -0,0 +1,448 @@
+use std::process::Stdio;
+
+#[cfg(feature = "gui")]
+use tauri::{AppHandle, Emitter};
+use tokio_postgres::Client;
+
+use crate::command_helper::create_async_command;
+#[cfg(feature = "gui")]
+use crate::connection::get_profile_by_id;
+use crate::error::{AppError, AppResult};
+#[cfg(feature = "gui")]
+use crate::pg_client::{connect_profile, describe_error};
+#[cfg(feature = "gui")]
+use crate::pg_tools::find_pg_dumpall;
+use crate::selection::glob_matches;
+use crate::types::{ConnectionProfile, RoleMapping};
+#[cfg(feature = "gui")]
+use crate::types::{GlobalsCloneOptions, GlobalsCloneResult};
+
+/// Runs pg_dumpall against the server of `profile` and returns the SQL it prints
+pub async fn dump_globals(
+    pg_dumpall: &str,
+    profile: &ConnectionProfile,
+    args: &[&str],
+) -> AppResult<String> {
+    let conn_str = format!(
+        "host={} port={} dbname={} user={}",
+        profile.host, profile.port, profile.database, profile.user
+    );
+
+    let output = create_async_command(pg_dumpall)
+        .env("PGPASSWORD", &profile.password)
+        .env("PGSSLMODE", if profile.ssl { "require" } else { "prefer" })
+        .args(["-d", &conn_str])
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .map_err(|e| AppError::process("Failed to run pg_dumpall", e))?;
+
+    if !output.status.success() {
+        return Err(AppError::DumpFailed {
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Reads an identifier, quoted or not, from the start of `text`. Returns the name
+/// and the rest of the text.
+pub fn split_identifier(text: &str) -> Option<(String, &str)> {
+    if let Some(quoted) = text.strip_prefix('"') {
+        let mut name = String::new();
+        let mut chars = quoted.char_indices().peekable();
+        while let Some((i, ch)) = chars.next() {
+            if ch == '"' {
+                if chars.peek().map(|&(_, c)| c) == Some('"') {
+                    chars.next();
+                    name.push('"');
+                } else {
+                    return Some((name, &quoted[i + 1..]));
+                }
+            } else {
+                name.push(ch);
+            }
+        }
+        return None;
+    }
+
+    let end = text
+        .find(|c: char| c.is_whitespace() || c == ';' || c == ',')
+        .unwrap_or(text.len());
+    (end > 0).then(|| (text[..end].to_string(), &text[end..]))
+}
+
+/// Quotes a role name only when pg_dump would
+fn role_ident(name: &str) -> String {
+    let plain = name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !name.starts_with(|c: char| c.is_ascii_digit());
+    if plain && !name.is_empty() {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+/// Replaces the role named after each `TO`, `FROM`, `ROLE` and `BY` keyword. Covers
+/// the `OWNER TO`, `GRANT`, `REVOKE` and role statements pg_dump and pg_dumpall emit.
+pub fn map_roles(statement: &str, mappings: &[RoleMapping]) -> String {
+    if mappings.is_empty() {
+        return statement.to_string();
+    }
+
+    let mut out = String::with_capacity(statement.len());
+    let mut rest = statement;
+    let mut expect_role = false;
+    let mut after_role = false;
+    while !rest.is_empty() {
+        let trimmed = rest.trim_start();
+        out.push_str(&rest[..rest.len() - trimmed.len()]);
+        rest = trimmed;
+        if rest.is_empty() {
+            break;
+        }
+
+        // String literals pass through untouched
+        if rest.starts_with('\'') {
+            let end = rest[1..].find('\'').map(|i| i + 2).unwrap_or(rest.len());
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            expect_role = false;
+            after_role = false;
+            continue;
+        }
+
+        match split_identifier(rest) {
+            Some((word, after)) => {
+                let raw = &rest[..rest.len() - after.len()];
+                if expect_role {
+                    match mappings.iter().find(|m| m.from == word) {
+                        Some(mapping) => out.push_str(&role_ident(&mapping.to)),
+                        None => out.push_str(raw),
+                    }
+                } else {
+                    out.push_str(raw);
+                }
+                after_role = expect_role;
+                expect_role = matches!(raw, "TO" | "FROM" | "ROLE" | "BY");
+                rest = after;
+            }
+            None => {
+                let ch = rest.chars().next().unwrap();
+                out.push(ch);
+                rest = &rest[ch.len_utf8()..];
+                // A list of grantees keeps naming roles
+                expect_role = after_role && ch == ',';
+                after_role = false;
+            }
+        }
+    }
+    out
+}
+
+/// Points a `CREATE ROLE` or `ALTER ROLE` statement at role `name`
+pub fn rename_role(statement: &str, name: &str) -> String {
+    for prefix in ["CREATE ROLE ", "ALTER ROLE "] {
+        if let Some((_, rest)) = statement.strip_prefix(prefix).and_then(split_identifier) {
+            return format!("{}{}{}", prefix, role_ident(name), rest);
+        }
+    }
+    statement.to_string()
+}
+
+/// The `CREATE ROLE` and `ALTER ROLE` statements of one role
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoleDefinition {
+    pub name: String,
+    pub statements: Vec<String>,
+}
+
+/// Role definitions in pg_dumpall output, in order
+pub fn parse_roles(sql: &str) -> Vec<RoleDefinition> {
+    let mut roles: Vec<RoleDefinition> = Vec::new();
+    for line in sql.lines() {
+        let Some(rest) = line
+            .strip_prefix("CREATE ROLE ")
+            .or_else(|| line.strip_prefix("ALTER ROLE "))
+        else {
+            continue;
+        };
+        let Some((name, _)) = split_identifier(rest) else {
+            continue;
+        };
+        match roles.iter_mut().find(|r| r.name == name) {
+            Some(role) => role.statements.push(line.to_string()),
+            None => roles.push(RoleDefinition {
+                name,
+                statements: vec![line.to_string()],
+            }),
+        }
+    }
+    roles
+}
+
+/// Statements of a globals dump to run on the destination, and what was left out
+#[derive(Debug, Default, PartialEq)]
+struct GlobalsPlan {
+    statements: Vec<String>,
+    skipped: Vec<String>,
+}
+
+/// Name of the object a statement starting with one of `prefixes` is about
+fn object_name(line: &str, prefixes: &[&str]) -> Option<String> {
+    prefixes
+        .iter()
+        .find_map(|prefix| line.strip_prefix(prefix))
+        .and_then(split_identifier)
+        .map(|(name, _)| name)
+}
+
+/// Picks the statements of pg_dumpall `--globals-only` output worth running on the
+/// destination. Roles and tablespaces it already has are left as they are, so the
+/// connecting role and its password are never touched.
+fn plan_globals(
+    sql: &str,
+    role_filter: &[String],
+    existing_roles: &[String],
+    existing_tablespaces: &[String],
+) -> GlobalsPlan {
+    let selected =
+        |role: &str| role_filter.is_empty() || role_filter.iter().any(|p| glob_matches(p, role));
+    let role_exists = |role: &str| existing_roles.iter().any(|r| r == role);
+    let mut plan = GlobalsPlan::default();
+    let skip = |plan: &mut GlobalsPlan, note: String| {
+        if !plan.skipped.contains(&note) {
+            plan.skipped.push(note);
+        }
+    };
+
+    for line in sql.lines().map(str::trim_end) {
+        if let Some(role) = object_name(line, &["CREATE ROLE ", "ALTER ROLE ", "COMMENT ON ROLE "])
+        {
+            if !selected(&role) {
+                continue;
+            }
+            if role_exists(&role) {
+                skip(&mut plan, format!("Role {} already exists", role));
+                continue;
+            }
+            plan.statements.push(line.to_string());
+        } else if let Some(tablespace) = object_name(
+            line,
+            &[
+                "CREATE TABLESPACE ",
+                "ALTER TABLESPACE ",
+                "COMMENT ON TABLESPACE ",
+            ],
+        ) {
+            if existing_tablespaces.contains(&tablespace) {
+                skip(
+                    &mut plan,
+                    format!("Tablespace {} already exists", tablespace),
+                );
+                continue;
+            }
+            plan.statements.push(line.to_string());
+        } else if line.contains(" ON TABLESPACE ") {
+            if line.starts_with("GRANT ") || line.starts_with("REVOKE ") {
+                plan.statements.push(line.to_string());
+            }
+        } else if let Some((role, rest)) = line.strip_prefix("GRANT ").and_then(split_identifier) {
+            // Membership: GRANT role TO member [WITH ...] [GRANTED BY grantor]
+            let Some((member, _)) = rest.strip_prefix(" TO ").and_then(split_identifier) else {
+                continue;
+            };
+            if !selected(&member) || !(selected(&role) || role_exists(&role)) {
+                continue;
+            }
+            // The grantor may not exist on the destination; the connecting role grants instead
+            let statement = match line.find(" GRANTED BY ") {
+                Some(i) => format!("{};", &line[..i]),
+                None => line.to_string(),
+            };
+            plan.statements.push(statement);
+        }
+    }
+    plan
+}
+
+#[cfg(feature = "gui")]
+fn emit_globals_log(app: &AppHandle, log: &str) {
+    let _ = app.emit("globals-log", log);
+}
+
+/// Names in the first column of `query`'s rows
+async fn names(client: &Client, query: &str) -> AppResult<Vec<String>> {
+    let rows = client
+        .query(query, &[])
+        .await
+        .map_err(|e| AppError::query("Failed to get existing globals", &e))?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+/// Copies roles, role memberships and tablespaces from one server to another
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn clone_globals(
+    app: AppHandle,
+    options: GlobalsCloneOptions,
+) -> AppResult<GlobalsCloneResult> {
+    let source = get_profile_by_id(&options.source_id)?;
+    let destination = get_profile_by_id(&options.destination_id)?;
+    let pg_dumpall = find_pg_dumpall().ok_or(AppError::ToolNotFound { tool: "pg_dumpall" })?;
+
+    let scope = match (options.include_roles, options.include_tablespaces) {
+        (true, true) => "--globals-only",
+        (true, false) => "--roles-only",
+        (false, true) => "--tablespaces-only",
+        (false, false) => {
+            return Err(AppError::InvalidInput(
+                "Choose roles, tablespaces or both".to_string(),
+            ))
+        }
+    };
+    let add_log = |msg: &str| emit_globals_log(&app, msg);
+
+    add_log(&format!(
+        "[INFO] Reading globals from {}:{}",
+        source.host, source.port
+    ));
+    let mut args = vec![scope];
+    if options.strip_passwords {
+        args.push("--no-role-passwords");
+    }
+    let sql = dump_globals(&pg_dumpall, &source, &args).await?;
+
+    let client = connect_profile(&destination).await?;
+    let existing_roles = names(&client, "SELECT rolname::text FROM pg_roles").await?;
+    let existing_tablespaces = names(&client, "SELECT spcname::text FROM pg_tablespace").await?;
+    let plan = plan_globals(
+        &sql,
+        &options.role_filter,
+        &existing_roles,
+        &existing_tablespaces,
+    );
+    for note in &plan.skipped {
+        add_log(&format!("[INFO] {}, left unchanged", note));
+    }
+
+    let mut failed = Vec::new();
+    for statement in &plan.statements {
+        if let Err(e) = client.batch_execute(statement).await {
+            failed.push(format!("{} ({})", statement, describe_error(&e)));
+        }
+    }
+
+    let applied = plan.statements.len() - failed.len();
+    add_log(&format!(
+        "[SUCCESS] Applied {} of {} statements on {}:{}",
+        applied,
+        plan.statements.len(),
+        destination.host,
+        destination.port
+    ));
+    if !failed.is_empty() {
+        add_log(&format!(
+            "[WARNING] {} statements could not be applied:\n{}",
+            failed.len(),
+            failed.join("\n")
+        ));
+    }
+
+    Ok(GlobalsCloneResult {
+        applied,
+        skipped: plan.skipped,
+        failed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mappings() -> Vec<RoleMapping> {
+        vec![RoleMapping {
+            from: "app".to_string(),
+            to: "App Owner".to_string(),
+        }]
+    }
+
+    #[test]
+    fn maps_roles_after_keywords() {
+        assert_eq!(
+            map_roles("ALTER TABLE public.app OWNER TO app;", &mappings()),
+            "ALTER TABLE public.app OWNER TO \"App Owner\";"
+        );
+        assert_eq!(
+            map_roles(
+                "ALTER DEFAULT PRIVILEGES FOR ROLE app IN SCHEMA public GRANT SELECT ON TABLES TO reader, app;",
+                &mappings()
+            ),
+            "ALTER DEFAULT PRIVILEGES FOR ROLE \"App Owner\" IN SCHEMA public GRANT SELECT ON TABLES TO reader, \"App Owner\";"
+        );
+        // Comments and other identifiers named like the role are left alone
+        assert_eq!(
+            map_roles("COMMENT ON TABLE app IS 'owned by TO app';", &mappings()),
+            "COMMENT ON TABLE app IS 'owned by TO app';"
+        );
+    }
+
+    #[test]
+    fn groups_role_statements() {
+        let sql = "--\n-- Roles\n--\n\nCREATE ROLE app;\n\
+                   ALTER ROLE app WITH NOSUPERUSER INHERIT LOGIN;\n\
+                   CREATE ROLE \"Read Only\";\n\
+                   ALTER ROLE \"Read Only\" WITH NOLOGIN;\n\
+                   ALTER ROLE app SET search_path TO app, public;\n";
+        let roles = parse_roles(sql);
+        assert_eq!(roles.len(), 2);
+        assert_eq!(roles[0].name, "app");
+        assert_eq!(roles[0].statements.len(), 3);
+        assert_eq!(roles[1].name, "Read Only");
+    }
+
+    #[test]
+    fn plans_globals_for_the_destination() {
+        let sql = "SET default_transaction_read_only = off;\n\
+                   CREATE ROLE app;\n\
+                   ALTER ROLE app WITH NOSUPERUSER INHERIT LOGIN;\n\
+                   CREATE ROLE postgres;\n\
+                   ALTER ROLE postgres WITH SUPERUSER LOGIN;\n\
+                   CREATE ROLE readers;\n\
+                   CREATE ROLE audit;\n\
+                   GRANT readers TO app WITH INHERIT TRUE GRANTED BY postgres;\n\
+                   GRANT readers TO audit GRANTED BY postgres;\n\
+                   CREATE TABLESPACE fast OWNER postgres LOCATION '/mnt/fast';\n\
+                   CREATE TABLESPACE slow OWNER postgres LOCATION '/mnt/slow';\n";
+        let existing = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let plan = plan_globals(
+            sql,
+            &["app".to_string(), "read*".to_string()],
+            &existing(&["postgres"]),
+            &existing(&["slow"]),
+        );
+        assert_eq!(
+            plan.statements,
+            [
+                "CREATE ROLE app;",
+                "ALTER ROLE app WITH NOSUPERUSER INHERIT LOGIN;",
+                "CREATE ROLE readers;",
+                "GRANT readers TO app WITH INHERIT TRUE;",
+                "CREATE TABLESPACE fast OWNER postgres LOCATION '/mnt/fast';",
+            ]
+        );
+        assert_eq!(plan.skipped, ["Tablespace slow already exists"]);
+    }
+
+    #[test]
+    fn renames_role_statements() {
+        assert_eq!(
+            rename_role("ALTER ROLE \"Read Only\" WITH NOLOGIN;", "reader"),
+            "ALTER ROLE reader WITH NOLOGIN;"
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use crate::error::{AppError, AppResult};
+use crate::storage::Store;
+use crate::types::{
+    CloneHistoryEntry, HistoryFilter, HistoryPage, HistoryPairStats, HistoryRetention,
+};
+
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn get_history(filter: Option<HistoryFilter>) -> AppResult<Vec<CloneHistoryEntry>> {
+    Store::open()?.list_history(&filter.unwrap_or_default())
+}
+
+/// One page of matching entries plus the total, for paginated views
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn query_history(filter: HistoryFilter) -> AppResult<HistoryPage> {
+    let store = Store::open()?;
+
+    Ok(HistoryPage {
+        entries: store.list_history(&filter)?,
+        total: store.count_history(&filter)?,
+    })
+}
+
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn get_history_entry(id: String) -> AppResult<Option<CloneHistoryEntry>> {
+    Store::open()?.get_history_entry(&id)
+}
+
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn delete_history_entry(id: String) -> AppResult<()> {
+    Store::open()?.delete_history_entry(&id)
+}
+
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn clear_history() -> AppResult<()> {
+    Store::open()?.clear_history()
+}
+
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn get_history_stats() -> AppResult<Vec<HistoryPairStats>> {
+    Store::open()?.history_stats()
+}
+
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn get_history_retention() -> AppResult<HistoryRetention> {
+    Store::open()?.history_retention()
+}
+
+/// Saves the retention settings and prunes existing history right away
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn set_history_retention(retention: HistoryRetention) -> AppResult<HistoryRetention> {
+    if retention.max_entries == Some(0) || retention.max_age_days == Some(0) {
+        return Err(AppError::InvalidInput(
+            "History limits must be at least 1".to_string(),
+        ));
+    }
+
+    let store = Store::open()?;
+    store.set_history_retention(&retention)?;
+    store.apply_history_retention()?;
+
+    Ok(retention)
+}
@@ -0,0 +1,84 @@
+use std::fs::{self, File, TryLockError};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Directory holding one locked file per running app or CLI process
+const INSTANCES_DIR: &str = "db-clone-instances";
+
+/// This process's id and the lock file that stays held until it exits
+static INSTANCE: OnceLock<(String, Option<File>)> = OnceLock::new();
+
+fn lock_path(id: &str) -> PathBuf {
+    std::env::temp_dir()
+        .join(INSTANCES_DIR)
+        .join(format!("{}.lock", id))
+}
+
+/// Id recorded on the history rows and temp files this process creates. The first
+/// call takes an exclusive lock the OS releases when the process ends, however it ends.
+pub fn id() -> &'static str {
+    let (id, _) = INSTANCE.get_or_init(|| {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let path = lock_path(&id);
+        let file = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| File::create(&path))
+            .and_then(|file| file.try_lock().map(|_| file).map_err(Into::into))
+            .ok();
+        (id, file)
+    });
+    id
+}
+
+/// Whether the process with this id is still running. Ids whose lock file is gone
+/// or no longer held belong to processes that have exited.
+pub fn is_alive(id: &str) -> bool {
+    if id == self::id() {
+        return true;
+    }
+
+    let path = lock_path(id);
+    let Ok(file) = File::open(&path) else {
+        return false;
+    };
+    match file.try_lock() {
+        Err(TryLockError::WouldBlock) => true,
+        Err(TryLockError::Error(_)) => false,
+        Ok(()) => {
+            drop(file);
+            let _ = fs::remove_file(&path);
+            false
+        }
+    }
+}
+
+/// Deletes the lock files left by processes that have exited
+pub fn remove_exited() {
+    let Ok(dir) = fs::read_dir(std::env::temp_dir().join(INSTANCES_DIR)) else {
+        return;
+    };
+    for file in dir.flatten() {
+        if let Some(id) = file.file_name().to_string_lossy().strip_suffix(".lock") {
+            is_alive(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_running_instances_from_exited_ones() {
+        assert!(is_alive(id()));
+
+        // A lock file nobody holds, as left behind by a crashed process
+        let exited = uuid::Uuid::new_v4().simple().to_string();
+        File::create(lock_path(&exited)).unwrap();
+        assert!(!is_alive(&exited));
+        assert!(!lock_path(&exited).exists());
+
+        assert!(!is_alive("never-started"));
+    }
+}
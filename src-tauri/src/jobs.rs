@@ -0,0 +1,329 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+use chrono::Utc;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::error::{AppError, AppResult};
+use crate::storage::Store;
+use crate::types::{CloneJob, ConnectionProfile, JobStatus};
+
+/// Clones allowed to run at once until the user picks another limit
+const DEFAULT_CONCURRENCY: usize = 2;
+/// Finished jobs kept in the list; older ones are dropped first
+const MAX_FINISHED_JOBS: usize = 20;
+
+/// Work run when the job leaves the queue. Futures do nothing until polled, so
+/// the clone only starts once the queue awaits it.
+pub type JobTask = Pin<Box<dyn Future<Output = AppResult<()>> + Send>>;
+
+struct QueuedJob {
+    job: CloneJob,
+    /// Taken when the job starts
+    task: Option<JobTask>,
+}
+
+struct QueueState {
+    jobs: Vec<QueuedJob>,
+    concurrency: usize,
+}
+
+/// Clones waiting for, holding or done with a slot, kept in Tauri managed state
+pub struct JobQueue {
+    state: Mutex<QueueState>,
+}
+
+/// Lock key of a destination database, shared by every profile pointing at it
+pub fn lock_key(profile: &ConnectionProfile, database: &str) -> String {
+    format!("{}:{}/{}", profile.host, profile.port, database)
+}
+
+/// Indexes of the queued jobs that can start now, in queue order. A job that has
+/// to wait still holds its place on its destinations, so a later job to the same
+/// database never overtakes it.
+fn startable(jobs: &[QueuedJob], concurrency: usize) -> Vec<usize> {
+    let running: Vec<&CloneJob> = jobs
+        .iter()
+        .map(|q| &q.job)
+        .filter(|j| j.status == JobStatus::Running)
+        .collect();
+    let mut slots = concurrency.saturating_sub(running.len());
+    let mut taken: HashSet<&str> = running
+        .iter()
+        .flat_map(|j| j.locks.iter().map(String::as_str))
+        .collect();
+
+    let mut ready = Vec::new();
+    for (i, queued) in jobs.iter().enumerate() {
+        if slots == 0 {
+            break;
+        }
+        if queued.job.status != JobStatus::Queued {
+            continue;
+        }
+        if queued.job.locks.iter().all(|l| !taken.contains(l.as_str())) {
+            ready.push(i);
+            slots -= 1;
+        }
+        taken.extend(queued.job.locks.iter().map(String::as_str));
+    }
+    ready
+}
+
+impl JobQueue {
+    /// Creates an empty queue with the saved concurrency limit
+    pub fn load() -> Self {
+        let concurrency = Store::open()
+            .and_then(|store| store.job_concurrency())
+            .ok()
+            .flatten()
+            .unwrap_or(DEFAULT_CONCURRENCY);
+
+        Self {
+            state: Mutex::new(QueueState {
+                jobs: Vec::new(),
+                concurrency,
+            }),
+        }
+    }
+
+    /// Adds a job at the end of the queue and starts it if it can run now
+    pub fn enqueue(&self, app: &AppHandle, job: CloneJob, task: JobTask) {
+        self.state.lock().unwrap().jobs.push(QueuedJob {
+            job,
+            task: Some(task),
+        });
+        self.pump(app);
+    }
+
+    pub fn list(&self) -> Vec<CloneJob> {
+        let state = self.state.lock().unwrap();
+        state.jobs.iter().map(|q| q.job.clone()).collect()
+    }
+
+    /// Starts every job that has a free slot and free destinations
+    fn pump(&self, app: &AppHandle) {
+        let started: Vec<(String, JobTask)> = {
+            let mut state = self.state.lock().unwrap();
+            let ready = startable(&state.jobs, state.concurrency);
+            ready
+                .into_iter()
+                .filter_map(|i| {
+                    let queued = &mut state.jobs[i];
+                    queued.job.status = JobStatus::Running;
+                    queued.job.started_at = Some(Utc::now());
+                    Some((queued.job.id.clone(), queued.task.take()?))
+                })
+                .collect()
+        };
+
+        for (id, task) in started {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let result = task.await;
+                let queue = app.state::<JobQueue>();
+                queue.finish(&id, result);
+                queue.pump(&app);
+            });
+        }
+
+        self.emit_changed(app);
+    }
+
+    fn finish(&self, id: &str, result: AppResult<()>) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(queued) = state.jobs.iter_mut().find(|q| q.job.id == id) {
+            queued.job.finished_at = Some(Utc::now());
+            match result {
+                Ok(()) => queued.job.status = JobStatus::Success,
+                Err(e) => {
+                    queued.job.status = JobStatus::Error;
+                    queued.job.error_message = Some(e.to_string());
+                }
+            }
+        }
+
+        let is_finished =
+            |q: &QueuedJob| matches!(q.job.status, JobStatus::Success | JobStatus::Error);
+        let mut excess = state
+            .jobs
+            .iter()
+            .filter(|q| is_finished(q))
+            .count()
+            .saturating_sub(MAX_FINISHED_JOBS);
+        state.jobs.retain(|q| {
+            if excess > 0 && is_finished(q) {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+    }
+
+    /// Moves a queued job to `position` among the queued jobs
+    fn move_queued(&self, id: &str, position: usize) -> AppResult<()> {
+        let mut state = self.state.lock().unwrap();
+        let queued = take_queued(&mut state.jobs, id)?;
+        let index = state
+            .jobs
+            .iter()
+            .enumerate()
+            .filter(|(_, q)| q.job.status == JobStatus::Queued)
+            .nth(position)
+            .map(|(i, _)| i)
+            .unwrap_or(state.jobs.len());
+        state.jobs.insert(index, queued);
+        Ok(())
+    }
+
+    fn remove_queued(&self, id: &str) -> AppResult<()> {
+        let mut state = self.state.lock().unwrap();
+        take_queued(&mut state.jobs, id)?;
+        Ok(())
+    }
+
+    /// Runs `f` while no clone is queued or running; none can start until it returns
+    pub fn while_idle<T>(&self, f: impl FnOnce() -> AppResult<T>) -> AppResult<T> {
+        let state = self.state.lock().unwrap();
+        if state
+            .jobs
+            .iter()
+            .any(|q| matches!(q.job.status, JobStatus::Queued | JobStatus::Running))
+        {
+            return Err(AppError::InvalidInput(
+                "Wait for the queued and running clones to finish first".to_string(),
+            ));
+        }
+        f()
+    }
+
+    fn emit_changed(&self, app: &AppHandle) {
+        let _ = app.emit("jobs-changed", self.list());
+    }
+}
+
+/// Removes a job that has not started yet from the list and returns it
+fn take_queued(jobs: &mut Vec<QueuedJob>, id: &str) -> AppResult<QueuedJob> {
+    let index = jobs
+        .iter()
+        .position(|q| q.job.id == id)
+        .ok_or_else(|| AppError::JobNotFound { id: id.to_string() })?;
+    if jobs[index].job.status != JobStatus::Queued {
+        return Err(AppError::InvalidInput(
+            "Only jobs that have not started can be changed".to_string(),
+        ));
+    }
+    Ok(jobs.remove(index))
+}
+
+#[tauri::command]
+pub fn list_jobs(queue: State<'_, JobQueue>) -> Vec<CloneJob> {
+    queue.list()
+}
+
+/// Moves a queued job to `position` among the jobs still waiting (0 = next)
+#[tauri::command]
+pub fn move_job(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    id: String,
+    position: usize,
+) -> AppResult<Vec<CloneJob>> {
+    queue.move_queued(&id, position)?;
+    queue.pump(&app);
+    Ok(queue.list())
+}
+
+/// Drops a job that has not started yet
+#[tauri::command]
+pub fn remove_job(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    id: String,
+) -> AppResult<Vec<CloneJob>> {
+    queue.remove_queued(&id)?;
+    queue.pump(&app);
+    Ok(queue.list())
+}
+
+#[tauri::command]
+pub fn get_job_concurrency(queue: State<'_, JobQueue>) -> usize {
+    queue.state.lock().unwrap().concurrency
+}
+
+/// Saves the number of clones allowed to run at once and starts any that now fit
+#[tauri::command]
+pub fn set_job_concurrency(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    limit: usize,
+) -> AppResult<usize> {
+    if limit == 0 {
+        return Err(AppError::InvalidInput(
+            "At least one clone must be allowed to run".to_string(),
+        ));
+    }
+
+    Store::open()?.set_job_concurrency(limit)?;
+    queue.state.lock().unwrap().concurrency = limit;
+    queue.pump(&app);
+    Ok(limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CloneType;
+
+    fn job(id: &str, status: JobStatus, locks: &[&str]) -> QueuedJob {
+        QueuedJob {
+            job: CloneJob {
+                id: id.to_string(),
+                source_name: "source".to_string(),
+                destination_name: "destination".to_string(),
+                clone_type: CloneType::Both,
+                status,
+                locks: locks.iter().map(|l| l.to_string()).collect(),
+                queued_at: Utc::now(),
+                started_at: None,
+                finished_at: None,
+                error_message: None,
+            },
+            task: None,
+        }
+    }
+
+    #[test]
+    fn starts_jobs_in_order_within_limits() {
+        let jobs = vec![
+            job("running", JobStatus::Running, &["db1:5432/app"]),
+            job("same-db", JobStatus::Queued, &["db1:5432/app"]),
+            job("other-db", JobStatus::Queued, &["db2:5432/app"]),
+            job("behind-same-db", JobStatus::Queued, &["db2:5432/app"]),
+            job("third-db", JobStatus::Queued, &["db3:5432/app"]),
+        ];
+
+        assert_eq!(startable(&jobs, 3), vec![2, 4]);
+        assert_eq!(startable(&jobs, 2), vec![2]);
+        assert!(startable(&jobs, 1).is_empty());
+    }
+
+    #[test]
+    fn waiting_job_holds_all_of_its_destinations() {
+        let jobs = vec![
+            job("running", JobStatus::Running, &["db1:5432/app"]),
+            job(
+                "fan-out",
+                JobStatus::Queued,
+                &["db1:5432/app", "db2:5432/app"],
+            ),
+            job("second", JobStatus::Queued, &["db2:5432/app"]),
+            job("done", JobStatus::Success, &["db3:5432/app"]),
+            job("third", JobStatus::Queued, &["db3:5432/app"]),
+        ];
+
+        assert_eq!(startable(&jobs, 4), vec![4]);
+    }
+}
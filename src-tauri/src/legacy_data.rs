@@ -0,0 +1,171 @@
+//! Reader for the JSON data file used before the SQLite store.
+//! Kept to import existing installs; files are upgraded to the last JSON version first.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::types::{AppData, APP_DATA_VERSION};
+
+const APP_DATA_FILE: &str = "db-clone-data.json";
+
+/// The JSON data file lived in the directory that now holds `database`
+pub fn legacy_data_path(database: &Path) -> PathBuf {
+    database.with_file_name(APP_DATA_FILE)
+}
+
+/// Upgrades a file from version N to N + 1; `MIGRATIONS[n]` handles version `n`
+type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1];
+
+/// v0 files have no `version` field and may predate tags and saved operations
+fn migrate_v0_to_v1(data: &mut Map<String, Value>) -> Result<(), String> {
+    for key in ["profiles", "history", "tags", "saved_operations"] {
+        let value = data.entry(key).or_insert_with(|| Value::Array(Vec::new()));
+        if !value.is_array() {
+            return Err(format!("`{}` must be a list", key));
+        }
+    }
+    Ok(())
+}
+
+pub enum ParseError {
+    Invalid(String),
+    TooNew(u32),
+}
+
+/// Brings a raw data file up to `APP_DATA_VERSION`, one migration at a time
+fn migrate(mut value: Value) -> Result<Value, ParseError> {
+    let data = value
+        .as_object_mut()
+        .ok_or_else(|| ParseError::Invalid("expected a JSON object".to_string()))?;
+
+    let version = match data.get("version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| ParseError::Invalid("`version` must be a number".to_string()))?,
+    };
+
+    if version > APP_DATA_VERSION {
+        return Err(ParseError::TooNew(version));
+    }
+
+    for migration in &MIGRATIONS[version as usize..APP_DATA_VERSION as usize] {
+        migration(data).map_err(ParseError::Invalid)?;
+    }
+    data.insert("version".to_string(), Value::from(APP_DATA_VERSION));
+
+    Ok(value)
+}
+
+pub fn parse_app_data(content: &str) -> Result<AppData, ParseError> {
+    let value: Value =
+        serde_json::from_str(content).map_err(|e| ParseError::Invalid(e.to_string()))?;
+
+    serde_json::from_value(migrate(value)?).map_err(|e| ParseError::Invalid(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> AppData {
+        match parse_app_data(content) {
+            Ok(data) => data,
+            Err(ParseError::Invalid(message)) => panic!("invalid fixture: {}", message),
+            Err(ParseError::TooNew(version)) => panic!("fixture too new: {}", version),
+        }
+    }
+
+    #[test]
+    fn loads_v0_file_without_tags_or_saved_operations() {
+        let data = parse(include_str!("../tests/fixtures/app-data-v0-initial.json"));
+
+        assert_eq!(data.version, APP_DATA_VERSION);
+        assert_eq!(data.profiles.len(), 2);
+        assert_eq!(data.profiles[0].name, "Production");
+        assert!(data.profiles[0].ssl);
+        assert_eq!(data.history.len(), 1);
+        assert!(data.tags.is_empty());
+        assert!(data.saved_operations.is_empty());
+    }
+
+    #[test]
+    fn loads_v0_file_with_tags_and_saved_operations() {
+        let data = parse(include_str!("../tests/fixtures/app-data-v0.json"));
+
+        assert_eq!(data.version, APP_DATA_VERSION);
+        assert_eq!(data.profiles.len(), 2);
+        assert_eq!(data.tags.len(), 1);
+        assert_eq!(
+            data.profiles[0].tag_id.as_deref(),
+            Some(data.tags[0].id.as_str())
+        );
+        assert_eq!(data.saved_operations.len(), 1);
+        assert_eq!(data.saved_operations[0].name, "Refresh staging");
+        assert_eq!(data.history[0].logs.len(), 3);
+    }
+
+    #[test]
+    fn loads_v1_file_unchanged() {
+        let content = include_str!("../tests/fixtures/app-data-v1.json");
+        let data = parse(content);
+
+        assert_eq!(data.version, 1);
+        assert_eq!(data.profiles.len(), 2);
+        assert_eq!(
+            data.history[0].error_code.as_deref(),
+            Some("RESTORE_FAILED")
+        );
+
+        // Fields added after the JSON file was retired load as null
+        let mut original: Value = serde_json::from_str(content).unwrap();
+        for entry in original["history"].as_array_mut().unwrap() {
+            entry["verifiedTables"] = Value::Null;
+            entry["options"] = Value::Null;
+            entry["environment"] = Value::Null;
+            entry["copiedRows"] = Value::Null;
+            entry["databaseResults"] = Value::Null;
+            entry["destinationResults"] = Value::Null;
+        }
+        for operation in original["saved_operations"].as_array_mut().unwrap() {
+            operation["masking"] = Value::Null;
+            operation["schedule"] = Value::Null;
+            operation["lastScheduledAt"] = Value::Null;
+            operation["options"] = Value::Null;
+        }
+        assert_eq!(serde_json::to_value(&data).unwrap(), original);
+    }
+
+    #[test]
+    fn rejects_files_from_newer_versions() {
+        let content = format!(
+            r#"{{"version": {}, "profiles": [], "history": [], "tags": [], "saved_operations": []}}"#,
+            APP_DATA_VERSION + 1
+        );
+
+        assert!(matches!(
+            parse_app_data(&content),
+            Err(ParseError::TooNew(v)) if v == APP_DATA_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn reports_malformed_files_as_invalid() {
+        assert!(matches!(
+            parse_app_data(r#"{"profiles": [{"id": 1}], "history": []}"#),
+            Err(ParseError::Invalid(_))
+        ));
+        assert!(matches!(
+            parse_app_data(r#"{"profiles": {}, "history": []}"#),
+            Err(ParseError::Invalid(_))
+        ));
+        assert!(matches!(
+            parse_app_data("not json"),
+            Err(ParseError::Invalid(_))
+        ));
+    }
+}
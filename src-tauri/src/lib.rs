@@ -1,21 +1,73 @@
+// Without the app, the Tauri commands and what only they use are never called
+#![cfg_attr(not(feature = "gui"), allow(dead_code))]
+
+#[cfg(feature = "gui")]
+mod app_data;
+mod cli;
+#[cfg(feature = "gui")]
 mod clone;
 mod command_helper;
 mod connection;
+/// Clone and schema export logic with no dependency on the app window. Progress and
+/// log lines go to a `ProgressSink`; the Tauri commands and the CLI supply their own.
+pub mod engine;
+mod error;
+mod export;
+mod globals;
+mod history;
+mod instance;
+#[cfg(feature = "gui")]
+mod jobs;
+mod legacy_data;
+mod masking;
+mod ownership;
+mod pg_client;
 mod pg_tools;
+mod pii;
 mod profiles;
+mod scheduler;
+#[cfg(feature = "gui")]
 mod schema;
+mod schema_rename;
+mod selection;
 mod storage;
+mod subset;
+mod table_copy;
 mod types;
 
-use clone::{clear_history, get_history, get_history_entry, start_clone};
-use connection::{check_pg_tools, get_database_structure, test_connection, test_connection_by_id};
+#[cfg(feature = "gui")]
+use app_data::{get_app_data_backup_error, list_app_data_backups, restore_app_data_backup};
+#[cfg(feature = "gui")]
+use clone::{rerun_history_entry, start_clone};
+#[cfg(feature = "gui")]
+use connection::{
+    check_pg_tools, get_database_structure, list_databases, test_connection, test_connection_by_id,
+};
+#[cfg(feature = "gui")]
+use export::export_history;
+#[cfg(feature = "gui")]
+use globals::clone_globals;
+#[cfg(feature = "gui")]
+use history::{
+    clear_history, delete_history_entry, get_history, get_history_entry, get_history_retention,
+    get_history_stats, query_history, set_history_retention,
+};
+#[cfg(feature = "gui")]
+use jobs::{get_job_concurrency, list_jobs, move_job, remove_job, set_job_concurrency, JobQueue};
+#[cfg(feature = "gui")]
+use pii::scan_pii;
+#[cfg(feature = "gui")]
 use profiles::{
     create_profile, create_saved_operation, create_tag, delete_profile, delete_saved_operation,
-    delete_tag, get_profile, get_profiles, get_saved_operations, get_tags, update_profile,
-    update_tag,
+    delete_tag, get_profile, get_profiles, get_saved_operations, get_tags, set_operation_schedule,
+    update_profile, update_tag,
 };
+#[cfg(feature = "gui")]
+use scheduler::{get_missed_run_policy, preview_schedule, set_missed_run_policy};
+#[cfg(feature = "gui")]
 use schema::download_schema;
 
+#[cfg(feature = "gui")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -25,6 +77,12 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_notification::init())
+        .manage(JobQueue::load())
+        .setup(|app| {
+            engine::recover_interrupted_clones();
+            scheduler::start(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Profile commands
             get_profiles,
@@ -41,19 +99,50 @@ pub fn run() {
             get_saved_operations,
             create_saved_operation,
             delete_saved_operation,
+            set_operation_schedule,
+            preview_schedule,
+            get_missed_run_policy,
+            set_missed_run_policy,
             // Connection commands
             check_pg_tools,
             test_connection,
             test_connection_by_id,
             get_database_structure,
+            list_databases,
+            scan_pii,
             // Clone commands
             start_clone,
+            rerun_history_entry,
+            clone_globals,
+            // Job queue commands
+            list_jobs,
+            move_job,
+            remove_job,
+            get_job_concurrency,
+            set_job_concurrency,
+            // History commands
             get_history,
+            query_history,
             get_history_entry,
+            delete_history_entry,
             clear_history,
+            get_history_stats,
+            get_history_retention,
+            set_history_retention,
+            export_history,
             // Schema commands
             download_schema,
+            // App data commands
+            list_app_data_backups,
+            get_app_data_backup_error,
+            restore_app_data_backup,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+/// Runs the `db-clone` command line tool on the same app data and returns its
+/// exit code
+pub fn run_cli() -> i32 {
+    cli::run()
+}
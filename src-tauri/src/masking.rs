@@ -0,0 +1,318 @@
+use tokio_postgres::Client;
+
+use crate::error::{AppError, AppResult};
+use crate::table_copy::{quote_ident, resolve_table, TableName};
+use crate::types::{MaskStrategy, MaskingOptions, MaskingRule};
+
+/// Masking rules resolved against the source, grouped by table
+#[derive(Debug, Default)]
+pub struct Masking {
+    seed: String,
+    tables: Vec<(TableName, Vec<MaskedColumn>)>,
+}
+
+/// A masking rule and the type of its column in the source
+#[derive(Debug, Clone)]
+struct MaskedColumn {
+    rule: MaskingRule,
+    /// As `format_type` prints it, e.g. `character varying(20)`
+    data_type: String,
+}
+
+/// A SQL string literal
+fn literal(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// Whether `strategy` yields values a column of `data_type` accepts; `category` is
+/// the type's `pg_type.typcategory`
+fn fits(strategy: &MaskStrategy, data_type: &str, category: &str) -> bool {
+    let text = category == "S";
+    match strategy {
+        MaskStrategy::FakeEmail => text,
+        // An md5 also reads as a uuid
+        MaskStrategy::Hash => text || data_type == "uuid",
+        // Only digits change, so numbers and uuids still parse
+        MaskStrategy::KeepFormat => text || category == "N" || data_type == "uuid",
+        _ => true,
+    }
+}
+
+/// Type of a masked column, checked against the rule's strategy
+async fn column_type(client: &Client, table: &TableName, rule: &MaskingRule) -> AppResult<String> {
+    let row = client
+        .query_opt(
+            "SELECT format_type(a.atttypid, a.atttypmod), t.typcategory::text
+             FROM pg_attribute a JOIN pg_type t ON t.oid = a.atttypid
+             WHERE a.attrelid = $1::text::regclass AND a.attname = $2
+               AND a.attnum > 0 AND NOT a.attisdropped",
+            &[&table.qualified(), &rule.column],
+        )
+        .await
+        .map_err(|e| AppError::query("Failed to read masked columns", &e))?
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!(
+                "Column {} not found in {}",
+                rule.column, rule.table
+            ))
+        })?;
+    let (data_type, category): (String, String) = (row.get(0), row.get(1));
+
+    if !fits(&rule.strategy, &data_type, &category) {
+        return Err(AppError::InvalidInput(format!(
+            "Column {} of {} is {}, which its masking strategy cannot produce",
+            rule.column, rule.table, data_type
+        )));
+    }
+    if let MaskStrategy::Constant { value } = &rule.strategy {
+        client
+            .query_one(&format!("SELECT $1::text::{}", data_type), &[value])
+            .await
+            .map_err(|_| {
+                AppError::InvalidInput(format!(
+                    "Masking value '{}' is not a valid {} for column {} of {}",
+                    value, data_type, rule.column, rule.table
+                ))
+            })?;
+    }
+    Ok(data_type)
+}
+
+impl Masking {
+    pub async fn resolve(client: &Client, options: &MaskingOptions) -> AppResult<Self> {
+        let mut tables: Vec<(TableName, Vec<MaskedColumn>)> = Vec::new();
+        for rule in &options.rules {
+            let table = resolve_table(client, &rule.table).await?;
+            let column = MaskedColumn {
+                rule: rule.clone(),
+                data_type: column_type(client, &table, rule).await?,
+            };
+            match tables.iter_mut().find(|(t, _)| *t == table) {
+                Some((_, columns)) => columns.push(column),
+                None => tables.push((table, vec![column])),
+            }
+        }
+
+        Ok(Self {
+            seed: options.seed.clone(),
+            tables,
+        })
+    }
+
+    /// Drops the rules of tables whose rows are not copied at all
+    pub fn exclude_data(&mut self, excluded: impl Fn(&TableName) -> bool) {
+        self.tables.retain(|(table, _)| !excluded(table));
+    }
+
+    /// Tables with at least one masked column
+    pub fn tables(&self) -> impl Iterator<Item = &TableName> {
+        self.tables.iter().map(|(table, _)| table)
+    }
+
+    fn rules_for(&self, table: &str) -> &[MaskedColumn] {
+        self.tables
+            .iter()
+            .find(|(t, _)| t.qualified() == table)
+            .map(|(_, columns)| columns.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Value a column is replaced with; `t` is the row being copied. Generated text is
+    /// cast to the column's type, which also trims it to a `varchar` length.
+    fn expression(&self, masked: &MaskedColumn, shuffle: &str) -> String {
+        let seed = literal(&self.seed);
+        let value = format!("t.{}", quote_ident(&masked.rule.column));
+        let generated = match &masked.rule.strategy {
+            MaskStrategy::FakeEmail => format!(
+                "'user_' || left(md5({} || {}::text), 12) || '@example.com'",
+                seed, value
+            ),
+            MaskStrategy::Hash => format!("md5({} || {}::text)", seed, value),
+            MaskStrategy::Null => return "NULL".to_string(),
+            MaskStrategy::Constant { value } => literal(value),
+            MaskStrategy::Shuffle => return format!("{}.value", shuffle),
+            // Each digit is derived from the value and its position, so equal values stay equal
+            MaskStrategy::KeepFormat => format!(
+                "CASE WHEN {value} IS NULL THEN NULL ELSE coalesce((
+                    SELECT string_agg(CASE WHEN d.ch BETWEEN '0' AND '9'
+                        THEN (get_byte(decode(md5({seed} || {value}::text || d.i::text), 'hex'), 0) % 10)::text
+                        ELSE d.ch END, '' ORDER BY d.i)
+                    FROM unnest(string_to_array({value}::text, NULL)) WITH ORDINALITY AS d(ch, i)
+                ), '') END",
+                value = value,
+                seed = seed
+            ),
+            MaskStrategy::Expression { sql } => return format!("({})", sql),
+        };
+        format!("({})::{}", generated, masked.data_type)
+    }
+
+    /// The query feeding COPY for `table`: `columns` in order, masked ones replaced
+    pub fn select_query(
+        &self,
+        table: &str,
+        columns: &[String],
+        condition: Option<&str>,
+    ) -> AppResult<String> {
+        let rules = self.rules_for(table);
+        if let Some(masked) = rules.iter().find(|m| !columns.contains(&m.rule.column)) {
+            return Err(AppError::InvalidInput(format!(
+                "Column {} not found in {}",
+                masked.rule.column, masked.rule.table
+            )));
+        }
+
+        let mut shuffles = Vec::new();
+        let values = columns
+            .iter()
+            .map(
+                |column| match rules.iter().find(|m| &m.rule.column == column) {
+                    Some(masked) => {
+                        let alias = format!("s{}", shuffles.len());
+                        if matches!(masked.rule.strategy, MaskStrategy::Shuffle) {
+                            shuffles.push((alias.clone(), column));
+                        }
+                        self.expression(masked, &alias)
+                    }
+                    None => format!("t.{}", quote_ident(column)),
+                },
+            )
+            .collect::<Vec<_>>()
+            .join(", ");
+        let filter = condition
+            .map(|c| format!(" WHERE {}", c))
+            .unwrap_or_default();
+
+        if shuffles.is_empty() {
+            return Ok(format!("SELECT {} FROM {} t{}", values, table, filter));
+        }
+
+        // Shuffled columns take the value of another row, picked by a seeded ordering
+        let mut query = format!(
+            "WITH source_rows AS (SELECT t.*, row_number() OVER () AS row_index FROM {} t{})",
+            table, filter
+        );
+        for (alias, column) in &shuffles {
+            query.push_str(&format!(
+                ", {alias} AS (SELECT t.{column} AS value, \
+                 row_number() OVER (ORDER BY md5({seed} || t.row_index::text)) AS row_index \
+                 FROM source_rows t)",
+                alias = alias,
+                column = quote_ident(column),
+                seed = literal(&self.seed)
+            ));
+        }
+        query.push_str(&format!(" SELECT {} FROM source_rows t", values));
+        for (alias, _) in &shuffles {
+            query.push_str(&format!(
+                " JOIN {alias} ON {alias}.row_index = t.row_index",
+                alias = alias
+            ));
+        }
+        Ok(query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn masking(rules: Vec<(&str, &str, MaskStrategy)>) -> Masking {
+        let table = TableName {
+            schema: "public".to_string(),
+            name: "users".to_string(),
+        };
+        let rules = rules
+            .into_iter()
+            .map(|(column, data_type, strategy)| MaskedColumn {
+                rule: MaskingRule {
+                    table: "users".to_string(),
+                    column: column.to_string(),
+                    strategy,
+                },
+                data_type: data_type.to_string(),
+            })
+            .collect();
+        Masking {
+            seed: "s'1".to_string(),
+            tables: vec![(table, rules)],
+        }
+    }
+
+    fn columns() -> Vec<String> {
+        ["id", "email", "phone"].map(String::from).to_vec()
+    }
+
+    #[test]
+    fn masks_columns_in_place() {
+        let masking = masking(vec![
+            ("email", "text", MaskStrategy::FakeEmail),
+            (
+                "phone",
+                "text",
+                MaskStrategy::Constant {
+                    value: "n/a".to_string(),
+                },
+            ),
+        ]);
+
+        let query = masking
+            .select_query("\"public\".\"users\"", &columns(), Some("t.id < 10"))
+            .unwrap();
+        assert_eq!(
+            query,
+            "SELECT t.\"id\", ('user_' || left(md5('s''1' || t.\"email\"::text), 12) || '@example.com')::text, \
+             ('n/a')::text FROM \"public\".\"users\" t WHERE t.id < 10"
+        );
+
+        // Tables without rules are copied as they are
+        let query = masking
+            .select_query("\"public\".\"orders\"", &columns(), None)
+            .unwrap();
+        assert_eq!(
+            query,
+            "SELECT t.\"id\", t.\"email\", t.\"phone\" FROM \"public\".\"orders\" t"
+        );
+    }
+
+    #[test]
+    fn shuffles_through_a_seeded_ordering() {
+        let masking = masking(vec![("phone", "text", MaskStrategy::Shuffle)]);
+
+        let query = masking
+            .select_query("\"public\".\"users\"", &columns(), None)
+            .unwrap();
+        assert!(query.starts_with("WITH source_rows AS (SELECT t.*, row_number() OVER ()"));
+        assert!(query.contains("ORDER BY md5('s''1' || t.row_index::text)"));
+        assert!(query.ends_with(
+            "SELECT t.\"id\", t.\"email\", s0.value FROM source_rows t JOIN s0 ON s0.row_index = t.row_index"
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_columns() {
+        let masking = masking(vec![("ssn", "text", MaskStrategy::Null)]);
+        assert!(masking
+            .select_query("\"public\".\"users\"", &columns(), None)
+            .is_err());
+    }
+
+    #[test]
+    fn fits_generated_values_to_the_column_type() {
+        assert!(!fits(&MaskStrategy::Hash, "integer", "N"));
+        assert!(!fits(&MaskStrategy::FakeEmail, "uuid", "U"));
+        assert!(fits(&MaskStrategy::Hash, "uuid", "U"));
+        assert!(fits(&MaskStrategy::KeepFormat, "bigint", "N"));
+        assert!(!fits(&MaskStrategy::KeepFormat, "date", "D"));
+
+        let masking = masking(vec![
+            ("id", "bigint", MaskStrategy::KeepFormat),
+            ("email", "character varying(20)", MaskStrategy::FakeEmail),
+        ]);
+        let query = masking
+            .select_query("\"public\".\"users\"", &columns(), None)
+            .unwrap();
+        assert!(query.contains("'') END)::bigint, "));
+        assert!(query.contains("'@example.com')::character varying(20), t.\"phone\""));
+    }
+}
@@ -0,0 +1,272 @@
+use std::path::Path;
+use std::process::Stdio;
+
+use tokio_postgres::Client;
+
+use crate::command_helper::create_async_command;
+use crate::error::{AppError, AppResult};
+use crate::globals::{dump_globals, map_roles, parse_roles, rename_role};
+use crate::pg_client::describe_error;
+use crate::types::{ConnectionProfile, RoleMapping};
+
+/// Roles that own objects in, or hold privileges on, the current database
+const REFERENCED_ROLES_QUERY: &str = r#"
+    SELECT DISTINCT r.rolname::text
+    FROM pg_shdepend d
+    JOIN pg_roles r ON r.oid = d.refobjid
+    WHERE d.refclassid = 'pg_authid'::regclass
+    AND d.dbid = (SELECT oid FROM pg_database WHERE datname = current_database())
+    UNION
+    SELECT pg_get_userbyid(datdba)::text FROM pg_database WHERE datname = current_database()
+    ORDER BY 1;
+"#;
+
+/// Destination name of a source role
+fn mapped<'a>(role: &'a str, mappings: &'a [RoleMapping]) -> &'a str {
+    mappings
+        .iter()
+        .find(|m| m.from == role)
+        .map(|m| m.to.as_str())
+        .unwrap_or(role)
+}
+
+/// Ownership and privilege statements in pg_restore's SQL output, default privileges
+/// included. Lines inside string literals, quoted identifiers or dollar-quoted function
+/// bodies are skipped, so a body line starting with `GRANT` is not taken for one.
+fn ownership_statements(sql: &str) -> Vec<&str> {
+    let mut quote = None;
+    let mut statements = Vec::new();
+    for line in sql.lines() {
+        if quote.is_none()
+            && (line.starts_with("ALTER DEFAULT PRIVILEGES ")
+                || (line.starts_with("ALTER ") && line.contains(" OWNER TO "))
+                || line.starts_with("GRANT ")
+                || line.starts_with("REVOKE "))
+        {
+            statements.push(line);
+        }
+        quote = open_quote(line, quote);
+    }
+    statements
+}
+
+/// The quote still open at the end of `line`, given the one open at its start: `'`,
+/// `"` or a dollar-quote tag such as `$_$`
+fn open_quote(line: &str, mut quote: Option<String>) -> Option<String> {
+    let mut rest = line;
+    loop {
+        if let Some(close) = &quote {
+            let Some(end) = rest.find(close.as_str()) else {
+                return quote;
+            };
+            rest = &rest[end + close.len()..];
+            quote = None;
+            continue;
+        }
+
+        let start = rest.find(['\'', '"', '$', '-'])?;
+        let (before, from) = rest.split_at(start);
+        if from.starts_with("--") {
+            return None;
+        }
+        let skip = if from.starts_with('$') {
+            // `$` inside an identifier such as `a$b` does not open a quote
+            let in_word = before
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_');
+            match dollar_tag(from).filter(|_| !in_word) {
+                Some(tag) => {
+                    quote = Some(tag.to_string());
+                    tag.len()
+                }
+                None => 1,
+            }
+        } else if from.starts_with(['\'', '"']) {
+            quote = Some(from[..1].to_string());
+            1
+        } else {
+            1
+        };
+        rest = &from[skip..];
+    }
+}
+
+/// The `$tag$` at the start of `text`, if any
+fn dollar_tag(text: &str) -> Option<&str> {
+    let name = &text[1..];
+    let len = name
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(name.len());
+    let starts_with_digit = name.starts_with(|c: char| c.is_ascii_digit());
+    (!starts_with_digit && name[len..].starts_with('$')).then(|| &text[..len + 2])
+}
+
+/// Creates the roles the source database refers to that the destination lacks,
+/// copying their attributes but not their passwords. Roles that cannot be created,
+/// usually for lack of CREATEROLE, are reported and skipped.
+pub async fn create_missing_roles(
+    pg_dumpall: &str,
+    source: &ConnectionProfile,
+    source_client: &Client,
+    destination_client: &Client,
+    mappings: &[RoleMapping],
+    add_log: &(dyn Fn(&str) + Sync),
+) -> AppResult<()> {
+    let referenced: Vec<String> = source_client
+        .query(REFERENCED_ROLES_QUERY, &[])
+        .await
+        .map_err(|e| AppError::query("Failed to get roles", &e))?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    let existing: Vec<String> = destination_client
+        .query("SELECT rolname::text FROM pg_roles", &[])
+        .await
+        .map_err(|e| AppError::query("Failed to get roles", &e))?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+
+    let missing: Vec<&String> = referenced
+        .iter()
+        .filter(|r| !existing.iter().any(|e| e == mapped(r, mappings)))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let globals = dump_globals(
+        pg_dumpall,
+        source,
+        &["--globals-only", "--no-role-passwords"],
+    )
+    .await?;
+    let definitions = parse_roles(&globals);
+    for role in missing {
+        let target = mapped(role, mappings);
+        let Some(definition) = definitions.iter().find(|d| &d.name == role) else {
+            add_log(&format!(
+                "[WARNING] Role {} not found in the source globals",
+                role
+            ));
+            continue;
+        };
+
+        // Per-role settings may name schemas that do not exist yet, so only attributes
+        let statements: Vec<String> = definition
+            .statements
+            .iter()
+            .filter(|s| s.starts_with("CREATE ROLE ") || s.contains(" WITH "))
+            .map(|s| rename_role(s, target))
+            .collect();
+        match destination_client
+            .batch_execute(&statements.join("\n"))
+            .await
+        {
+            Ok(_) => add_log(&format!("[SUCCESS] Created role {}", target)),
+            Err(e) => add_log(&format!(
+                "[WARNING] Could not create role {}: {}",
+                target,
+                describe_error(&e)
+            )),
+        }
+    }
+    Ok(())
+}
+
+/// Applies the dump's owners and grants with roles mapped, one statement at a time,
+/// and reports the ones the destination rejected
+pub async fn apply(
+    pg_restore: &str,
+    dump_path: &Path,
+    destination_client: &Client,
+    mappings: &[RoleMapping],
+    add_log: &(dyn Fn(&str) + Sync),
+) -> AppResult<()> {
+    let output = create_async_command(pg_restore)
+        .args(["--schema-only", "-f", "-"])
+        .arg(dump_path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .map_err(|e| AppError::process("Failed to read owners and privileges", e))?;
+    if !output.status.success() {
+        return Err(AppError::RestoreFailed {
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        });
+    }
+
+    let sql = String::from_utf8_lossy(&output.stdout);
+    let statements = ownership_statements(&sql);
+    let mut failed = Vec::new();
+    for statement in &statements {
+        let statement = map_roles(statement, mappings);
+        if let Err(e) = destination_client.batch_execute(&statement).await {
+            failed.push(format!("{} ({})", statement, describe_error(&e)));
+        }
+    }
+
+    add_log(&format!(
+        "[INFO] Applied {} of {} ownership and privilege statements",
+        statements.len() - failed.len(),
+        statements.len()
+    ));
+    if !failed.is_empty() {
+        add_log(&format!(
+            "[WARNING] {} grants could not be applied:\n{}",
+            failed.len(),
+            failed.join("\n")
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_owner_and_privilege_statements() {
+        let sql = "SET statement_timeout = 0;\n\
+                   CREATE TABLE public.users (id integer);\n\
+                   ALTER TABLE public.users OWNER TO app;\n\
+                   ALTER TABLE ONLY public.users ADD CONSTRAINT users_pkey PRIMARY KEY (id);\n\
+                   REVOKE ALL ON SCHEMA public FROM PUBLIC;\n\
+                   GRANT SELECT ON TABLE public.users TO reader;\n";
+        assert_eq!(
+            ownership_statements(sql),
+            [
+                "ALTER TABLE public.users OWNER TO app;",
+                "REVOKE ALL ON SCHEMA public FROM PUBLIC;",
+                "GRANT SELECT ON TABLE public.users TO reader;",
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_default_privileges_and_skips_quoted_lines() {
+        let sql = "-- Name: o'brien; Type: SCHEMA\n\
+                   CREATE FUNCTION public.share() RETURNS void\n\
+                   \x20   LANGUAGE plpgsql\n\
+                   \x20   AS $_$\n\
+                   BEGIN\n\
+                   GRANT SELECT ON public.users TO reader;\n\
+                   END\n\
+                   $_$;\n\
+                   ALTER FUNCTION public.share() OWNER TO app;\n\
+                   COMMENT ON TABLE public.users IS 'Users.\n\
+                   REVOKE nothing, this is text';\n\
+                   ALTER DEFAULT PRIVILEGES FOR ROLE app IN SCHEMA public GRANT SELECT ON TABLES TO reader;\n";
+        assert_eq!(
+            ownership_statements(sql),
+            [
+                "ALTER FUNCTION public.share() OWNER TO app;",
+                "ALTER DEFAULT PRIVILEGES FOR ROLE app IN SCHEMA public GRANT SELECT ON TABLES TO reader;",
+            ]
+        );
+        assert_eq!(open_quote("SELECT a$b$ FROM t", None), None);
+        assert_eq!(open_quote("AS $$", None).as_deref(), Some("$$"));
+    }
+}
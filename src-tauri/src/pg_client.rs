@@ -0,0 +1,174 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+use tokio_postgres::config::SslMode;
+use tokio_postgres::{Client, Config};
+use tokio_postgres_rustls::MakeRustlsConnect;
+
+use crate::error::{AppError, AppResult, ConnectionFailureKind};
+use crate::types::ConnectionProfile;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Accepts any server certificate, matching libpq's `sslmode=require`
+/// (encrypted, but the server identity is not verified).
+#[derive(Debug)]
+struct AcceptAnyServerCert(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyServerCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn make_tls_connector() -> AppResult<MakeRustlsConnect> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+    let config = ClientConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| AppError::ConnectionFailed {
+            kind: ConnectionFailureKind::Tls,
+            message: format!("Failed to configure TLS: {}", e),
+        })?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(AcceptAnyServerCert(provider)))
+        .with_no_client_auth();
+
+    Ok(MakeRustlsConnect::new(config))
+}
+
+/// Turns a driver error into a readable message, preferring the server's own text
+pub fn describe_error(error: &tokio_postgres::Error) -> String {
+    match error.as_db_error() {
+        Some(db_error) => db_error.message().to_string(),
+        None => error.to_string(),
+    }
+}
+
+/// Opens a native connection to PostgreSQL.
+/// With `ssl` the connection requires TLS, otherwise TLS is used only if the server offers it.
+pub async fn connect(
+    host: &str,
+    port: u16,
+    database: &str,
+    user: &str,
+    password: &str,
+    ssl: bool,
+) -> AppResult<Client> {
+    let mut config = Config::new();
+    config
+        .host(host)
+        .port(port)
+        .dbname(database)
+        .user(user)
+        .password(password)
+        .application_name("db-clone-app")
+        .connect_timeout(CONNECT_TIMEOUT)
+        .ssl_mode(if ssl {
+            SslMode::Require
+        } else {
+            SslMode::Prefer
+        });
+
+    let (client, connection) = config
+        .connect(make_tls_connector()?)
+        .await
+        .map_err(|e| AppError::from_connect_error(&e))?;
+
+    // The connection object drives the socket; it finishes once the client is dropped
+    tokio::spawn(async move {
+        let _ = connection.await;
+    });
+
+    Ok(client)
+}
+
+/// Opens a native connection using the settings of a saved profile
+pub async fn connect_profile(profile: &ConnectionProfile) -> AppResult<Client> {
+    connect(
+        &profile.host,
+        profile.port,
+        &profile.database,
+        &profile.user,
+        &profile.password,
+        profile.ssl,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reports_an_unreachable_server() {
+        // Nothing listens on port 1
+        let error = connect("127.0.0.1", 1, "postgres", "postgres", "", false)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            AppError::ConnectionFailed {
+                kind: ConnectionFailureKind::Refused,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn accepts_any_server_certificate() {
+        let verifier = AcceptAnyServerCert(Arc::new(rustls::crypto::ring::default_provider()));
+        let certificate = CertificateDer::from(vec![0u8; 16]);
+        let server = ServerName::try_from("db.example.com").unwrap();
+
+        assert!(verifier
+            .verify_server_cert(&certificate, &[], &server, &[], UnixTime::now())
+            .is_ok());
+        assert!(!verifier.supported_verify_schemes().is_empty());
+        assert!(make_tls_connector().is_ok());
+    }
+}
@@ -58,41 +58,43 @@ fn find_pg_install_dirs() -> Vec<PathBuf> {
     dirs
 }
 
-/// Encuentra el ejecutable psql
+/// Encuentra un ejecutable de PostgreSQL por nombre (sin extensión)
 /// Primero intenta encontrarlo en el PATH, luego busca en las instalaciones de PostgreSQL
-pub fn find_psql() -> Option<String> {
+fn find_pg_binary(name: &str) -> Option<String> {
     // Primero intentar en el PATH
-    if let Some(path) = find_in_path("psql") {
+    if let Some(path) = find_in_path(name) {
         return Some(path);
     }
 
     // Buscar en instalaciones de PostgreSQL (Windows)
     if cfg!(windows) {
         for bin_dir in find_pg_install_dirs() {
-            let psql_path = bin_dir.join("psql.exe");
-            if psql_path.exists() {
-                if let Some(path_str) = psql_path.to_str() {
-                    // Verificar que funciona
-                    if create_command(path_str).arg("--version").output().is_ok() {
-                        return Some(path_str.to_string());
-                    }
+            let binary_path = bin_dir.join(format!("{}.exe", name));
+            if !binary_path.exists() {
+                continue;
+            }
+            if let Some(path_str) = binary_path.to_str() {
+                // Verificar que funciona
+                if create_command(path_str).arg("--version").output().is_ok() {
+                    return Some(path_str.to_string());
                 }
             }
         }
     } else {
         // Linux/macOS rutas comunes
-        let unix_paths = vec![
-            "/usr/bin/psql",
-            "/usr/local/bin/psql",
-            "/opt/homebrew/bin/psql",
-            "/usr/local/pgsql/bin/psql",
+        let unix_dirs = [
+            "/usr/bin",
+            "/usr/local/bin",
+            "/opt/homebrew/bin",
+            "/usr/local/pgsql/bin",
         ];
 
-        for path in unix_paths {
-            if std::path::Path::new(path).exists() {
-                if create_command(path).arg("--version").output().is_ok() {
-                    return Some(path.to_string());
-                }
+        for dir in unix_dirs {
+            let path = format!("{}/{}", dir, name);
+            if std::path::Path::new(&path).exists()
+                && create_command(&path).arg("--version").output().is_ok()
+            {
+                return Some(path);
             }
         }
     }
@@ -100,88 +102,24 @@ pub fn find_psql() -> Option<String> {
     None
 }
 
+/// Encuentra el ejecutable psql
+pub fn find_psql() -> Option<String> {
+    find_pg_binary("psql")
+}
+
 /// Encuentra el ejecutable pg_dump
-/// Primero intenta encontrarlo en el PATH, luego busca en las instalaciones de PostgreSQL
 pub fn find_pg_dump() -> Option<String> {
-    // Primero intentar en el PATH
-    if let Some(path) = find_in_path("pg_dump") {
-        return Some(path);
-    }
-
-    // Buscar en instalaciones de PostgreSQL (Windows)
-    if cfg!(windows) {
-        for bin_dir in find_pg_install_dirs() {
-            let pg_dump_path = bin_dir.join("pg_dump.exe");
-            if pg_dump_path.exists() {
-                if let Some(path_str) = pg_dump_path.to_str() {
-                    // Verificar que funciona
-                    if create_command(path_str).arg("--version").output().is_ok() {
-                        return Some(path_str.to_string());
-                    }
-                }
-            }
-        }
-    } else {
-        // Linux/macOS rutas comunes
-        let unix_paths = vec![
-            "/usr/bin/pg_dump",
-            "/usr/local/bin/pg_dump",
-            "/opt/homebrew/bin/pg_dump",
-            "/usr/local/pgsql/bin/pg_dump",
-        ];
-
-        for path in unix_paths {
-            if std::path::Path::new(path).exists() {
-                if create_command(path).arg("--version").output().is_ok() {
-                    return Some(path.to_string());
-                }
-            }
-        }
-    }
-
-    None
+    find_pg_binary("pg_dump")
 }
 
 /// Encuentra el ejecutable pg_restore
-/// Primero intenta encontrarlo en el PATH, luego busca en las instalaciones de PostgreSQL
 pub fn find_pg_restore() -> Option<String> {
-    // Primero intentar en el PATH
-    if let Some(path) = find_in_path("pg_restore") {
-        return Some(path);
-    }
-
-    // Buscar en instalaciones de PostgreSQL (Windows)
-    if cfg!(windows) {
-        for bin_dir in find_pg_install_dirs() {
-            let pg_restore_path = bin_dir.join("pg_restore.exe");
-            if pg_restore_path.exists() {
-                if let Some(path_str) = pg_restore_path.to_str() {
-                    // Verificar que funciona
-                    if create_command(path_str).arg("--version").output().is_ok() {
-                        return Some(path_str.to_string());
-                    }
-                }
-            }
-        }
-    } else {
-        // Linux/macOS rutas comunes
-        let unix_paths = vec![
-            "/usr/bin/pg_restore",
-            "/usr/local/bin/pg_restore",
-            "/opt/homebrew/bin/pg_restore",
-            "/usr/local/pgsql/bin/pg_restore",
-        ];
-
-        for path in unix_paths {
-            if std::path::Path::new(path).exists() {
-                if create_command(path).arg("--version").output().is_ok() {
-                    return Some(path.to_string());
-                }
-            }
-        }
-    }
+    find_pg_binary("pg_restore")
+}
 
-    None
+/// Encuentra el ejecutable pg_dumpall
+pub fn find_pg_dumpall() -> Option<String> {
+    find_pg_binary("pg_dumpall")
 }
 
 /// Intenta encontrar un ejecutable en el PATH del sistema
@@ -217,9 +155,12 @@ fn find_in_path(executable: &str) -> Option<String> {
 
 /// Obtiene la versión del cliente PostgreSQL instalado
 pub fn get_pg_client_version() -> Option<String> {
-    let psql = find_psql()?;
+    get_tool_version(&find_psql()?)
+}
 
-    let output = create_command(&psql).arg("--version").output().ok()?;
+/// Obtiene la versión que reporta una herramienta con `--version`
+pub fn get_tool_version(path: &str) -> Option<String> {
+    let output = create_command(path).arg("--version").output().ok()?;
 
     if output.status.success() {
         let version_str = String::from_utf8_lossy(&output.stdout);
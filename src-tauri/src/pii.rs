@@ -0,0 +1,412 @@
+use std::net::IpAddr;
+use std::sync::OnceLock;
+
+use regex::Regex;
+use tokio_postgres::Client;
+
+use crate::connection::get_profile_by_id;
+use crate::error::{AppError, AppResult};
+use crate::pg_client::{connect_profile, describe_error};
+use crate::table_copy::{quote_ident, TableName};
+use crate::types::{PiiColumn, PiiKind, PiiScan, PiiSkippedTable};
+
+/// Rows sampled per table
+const SAMPLE_SIZE: i64 = 200;
+
+/// Columns scoring below this are left out of the report
+const MIN_CONFIDENCE: f64 = 0.4;
+
+/// A name hint no sampled value backs up is only reported from this weight up, so
+/// loose fragments like `tax` or `city` need matching values
+const MIN_NAME_ONLY_CONFIDENCE: f64 = 0.5;
+
+/// Types whose values are worth sampling
+const SAMPLED_TYPES: &[&str] = &["text", "character varying", "character", "inet", "cidr"];
+
+const COLUMNS_QUERY: &str = r#"
+    SELECT c.table_schema::text, c.table_name::text, c.column_name::text, c.data_type::text
+    FROM information_schema.columns c
+    JOIN information_schema.tables t
+        ON t.table_schema = c.table_schema AND t.table_name = c.table_name
+    WHERE c.table_schema NOT IN ('pg_catalog', 'information_schema')
+    AND t.table_type = 'BASE TABLE'
+    ORDER BY c.table_schema, c.table_name, c.ordinal_position;
+"#;
+
+/// Column name fragments and how strongly each suggests a kind
+const NAME_HINTS: &[(&str, PiiKind, f64)] = &[
+    ("email", PiiKind::Email, 0.6),
+    ("mail", PiiKind::Email, 0.5),
+    ("phone", PiiKind::Phone, 0.6),
+    ("mobile", PiiKind::Phone, 0.5),
+    ("cellphone", PiiKind::Phone, 0.6),
+    ("telephone", PiiKind::Phone, 0.6),
+    ("card", PiiKind::CreditCard, 0.5),
+    ("pan", PiiKind::CreditCard, 0.4),
+    ("iban", PiiKind::CreditCard, 0.6),
+    ("ssn", PiiKind::NationalId, 0.7),
+    ("dni", PiiKind::NationalId, 0.7),
+    ("nif", PiiKind::NationalId, 0.6),
+    ("ruc", PiiKind::NationalId, 0.6),
+    ("passport", PiiKind::NationalId, 0.6),
+    ("national", PiiKind::NationalId, 0.5),
+    ("tax", PiiKind::NationalId, 0.4),
+    ("taxid", PiiKind::NationalId, 0.6),
+    ("ip", PiiKind::IpAddress, 0.5),
+    ("ipaddress", PiiKind::IpAddress, 0.6),
+    ("firstname", PiiKind::Name, 0.6),
+    ("lastname", PiiKind::Name, 0.6),
+    ("surname", PiiKind::Name, 0.6),
+    ("fullname", PiiKind::Name, 0.6),
+    ("username", PiiKind::Name, 0.4),
+    ("name", PiiKind::Name, 0.3),
+    ("address", PiiKind::Address, 0.6),
+    ("street", PiiKind::Address, 0.6),
+    ("city", PiiKind::Address, 0.4),
+    ("zip", PiiKind::Address, 0.4),
+    ("postal", PiiKind::Address, 0.4),
+    ("postcode", PiiKind::Address, 0.4),
+    ("birth", PiiKind::BirthDate, 0.6),
+    ("birthday", PiiKind::BirthDate, 0.7),
+    ("dob", PiiKind::BirthDate, 0.7),
+    ("password", PiiKind::Secret, 0.7),
+    ("secret", PiiKind::Secret, 0.6),
+    ("token", PiiKind::Secret, 0.5),
+];
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).unwrap())
+}
+
+/// Best guess from the column name alone. `first_name`, `firstName` and `firstname`
+/// all match `firstname`, so both the words and the joined name are checked.
+fn name_hint(column: &str) -> Option<(PiiKind, f64)> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut previous_lower = false;
+    for ch in column.chars() {
+        if (!ch.is_alphanumeric() || (ch.is_uppercase() && previous_lower)) && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        if ch.is_alphanumeric() {
+            word.extend(ch.to_lowercase());
+        }
+        previous_lower = ch.is_lowercase();
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    let joined = words.concat();
+
+    NAME_HINTS
+        .iter()
+        .filter(|(hint, _, _)| words.iter().any(|w| w == hint) || joined == *hint)
+        .map(|&(_, kind, score)| (kind, score))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Luhn checksum used by payment card numbers
+fn passes_luhn(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match i % 2 {
+            0 => d,
+            _ if d * 2 > 9 => d * 2 - 9,
+            _ => d * 2,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// What a single sampled value looks like, if anything sensitive
+fn classify_value(value: &str) -> Option<PiiKind> {
+    static EMAIL: OnceLock<Regex> = OnceLock::new();
+    static NATIONAL_ID: OnceLock<Regex> = OnceLock::new();
+    static PHONE: OnceLock<Regex> = OnceLock::new();
+    static CARD: OnceLock<Regex> = OnceLock::new();
+    static DATE: OnceLock<Regex> = OnceLock::new();
+
+    let value = value.trim();
+    if regex(&EMAIL, r"^[^@\s]+@[^@\s]+\.[A-Za-z]{2,}$").is_match(value) {
+        return Some(PiiKind::Email);
+    }
+    // inet values come back with a netmask suffix
+    if value.split('/').next()?.parse::<IpAddr>().is_ok() {
+        return Some(PiiKind::IpAddress);
+    }
+    if regex(&CARD, r"^\d{4}([ -]?\d{3,4}){2,4}$").is_match(value) {
+        let digits: Vec<u32> = value.chars().filter_map(|c| c.to_digit(10)).collect();
+        if (13..=19).contains(&digits.len()) && passes_luhn(&digits) {
+            return Some(PiiKind::CreditCard);
+        }
+    }
+    // US SSN, or 8 digits plus a check letter (Spanish DNI)
+    if regex(&NATIONAL_ID, r"^(\d{3}-\d{2}-\d{4}|\d{8}-?[A-Za-z])$").is_match(value) {
+        return Some(PiiKind::NationalId);
+    }
+    // Dates such as 2024-01-15 or 15.01.2024 are made of the same characters as phones
+    if regex(
+        &DATE,
+        r"^(\d{4}[-/.]\d{1,2}[-/.]\d{1,2}|\d{1,2}[-/.]\d{1,2}[-/.]\d{4})$",
+    )
+    .is_match(value)
+    {
+        return None;
+    }
+    // Bare digit runs are just as often order numbers or SKUs, so a phone needs a
+    // leading `+` or separators; `score` accepts bare ones under a phone-like name
+    if regex(&PHONE, r"^\+?[\d\s().-]{7,20}$").is_match(value)
+        && value.chars().filter(char::is_ascii_digit).count() >= 7
+        && !value.chars().all(|c| c.is_ascii_digit())
+    {
+        return Some(PiiKind::Phone);
+    }
+    None
+}
+
+/// A run of 7 to 15 digits, which could be a phone number without formatting
+fn is_bare_phone(value: &str) -> bool {
+    let value = value.trim();
+    (7..=15).contains(&value.len()) && value.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Combines the name hint with how many sampled values matched each kind
+fn score(
+    name: Option<(PiiKind, f64)>,
+    data_type: &str,
+    values: &[String],
+) -> Option<(PiiKind, f64, usize)> {
+    let phone_name = matches!(name, Some((PiiKind::Phone, _)));
+    let mut matches: Vec<(PiiKind, usize)> = Vec::new();
+    for kind in values.iter().filter_map(|v| {
+        classify_value(v).or_else(|| (phone_name && is_bare_phone(v)).then_some(PiiKind::Phone))
+    }) {
+        match matches.iter_mut().find(|(k, _)| *k == kind) {
+            Some((_, count)) => *count += 1,
+            None => matches.push((kind, 1)),
+        }
+    }
+
+    let mut candidates: Vec<(PiiKind, f64, usize)> = matches
+        .iter()
+        .map(|&(kind, count)| {
+            // A handful of values is weak evidence on its own
+            let weight = (values.len().min(20) as f64) / 20.0;
+            let ratio = count as f64 / values.len() as f64;
+            (kind, 0.9 * ratio * weight, count)
+        })
+        .collect();
+    if matches!(data_type, "inet" | "cidr") {
+        candidates.push((PiiKind::IpAddress, 0.9, 0));
+    }
+    if let Some((kind, hint)) = name {
+        match candidates.iter_mut().find(|(k, _, _)| *k == kind) {
+            Some((_, confidence, _)) => *confidence = (confidence.max(hint) + 0.2).min(1.0),
+            None if hint >= MIN_NAME_ONLY_CONFIDENCE => candidates.push((kind, hint, 0)),
+            None => {}
+        }
+    }
+
+    candidates
+        .into_iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .filter(|&(_, confidence, _)| confidence >= MIN_CONFIDENCE)
+}
+
+/// Up to `SAMPLE_SIZE` non-null values per column, as text. Fails with the server's
+/// reason when the table cannot be read.
+async fn sample_values(
+    client: &Client,
+    table: &TableName,
+    columns: &[String],
+) -> Result<Vec<Vec<String>>, String> {
+    let query = format!(
+        "SELECT {} FROM {} LIMIT {}",
+        columns
+            .iter()
+            .map(|c| format!("{}::text", quote_ident(c)))
+            .collect::<Vec<_>>()
+            .join(", "),
+        table.qualified(),
+        SAMPLE_SIZE
+    );
+    let rows = client
+        .query(&query, &[])
+        .await
+        .map_err(|e| describe_error(&e))?;
+
+    Ok((0..columns.len())
+        .map(|i| {
+            rows.iter()
+                .filter_map(|row| row.get::<_, Option<String>>(i))
+                .collect()
+        })
+        .collect())
+}
+
+/// Ranks columns of a database by how likely they hold personal data. Tables the
+/// role cannot read, for lack of a grant or through row-level security, are judged
+/// by column names and listed as skipped.
+#[cfg_attr(feature = "gui", tauri::command)]
+pub async fn scan_pii(profile_id: String) -> AppResult<PiiScan> {
+    let profile = get_profile_by_id(&profile_id)?;
+    let client = connect_profile(&profile).await?;
+
+    let rows = client
+        .query(COLUMNS_QUERY, &[])
+        .await
+        .map_err(|e| AppError::query("Failed to get columns", &e))?;
+
+    // Columns grouped by table, keeping catalog order
+    let mut tables: Vec<(TableName, Vec<(String, String)>)> = Vec::new();
+    for row in &rows {
+        let table = TableName {
+            schema: row.get(0),
+            name: row.get(1),
+        };
+        let column = (row.get(2), row.get(3));
+        match tables.last_mut() {
+            Some((last, columns)) if *last == table => columns.push(column),
+            _ => tables.push((table, vec![column])),
+        }
+    }
+
+    let mut found = Vec::new();
+    let mut skipped_tables = Vec::new();
+    for (table, columns) in tables {
+        let sampled: Vec<String> = columns
+            .iter()
+            .filter(|(_, data_type)| SAMPLED_TYPES.contains(&data_type.as_str()))
+            .map(|(column, _)| column.clone())
+            .collect();
+        let samples = if sampled.is_empty() {
+            Vec::new()
+        } else {
+            match sample_values(&client, &table, &sampled).await {
+                Ok(samples) => samples,
+                Err(e) => {
+                    skipped_tables.push(PiiSkippedTable {
+                        schema: table.schema.clone(),
+                        table: table.name.clone(),
+                        reason: e,
+                    });
+                    Vec::new()
+                }
+            }
+        };
+
+        for (column, data_type) in columns {
+            let values = sampled
+                .iter()
+                .position(|c| *c == column)
+                .and_then(|i| samples.get(i))
+                .map(Vec::as_slice)
+                .unwrap_or(&[]);
+            if let Some((kind, confidence, matched)) = score(name_hint(&column), &data_type, values)
+            {
+                found.push(PiiColumn {
+                    schema: table.schema.clone(),
+                    table: table.name.clone(),
+                    column,
+                    data_type,
+                    kind,
+                    confidence,
+                    sampled_values: values.len(),
+                    matched_values: matched,
+                    suggested_strategy: kind.suggested_strategy(),
+                });
+            }
+        }
+    }
+
+    found.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    Ok(PiiScan {
+        columns: found,
+        skipped_tables,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_hints_from_column_names() {
+        assert_eq!(name_hint("first_name"), Some((PiiKind::Name, 0.6)));
+        assert_eq!(name_hint("customerEmail"), Some((PiiKind::Email, 0.6)));
+        assert_eq!(name_hint("user_ip"), Some((PiiKind::IpAddress, 0.5)));
+        assert_eq!(name_hint("shipping"), None);
+        assert_eq!(name_hint("description"), None);
+    }
+
+    #[test]
+    fn classifies_sampled_values() {
+        assert_eq!(classify_value("ana@example.pe"), Some(PiiKind::Email));
+        assert_eq!(classify_value("10.0.0.1/32"), Some(PiiKind::IpAddress));
+        assert_eq!(
+            classify_value("4111 1111 1111 1111"),
+            Some(PiiKind::CreditCard)
+        );
+        assert_ne!(
+            classify_value("4111 1111 1111 1112"),
+            Some(PiiKind::CreditCard)
+        );
+        assert_eq!(classify_value("123-45-6789"), Some(PiiKind::NationalId));
+        assert_eq!(classify_value("+51 (1) 555-0199"), Some(PiiKind::Phone));
+        assert_eq!(classify_value("pending"), None);
+    }
+
+    #[test]
+    fn dates_are_not_phone_numbers() {
+        assert_eq!(classify_value("2024-01-15"), None);
+        assert_eq!(classify_value("15.01.2024"), None);
+        assert_eq!(classify_value("1/15/2024"), None);
+        let dates: Vec<String> = (1..=20).map(|d| format!("2024-01-{:02}", d)).collect();
+        assert!(score(None, "text", &dates).is_none());
+    }
+
+    #[test]
+    fn bare_digit_runs_need_a_phone_name() {
+        assert_eq!(classify_value("1000234"), None);
+        assert_eq!(classify_value("+51987654321"), Some(PiiKind::Phone));
+        let references: Vec<String> = (0..20).map(|i| format!("{}", 1000234 + i)).collect();
+        assert!(score(name_hint("order_number"), "text", &references).is_none());
+        assert!(score(None, "bigint", &references).is_none());
+
+        let phones: Vec<String> = (0..20).map(|i| format!("98765{:04}", i)).collect();
+        let (kind, _, matched) = score(name_hint("phone"), "text", &phones).unwrap();
+        assert_eq!((kind, matched), (PiiKind::Phone, 20));
+    }
+
+    #[test]
+    fn loose_name_hints_need_matching_values() {
+        assert!(score(name_hint("tax_rate"), "numeric", &[]).is_none());
+        assert!(score(name_hint("city"), "text", &[]).is_none());
+        assert_eq!(
+            score(name_hint("tax_id"), "text", &[]),
+            Some((PiiKind::NationalId, 0.6, 0))
+        );
+        let ids = vec!["123-45-6789".to_string(); 20];
+        let (kind, _, _) = score(name_hint("tax_number"), "text", &ids).unwrap();
+        assert_eq!(kind, PiiKind::NationalId);
+    }
+
+    #[test]
+    fn agreeing_name_and_values_score_highest() {
+        let emails: Vec<String> = (0..20).map(|i| format!("u{}@example.com", i)).collect();
+        let (kind, confidence, matched) = score(name_hint("email"), "text", &emails).unwrap();
+        assert_eq!((kind, matched), (PiiKind::Email, 20));
+        assert_eq!(confidence, 1.0);
+
+        // Values alone, without a telling name
+        let (kind, confidence, _) = score(None, "text", &emails).unwrap();
+        assert_eq!(kind, PiiKind::Email);
+        assert!((0.8..1.0).contains(&confidence));
+
+        // A weak name hint with unrelated values is not reported
+        let statuses = vec!["active".to_string(); 20];
+        assert!(score(name_hint("name"), "text", &statuses).is_none());
+    }
+}
@@ -1,21 +1,22 @@
 use chrono::Utc;
 
-use crate::storage::{load_app_data, save_app_data};
-use crate::types::{CloneType, ConnectionProfile, SavedOperation, Tag};
-
-#[tauri::command]
-pub fn get_profiles() -> Result<Vec<ConnectionProfile>, String> {
-    let data = load_app_data();
-    Ok(data.profiles)
+use crate::error::{AppError, AppResult};
+use crate::scheduler::next_run;
+use crate::storage::Store;
+use crate::types::{CloneOptions, ConnectionProfile, OperationSchedule, SavedOperation, Tag};
+
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn get_profiles() -> AppResult<Vec<ConnectionProfile>> {
+    Store::open()?.list_profiles()
 }
 
-#[tauri::command]
-pub fn get_profile(id: String) -> Result<Option<ConnectionProfile>, String> {
-    let data = load_app_data();
-    Ok(data.profiles.into_iter().find(|p| p.id == id))
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn get_profile(id: String) -> AppResult<Option<ConnectionProfile>> {
+    Store::open()?.get_profile(&id)
 }
 
-#[tauri::command]
+#[cfg_attr(feature = "gui", tauri::command)]
+#[allow(clippy::too_many_arguments)]
 pub fn create_profile(
     name: String,
     host: String,
@@ -25,18 +26,16 @@ pub fn create_profile(
     password: String,
     ssl: bool,
     tag_id: Option<String>,
-) -> Result<ConnectionProfile, String> {
-    let mut data = load_app_data();
-
+) -> AppResult<ConnectionProfile> {
     let profile = ConnectionProfile::new(name, host, port, database, user, password, ssl, tag_id);
 
-    data.profiles.push(profile.clone());
-    save_app_data(&data)?;
+    Store::open()?.insert_profile(&profile)?;
 
     Ok(profile)
 }
 
-#[tauri::command]
+#[cfg_attr(feature = "gui", tauri::command)]
+#[allow(clippy::too_many_arguments)]
 pub fn update_profile(
     id: String,
     name: String,
@@ -47,14 +46,11 @@ pub fn update_profile(
     password: String,
     ssl: bool,
     tag_id: Option<String>,
-) -> Result<ConnectionProfile, String> {
-    let mut data = load_app_data();
-
-    let profile = data
-        .profiles
-        .iter_mut()
-        .find(|p| p.id == id)
-        .ok_or("Profile not found")?;
+) -> AppResult<ConnectionProfile> {
+    let store = Store::open()?;
+    let mut profile = store
+        .get_profile(&id)?
+        .ok_or_else(|| AppError::ProfileNotFound { id: id.clone() })?;
 
     profile.name = name;
     profile.host = host;
@@ -66,142 +62,101 @@ pub fn update_profile(
     profile.tag_id = tag_id;
     profile.updated_at = Utc::now();
 
-    let updated = profile.clone();
-    save_app_data(&data)?;
-
-    Ok(updated)
+    store.update_profile(&profile)?;
+    Ok(profile)
 }
 
-#[tauri::command]
-pub fn delete_profile(id: String) -> Result<(), String> {
-    let mut data = load_app_data();
-
-    let initial_len = data.profiles.len();
-    data.profiles.retain(|p| p.id != id);
-
-    if data.profiles.len() == initial_len {
-        return Err("Profile not found".to_string());
-    }
-
-    save_app_data(&data)?;
-    Ok(())
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn delete_profile(id: String) -> AppResult<()> {
+    Store::open()?.delete_profile(&id)
 }
 
 // Tag commands
 
-#[tauri::command]
-pub fn get_tags() -> Result<Vec<Tag>, String> {
-    let data = load_app_data();
-    Ok(data.tags)
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn get_tags() -> AppResult<Vec<Tag>> {
+    Store::open()?.list_tags()
 }
 
-#[tauri::command]
-pub fn create_tag(name: String, color: String) -> Result<Tag, String> {
-    let mut data = load_app_data();
-
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn create_tag(name: String, color: String) -> AppResult<Tag> {
     // Validate color format
     if !color.starts_with('#') || color.len() != 7 {
-        return Err("Color must be in hex format: #RRGGBB".to_string());
+        return Err(AppError::InvalidInput(
+            "Color must be in hex format: #RRGGBB".to_string(),
+        ));
     }
 
     let tag = Tag::new(name, color);
-    data.tags.push(tag.clone());
-    save_app_data(&data)?;
+
+    Store::open()?.insert_tag(&tag)?;
 
     Ok(tag)
 }
 
-#[tauri::command]
-pub fn update_tag(id: String, name: String, color: String) -> Result<Tag, String> {
-    let mut data = load_app_data();
-
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn update_tag(id: String, name: String, color: String) -> AppResult<Tag> {
     // Validate color format
     if !color.starts_with('#') || color.len() != 7 {
-        return Err("Color must be in hex format: #RRGGBB".to_string());
+        return Err(AppError::InvalidInput(
+            "Color must be in hex format: #RRGGBB".to_string(),
+        ));
     }
 
-    let tag = data
-        .tags
-        .iter_mut()
-        .find(|t| t.id == id)
-        .ok_or("Tag not found")?;
+    let tag = Tag { id, name, color };
+    Store::open()?.update_tag(&tag)?;
 
-    tag.name = name;
-    tag.color = color;
-
-    let updated = tag.clone();
-    save_app_data(&data)?;
-
-    Ok(updated)
+    Ok(tag)
 }
 
-#[tauri::command]
-pub fn delete_tag(id: String) -> Result<(), String> {
-    let mut data = load_app_data();
-
-    let initial_len = data.tags.len();
-    data.tags.retain(|t| t.id != id);
-
-    if data.tags.len() == initial_len {
-        return Err("Tag not found".to_string());
-    }
-
-    // Remove tag_id from profiles that reference this tag
-    for profile in data.profiles.iter_mut() {
-        if profile.tag_id.as_ref() == Some(&id) {
-            profile.tag_id = None;
-        }
-    }
-
-    save_app_data(&data)?;
-    Ok(())
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn delete_tag(id: String) -> AppResult<()> {
+    // Profiles that reference this tag are untagged by the database
+    Store::open()?.delete_tag(&id)
 }
 
 // Saved Operations commands
 
-#[tauri::command]
-pub fn get_saved_operations() -> Result<Vec<SavedOperation>, String> {
-    let data = load_app_data();
-    Ok(data.saved_operations)
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn get_saved_operations() -> AppResult<Vec<SavedOperation>> {
+    Store::open()?.list_saved_operations()
 }
 
-#[tauri::command]
-pub fn create_saved_operation(
-    name: String,
-    source_id: String,
-    destination_id: String,
-    clean_destination: bool,
-    create_backup: bool,
-    clone_type: CloneType,
-) -> Result<SavedOperation, String> {
-    let mut data = load_app_data();
-
-    let operation = SavedOperation::new(
-        name,
-        source_id,
-        destination_id,
-        clean_destination,
-        create_backup,
-        clone_type,
-    );
-
-    data.saved_operations.push(operation.clone());
-    save_app_data(&data)?;
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn create_saved_operation(name: String, options: CloneOptions) -> AppResult<SavedOperation> {
+    let operation = SavedOperation::new(name, options);
+
+    Store::open()?.insert_saved_operation(&operation)?;
 
     Ok(operation)
 }
 
-#[tauri::command]
-pub fn delete_saved_operation(id: String) -> Result<(), String> {
-    let mut data = load_app_data();
-
-    let initial_len = data.saved_operations.len();
-    data.saved_operations.retain(|o| o.id != id);
-
-    if data.saved_operations.len() == initial_len {
-        return Err("Saved operation not found".to_string());
+/// Sets or clears the schedule of a saved operation. Runs are counted from now, so
+/// a new schedule never starts with a missed run.
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn set_operation_schedule(
+    id: String,
+    schedule: Option<OperationSchedule>,
+) -> AppResult<SavedOperation> {
+    let now = Utc::now();
+    if let Some(schedule) = &schedule {
+        if next_run(schedule, now)?.is_none() {
+            return Err(AppError::InvalidInput(
+                "This schedule never runs".to_string(),
+            ));
+        }
     }
 
-    save_app_data(&data)?;
-    Ok(())
+    let store = Store::open()?;
+    store.set_operation_schedule(&id, &schedule, now)?;
+    store
+        .list_saved_operations()?
+        .into_iter()
+        .find(|operation| operation.id == id)
+        .ok_or(AppError::SavedOperationNotFound { id })
+}
+
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn delete_saved_operation(id: String) -> AppResult<()> {
+    Store::open()?.delete_saved_operation(&id)
 }
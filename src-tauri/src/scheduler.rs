@@ -0,0 +1,356 @@
+use std::str::FromStr;
+#[cfg(feature = "gui")]
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Local, NaiveTime, Utc};
+#[cfg(feature = "gui")]
+use tauri::AppHandle;
+#[cfg(feature = "gui")]
+use tauri_plugin_notification::NotificationExt;
+
+#[cfg(feature = "gui")]
+use crate::clone::queue_clone;
+use crate::error::{AppError, AppResult};
+use crate::storage::Store;
+#[cfg(feature = "gui")]
+use crate::types::{CloneHistoryEntry, CloneStatus};
+use crate::types::{MissedRunPolicy, OperationSchedule, SavedOperation};
+
+/// How often the scheduler looks for operations that are due
+#[cfg(feature = "gui")]
+const TICK: Duration = Duration::from_secs(30);
+/// Runs listed by `preview_schedule`
+const PREVIEW_RUNS: usize = 3;
+/// Standard cron weekday numbers, where 0 and 7 are both Sunday
+const WEEKDAY_NAMES: [&str; 8] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"];
+
+fn parse_time(time: &str) -> AppResult<NaiveTime> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M")
+        .map_err(|_| AppError::InvalidInput(format!("Invalid time {}, expected HH:MM", time)))
+}
+
+/// Replaces weekday numbers with names, since the `cron` crate counts from 1 = Sunday
+fn weekday_names(field: &str) -> String {
+    let name = |value: &str| -> String {
+        value
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| WEEKDAY_NAMES.get(n))
+            .map_or(value, |name| name)
+            .to_string()
+    };
+
+    field
+        .split(',')
+        .map(|part| {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, Some(step)),
+                None => (part, None),
+            };
+            if let Some(days) = through_sunday(range, step) {
+                return days;
+            }
+            let range = range.split('-').map(name).collect::<Vec<_>>().join("-");
+            match step {
+                Some(step) => format!("{}/{}", range, step),
+                None => range,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Lists a range ending in 7 day by day. As a range of names it would end on Sunday,
+/// which the `cron` crate reads as the start of the week, turning the range around.
+fn through_sunday(range: &str, step: Option<&str>) -> Option<String> {
+    let (start, end) = range.split_once('-')?;
+    let start = start.parse::<usize>().ok().filter(|_| end == "7")?;
+    let step = step.map_or(Some(1), |s| s.parse::<usize>().ok().filter(|s| *s > 0))?;
+
+    let mut days: Vec<&str> = Vec::new();
+    for day in (start..=7).step_by(step) {
+        if !days.contains(&WEEKDAY_NAMES[day]) {
+            days.push(WEEKDAY_NAMES[day]);
+        }
+    }
+    (!days.is_empty()).then(|| days.join(","))
+}
+
+/// Parses a cron expression. Five fields are read as standard cron; the seconds and
+/// year fields of the `cron` crate are also accepted.
+fn parse_cron(expression: &str) -> AppResult<cron::Schedule> {
+    let expression = expression.trim();
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    let full = if let [minute, hour, day, month, weekday] = fields[..] {
+        format!(
+            "0 {} {} {} {} {}",
+            minute,
+            hour,
+            day,
+            month,
+            weekday_names(weekday)
+        )
+    } else {
+        expression.to_string()
+    };
+
+    cron::Schedule::from_str(&full).map_err(|e| {
+        AppError::InvalidInput(format!("Invalid cron expression {}: {}", expression, e))
+    })
+}
+
+/// First run of the schedule strictly after `after`
+pub fn next_run(
+    schedule: &OperationSchedule,
+    after: DateTime<Utc>,
+) -> AppResult<Option<DateTime<Utc>>> {
+    let (time, day) = match schedule {
+        OperationSchedule::Cron { expression } => {
+            let next = parse_cron(expression)?
+                .after(&after.with_timezone(&Local))
+                .next();
+            return Ok(next.map(|t| t.with_timezone(&Utc)));
+        }
+        OperationSchedule::Daily { time } => (parse_time(time)?, None),
+        OperationSchedule::Weekly { day, time } => (parse_time(time)?, Some(*day)),
+    };
+
+    // Days where the time falls in a DST gap are skipped
+    let start = after.with_timezone(&Local).date_naive();
+    Ok(start
+        .iter_days()
+        .take(15)
+        .filter(|date| day.is_none_or(|d| date.weekday() == d))
+        .filter_map(|date| date.and_time(time).and_local_timezone(Local).earliest())
+        .map(|t| t.with_timezone(&Utc))
+        .find(|t| *t > after))
+}
+
+/// Starts the loop that runs scheduled operations while the app is open
+#[cfg(feature = "gui")]
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut catching_up = true;
+        loop {
+            for due in take_due_operations(catching_up) {
+                match due {
+                    Ok(operation) => run_operation(&app, operation),
+                    Err(e) => notify(&app, "Scheduled Clone Failed", &e.to_string()),
+                }
+            }
+            catching_up = false;
+            tokio::time::sleep(TICK).await;
+        }
+    });
+}
+
+/// Operations whose next run has come, marked as handled. On the first pass after
+/// start these are runs missed while the app was closed, and the saved policy
+/// decides whether they run.
+fn take_due_operations(catching_up: bool) -> Vec<AppResult<SavedOperation>> {
+    let Ok(store) = Store::open() else {
+        return Vec::new();
+    };
+    let policy = if catching_up {
+        store.missed_run_policy().unwrap_or_default()
+    } else {
+        MissedRunPolicy::RunOnce
+    };
+    claim_due_operations(&store, policy, Utc::now())
+}
+
+/// Claims each due run in the store, so a run another window or instance of the app
+/// already took is left to it. Runs that could not be claimed are returned as errors.
+fn claim_due_operations(
+    store: &Store,
+    policy: MissedRunPolicy,
+    now: DateTime<Utc>,
+) -> Vec<AppResult<SavedOperation>> {
+    store
+        .list_saved_operations()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|operation| {
+            let schedule = operation.schedule.as_ref()?;
+            let since = operation.last_scheduled_at.unwrap_or(operation.created_at);
+            let due = next_run(schedule, since)
+                .ok()
+                .flatten()
+                .is_some_and(|due| due <= now);
+            if !due {
+                return None;
+            }
+
+            match store.claim_scheduled_run(&operation.id, operation.last_scheduled_at, now) {
+                Ok(true) if policy == MissedRunPolicy::RunOnce => Some(Ok(operation)),
+                Ok(_) => None,
+                Err(e) => Some(Err(AppError::storage(
+                    &format!("{} could not be scheduled", operation.name),
+                    e,
+                ))),
+            }
+        })
+        .collect()
+}
+
+#[cfg(feature = "gui")]
+fn run_operation(app: &AppHandle, operation: SavedOperation) {
+    match queue_clone(app, operation.clone_options()) {
+        Ok((_, finished)) => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                // Dropped without a value when the job is removed from the queue
+                if let Ok(entry) = finished.await {
+                    notify_result(&app, &operation, &entry);
+                }
+            });
+        }
+        Err(e) => notify(
+            app,
+            "Scheduled Clone Failed",
+            &format!("{} could not start: {}", operation.name, e),
+        ),
+    }
+}
+
+#[cfg(feature = "gui")]
+fn notify_result(app: &AppHandle, operation: &SavedOperation, entry: &CloneHistoryEntry) {
+    match entry.status {
+        CloneStatus::Success => notify(
+            app,
+            "Scheduled Clone Completed",
+            &format!(
+                "{}: {} → {} in {}s",
+                operation.name,
+                entry.source_name,
+                entry.destination_name,
+                entry.duration.unwrap_or(0)
+            ),
+        ),
+        _ => notify(
+            app,
+            "Scheduled Clone Failed",
+            &format!(
+                "{}: {}",
+                operation.name,
+                entry.error_message.as_deref().unwrap_or("Unknown error")
+            ),
+        ),
+    }
+}
+
+#[cfg(feature = "gui")]
+fn notify(app: &AppHandle, title: &str, body: &str) {
+    let _ = app.notification().builder().title(title).body(body).show();
+}
+
+/// Next runs of a schedule, to check it before saving
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn preview_schedule(schedule: OperationSchedule) -> AppResult<Vec<DateTime<Utc>>> {
+    let mut runs = Vec::new();
+    let mut after = Utc::now();
+    while runs.len() < PREVIEW_RUNS {
+        match next_run(&schedule, after)? {
+            Some(run) => {
+                runs.push(run);
+                after = run;
+            }
+            None => break,
+        }
+    }
+    Ok(runs)
+}
+
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn get_missed_run_policy() -> AppResult<MissedRunPolicy> {
+    Store::open()?.missed_run_policy()
+}
+
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn set_missed_run_policy(policy: MissedRunPolicy) -> AppResult<MissedRunPolicy> {
+    Store::open()?.set_missed_run_policy(policy)?;
+    Ok(policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Weekday};
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Local
+            .with_ymd_and_hms(y, m, d, h, min, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn finds_next_daily_and_weekly_runs() {
+        let daily = OperationSchedule::Daily {
+            time: "03:30".to_string(),
+        };
+        // Thursday
+        let after = local(2026, 1, 15, 4, 0);
+        assert_eq!(
+            next_run(&daily, after).unwrap(),
+            Some(local(2026, 1, 16, 3, 30))
+        );
+        assert_eq!(
+            next_run(&daily, local(2026, 1, 15, 3, 0)).unwrap(),
+            Some(local(2026, 1, 15, 3, 30))
+        );
+
+        let weekly = OperationSchedule::Weekly {
+            day: Weekday::Mon,
+            time: "22:00".to_string(),
+        };
+        assert_eq!(
+            next_run(&weekly, after).unwrap(),
+            Some(local(2026, 1, 19, 22, 0))
+        );
+    }
+
+    #[test]
+    fn reads_five_field_cron_expressions() {
+        let cron = OperationSchedule::Cron {
+            expression: "15 2 * * *".to_string(),
+        };
+        assert_eq!(
+            next_run(&cron, local(2026, 1, 15, 4, 0)).unwrap(),
+            Some(local(2026, 1, 16, 2, 15))
+        );
+
+        // Weekdays count from 0 = Sunday as in standard cron
+        let weekdays = OperationSchedule::Cron {
+            expression: "0 6 * * 1-5".to_string(),
+        };
+        // Saturday
+        assert_eq!(
+            next_run(&weekdays, local(2026, 1, 17, 4, 0)).unwrap(),
+            Some(local(2026, 1, 19, 6, 0))
+        );
+        assert_eq!(weekday_names("0,6/2,7"), "SUN,SAT/2,SUN");
+
+        // Every day, Monday through Sunday
+        let every_day = OperationSchedule::Cron {
+            expression: "0 6 * * 1-7".to_string(),
+        };
+        assert_eq!(
+            next_run(&every_day, local(2026, 1, 17, 4, 0)).unwrap(),
+            Some(local(2026, 1, 17, 6, 0))
+        );
+        assert_eq!(
+            next_run(&every_day, local(2026, 1, 18, 7, 0)).unwrap(),
+            Some(local(2026, 1, 19, 6, 0))
+        );
+        assert_eq!(weekday_names("1-7"), "MON,TUE,WED,THU,FRI,SAT,SUN");
+        assert_eq!(weekday_names("5-7/2"), "FRI,SUN");
+        assert_eq!(weekday_names("0-7"), "SUN,MON,TUE,WED,THU,FRI,SAT");
+
+        let invalid = OperationSchedule::Cron {
+            expression: "every night".to_string(),
+        };
+        assert!(next_run(&invalid, Utc::now()).is_err());
+        assert!(parse_time("25:00").is_err());
+    }
+}
@@ -0,0 +1,303 @@
+use tokio_postgres::Client;
+
+use crate::error::{AppError, AppResult};
+use crate::pg_client::describe_error;
+use crate::table_copy::quote_ident;
+use crate::types::{CloneType, SchemaRename};
+
+// The dump always refers to source schema names. While it is restored, each target
+// schema on the destination goes by its source name, and whatever already had that
+// name is parked under a temporary one. Renaming back afterwards carries every
+// OID-based reference along: column defaults, sequence ownership, views and foreign
+// keys. Only text such as function bodies keeps the old name.
+
+/// Temporary name for a destination schema that shares a source schema's name
+fn hold_name(schema: &str) -> String {
+    format!("{}__clone_hold", schema)
+}
+
+/// Statements that give each target schema its source name before the restore
+fn swap_in_statements(
+    renames: &[SchemaRename],
+    existing: &[String],
+    clone_type: &CloneType,
+    clean_destination: bool,
+) -> AppResult<Vec<String>> {
+    let exists = |name: &str| existing.iter().any(|e| e == name);
+    let mut statements = Vec::new();
+
+    for rename in renames {
+        let hold = hold_name(&rename.from);
+        if exists(&hold) {
+            return Err(AppError::InvalidInput(format!(
+                "Schema {} is left over from an earlier clone; rename or drop it first",
+                hold
+            )));
+        }
+        if exists(&rename.from) {
+            statements.push(format!(
+                "ALTER SCHEMA {} RENAME TO {}",
+                quote_ident(&rename.from),
+                quote_ident(&hold)
+            ));
+        }
+
+        if matches!(clone_type, CloneType::Data) {
+            // Data goes into the tables already in the target schema
+            if !exists(&rename.to) {
+                return Err(AppError::InvalidInput(format!(
+                    "Schema {} does not exist in the destination",
+                    rename.to
+                )));
+            }
+            statements.push(format!(
+                "ALTER SCHEMA {} RENAME TO {}",
+                quote_ident(&rename.to),
+                quote_ident(&rename.from)
+            ));
+        } else {
+            // The restore creates the schema, except public which pg_dump assumes exists
+            if exists(&rename.to) {
+                if !clean_destination {
+                    return Err(AppError::InvalidInput(format!(
+                        "Schema {} already exists in the destination; enable Clean destination to replace it",
+                        rename.to
+                    )));
+                }
+                statements.push(format!("DROP SCHEMA {} CASCADE", quote_ident(&rename.to)));
+            }
+            if rename.from == "public" {
+                statements.push("CREATE SCHEMA public".to_string());
+            }
+        }
+    }
+    Ok(statements)
+}
+
+/// Statements that move restored schemas to their targets and put parked ones back
+fn swap_out_statements(renames: &[SchemaRename], existing: &[String]) -> Vec<String> {
+    let exists = |name: &str| existing.iter().any(|e| e == name);
+    let mut statements = Vec::new();
+
+    for rename in renames {
+        if exists(&rename.from) {
+            statements.push(format!(
+                "ALTER SCHEMA {} RENAME TO {}",
+                quote_ident(&rename.from),
+                quote_ident(&rename.to)
+            ));
+        }
+        let hold = hold_name(&rename.from);
+        if exists(&hold) {
+            statements.push(format!(
+                "ALTER SCHEMA {} RENAME TO {}",
+                quote_ident(&hold),
+                quote_ident(&rename.from)
+            ));
+        }
+    }
+    statements
+}
+
+/// Rewrites the schemas of a `search_path` setting, as stored in `proconfig`
+fn remap_search_path(value: &str, renames: &[SchemaRename]) -> String {
+    value
+        .split(',')
+        .map(|item| {
+            let item = item.trim();
+            let name = item.trim_matches('"');
+            match renames.iter().find(|r| r.from == name) {
+                Some(rename) => quote_ident(&rename.to),
+                None => item.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Which source schema a destination schema is cleaned as. Schemas sharing a
+/// renamed source schema's name hold unrelated data and are not cleaned at all.
+pub fn source_schema(renames: &[SchemaRename], schema: &str) -> Option<String> {
+    if let Some(rename) = renames.iter().find(|r| r.to == schema) {
+        return Some(rename.from.clone());
+    }
+    if renames.iter().any(|r| r.from == schema) {
+        return None;
+    }
+    Some(schema.to_string())
+}
+
+/// Name a source schema ends up with on the destination
+pub fn destination_schema(renames: &[SchemaRename], schema: &str) -> String {
+    renames
+        .iter()
+        .find(|r| r.from == schema)
+        .map_or(schema, |r| r.to.as_str())
+        .to_string()
+}
+
+async fn existing_schemas(client: &Client) -> AppResult<Vec<String>> {
+    let rows = client
+        .query("SELECT nspname::text FROM pg_namespace", &[])
+        .await
+        .map_err(|e| AppError::query("Failed to get schemas", &e))?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+/// Runs `statements` in one transaction, so a failure leaves every name as it was
+async fn run_atomically(client: &Client, statements: &[String], context: &str) -> AppResult<()> {
+    if statements.is_empty() {
+        return Ok(());
+    }
+    let batch = format!("BEGIN; {}; COMMIT;", statements.join("; "));
+    if let Err(e) = client.batch_execute(&batch).await {
+        let _ = client.batch_execute("ROLLBACK").await;
+        return Err(AppError::query(context, &e));
+    }
+    Ok(())
+}
+
+/// Gives target schemas their source names ahead of the restore
+pub async fn swap_in(
+    client: &Client,
+    renames: &[SchemaRename],
+    clone_type: &CloneType,
+    clean_destination: bool,
+) -> AppResult<()> {
+    let existing = existing_schemas(client).await?;
+    let statements = swap_in_statements(renames, &existing, clone_type, clean_destination)?;
+    run_atomically(client, &statements, "Failed to prepare renamed schemas").await
+}
+
+/// Renames restored schemas to their targets and fixes function search paths.
+/// Safe to call after a failed restore.
+pub async fn swap_out(
+    client: &Client,
+    renames: &[SchemaRename],
+    add_log: &(dyn Fn(&str) + Sync),
+) -> AppResult<()> {
+    let existing = existing_schemas(client).await?;
+    let statements = swap_out_statements(renames, &existing);
+    run_atomically(client, &statements, "Failed to rename restored schemas").await?;
+
+    for rename in renames {
+        let rows = client
+            .query(
+                "SELECT p.oid::regprocedure::text, substr(cfg, length('search_path=') + 1)
+                 FROM pg_proc p
+                 JOIN pg_namespace n ON n.oid = p.pronamespace
+                 CROSS JOIN unnest(p.proconfig) AS cfg
+                 WHERE n.nspname = $1 AND cfg LIKE 'search_path=%'",
+                &[&rename.to],
+            )
+            .await
+            .map_err(|e| AppError::query("Failed to get function settings", &e))?;
+
+        for row in rows {
+            let function: String = row.get(0);
+            let path: String = row.get(1);
+            let remapped = remap_search_path(&path, renames);
+            if remapped == path {
+                continue;
+            }
+            let statement = format!("ALTER FUNCTION {} SET search_path = {}", function, remapped);
+            match client.batch_execute(&statement).await {
+                Ok(_) => add_log(&format!("[INFO] Updated search_path of {}", function)),
+                Err(e) => add_log(&format!(
+                    "[WARNING] Could not update search_path of {}: {}",
+                    function,
+                    describe_error(&e)
+                )),
+            }
+        }
+        add_log(&format!(
+            "[SUCCESS] Schema {} restored as {}",
+            rename.from, rename.to
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn renames() -> Vec<SchemaRename> {
+        vec![SchemaRename {
+            from: "public".to_string(),
+            to: "tenant_42".to_string(),
+        }]
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn parks_the_source_name_while_restoring() {
+        let statements = swap_in_statements(
+            &renames(),
+            &names(&["public", "tenant_42"]),
+            &CloneType::Both,
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            statements,
+            [
+                "ALTER SCHEMA \"public\" RENAME TO \"public__clone_hold\"",
+                "DROP SCHEMA \"tenant_42\" CASCADE",
+                "CREATE SCHEMA public",
+            ]
+        );
+
+        // Without cleaning an existing target is left alone
+        assert!(swap_in_statements(
+            &renames(),
+            &names(&["public", "tenant_42"]),
+            &CloneType::Both,
+            false
+        )
+        .is_err());
+
+        let statements = swap_in_statements(
+            &renames(),
+            &names(&["public", "tenant_42"]),
+            &CloneType::Data,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            statements[1],
+            "ALTER SCHEMA \"tenant_42\" RENAME TO \"public\""
+        );
+
+        let statements = swap_out_statements(&renames(), &names(&["public", "public__clone_hold"]));
+        assert_eq!(
+            statements,
+            [
+                "ALTER SCHEMA \"public\" RENAME TO \"tenant_42\"",
+                "ALTER SCHEMA \"public__clone_hold\" RENAME TO \"public\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn remaps_search_paths_and_clean_targets() {
+        assert_eq!(
+            remap_search_path("public, \"$user\", pg_temp", &renames()),
+            "\"tenant_42\", \"$user\", pg_temp"
+        );
+        assert_eq!(
+            source_schema(&renames(), "tenant_42").as_deref(),
+            Some("public")
+        );
+        assert_eq!(source_schema(&renames(), "public"), None);
+        assert_eq!(
+            source_schema(&renames(), "billing").as_deref(),
+            Some("billing")
+        );
+        assert_eq!(destination_schema(&renames(), "public"), "tenant_42");
+        assert_eq!(destination_schema(&renames(), "billing"), "billing");
+    }
+}
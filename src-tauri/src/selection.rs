@@ -0,0 +1,251 @@
+use crate::error::{AppError, AppResult};
+use crate::table_copy::TableName;
+use crate::types::CloneOptions;
+
+/// Matches `text` against a pg_dump-style wildcard pattern: `*` is any run of
+/// characters, `?` a single one
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    // Backtracking to the last `*` is enough for patterns without classes
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// A table pattern is `table` (any schema) or `schema.table`, as with pg_dump `-t`
+fn table_matches(pattern: &str, schema: &str, table: &str) -> bool {
+    match pattern.split_once('.') {
+        Some((schema_pattern, table_pattern)) => {
+            glob_matches(schema_pattern, schema) && glob_matches(table_pattern, table)
+        }
+        None => glob_matches(pattern, table),
+    }
+}
+
+/// Whether pg_dump would dump `schema.table` given the clone's include and exclude
+/// lists. Like pg_dump, included tables win over the schema lists.
+pub fn is_selected(options: &CloneOptions, schema: &str, table: &str) -> bool {
+    if options
+        .exclude_tables
+        .iter()
+        .any(|p| table_matches(p, schema, table))
+    {
+        return false;
+    }
+    if !options.include_tables.is_empty() {
+        return options
+            .include_tables
+            .iter()
+            .any(|p| table_matches(p, schema, table));
+    }
+    (options.include_schemas.is_empty()
+        || options
+            .include_schemas
+            .iter()
+            .any(|p| glob_matches(p, schema)))
+        && !options
+            .exclude_schemas
+            .iter()
+            .any(|p| glob_matches(p, schema))
+}
+
+/// Whether the rows of `schema.table` are left out while its definition is kept
+pub fn is_data_excluded(options: &CloneOptions, schema: &str, table: &str) -> bool {
+    options
+        .exclude_table_data
+        .iter()
+        .any(|p| table_matches(p, schema, table))
+}
+
+/// Whether the rows of `schema.table` are copied: the table is dumped and its data kept
+pub fn is_data_selected(options: &CloneOptions, schema: &str, table: &str) -> bool {
+    is_selected(options, schema, table) && !is_data_excluded(options, schema, table)
+}
+
+/// A row filter whose table has been looked up in the source
+#[derive(Debug, Clone)]
+pub struct RowFilter {
+    pub table: TableName,
+    pub where_clause: String,
+}
+
+/// Row filters on tables whose rows are copied. Two filters on one table are
+/// rejected, since its rows would be copied twice.
+pub fn row_filters(options: &CloneOptions, filters: Vec<RowFilter>) -> AppResult<Vec<RowFilter>> {
+    let mut kept: Vec<RowFilter> = Vec::new();
+    for filter in filters {
+        if kept.iter().any(|f| f.table == filter.table) {
+            return Err(AppError::InvalidInput(format!(
+                "Several row filters for {}",
+                filter.table.qualified()
+            )));
+        }
+        kept.push(filter);
+    }
+    kept.retain(|f| is_data_selected(options, &f.table.schema, &f.table.name));
+    Ok(kept)
+}
+
+/// Whether the clean step should empty or drop `schema.table`, named as in the
+/// source. Without include lists only `public` and renamed schemas are cleaned, as
+/// before selections existed.
+pub fn is_cleaned(options: &CloneOptions, schema: &str, table: &str) -> bool {
+    let scoped = !options.include_schemas.is_empty()
+        || !options.include_tables.is_empty()
+        || schema == "public"
+        || options.schema_renames.iter().any(|r| r.from == schema);
+    scoped && is_selected(options, schema, table)
+}
+
+/// pg_dump switches for the include and exclude lists
+pub fn dump_args(options: &CloneOptions) -> Vec<String> {
+    let switches = [
+        ("--schema", &options.include_schemas),
+        ("--exclude-schema", &options.exclude_schemas),
+        ("--table", &options.include_tables),
+        ("--exclude-table", &options.exclude_tables),
+        ("--exclude-table-data", &options.exclude_table_data),
+    ];
+
+    switches
+        .into_iter()
+        .flat_map(|(switch, patterns)| {
+            patterns
+                .iter()
+                .flat_map(move |p| [switch.to_string(), p.clone()])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CloneType;
+
+    fn options() -> CloneOptions {
+        CloneOptions {
+            clean_destination: true,
+            ..CloneOptions::new("a".to_string(), "b".to_string(), CloneType::Both)
+        }
+    }
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(glob_matches("billing", "billing"));
+        assert!(glob_matches("bill*", "billing"));
+        assert!(glob_matches("*_log", "audit_log"));
+        assert!(glob_matches("a*b*c", "axxbyyc"));
+        assert!(glob_matches("invoice_202?", "invoice_2024"));
+        assert!(!glob_matches("invoice_202?", "invoice_20245"));
+        assert!(!glob_matches("bill*", "rebilling"));
+    }
+
+    #[test]
+    fn selects_like_pg_dump() {
+        let mut options = options();
+        options.include_schemas = vec!["billing".to_string()];
+        options.exclude_tables = vec!["*.tmp_*".to_string()];
+        assert!(is_selected(&options, "billing", "invoices"));
+        assert!(!is_selected(&options, "billing", "tmp_import"));
+        assert!(!is_selected(&options, "public", "users"));
+        assert!(is_cleaned(&options, "billing", "invoices"));
+
+        // Included tables ignore the schema lists
+        options.include_tables = vec!["public.users".to_string()];
+        assert!(is_selected(&options, "public", "users"));
+        assert!(!is_selected(&options, "billing", "invoices"));
+
+        // Without include lists only public is cleaned
+        let mut options = self::options();
+        options.exclude_schemas = vec!["audit".to_string()];
+        assert!(is_selected(&options, "sales", "orders"));
+        assert!(!is_cleaned(&options, "sales", "orders"));
+        assert!(!is_selected(&options, "audit", "events"));
+        assert!(is_cleaned(&options, "public", "users"));
+    }
+
+    #[test]
+    fn maps_lists_to_pg_dump_switches() {
+        let mut options = options();
+        options.include_schemas = vec!["billing".to_string()];
+        options.exclude_tables = vec!["billing.log_*".to_string()];
+        options.exclude_table_data = vec!["sessions".to_string()];
+        assert_eq!(
+            dump_args(&options),
+            [
+                "--schema",
+                "billing",
+                "--exclude-table",
+                "billing.log_*",
+                "--exclude-table-data",
+                "sessions"
+            ]
+        );
+        assert!(is_data_excluded(&options, "billing", "sessions"));
+        assert!(!is_data_excluded(&options, "billing", "invoices"));
+    }
+
+    #[test]
+    fn drops_row_filters_of_tables_left_out() {
+        let filter = |schema: &str, name: &str| RowFilter {
+            table: TableName {
+                schema: schema.to_string(),
+                name: name.to_string(),
+            },
+            where_clause: "id < 10".to_string(),
+        };
+        let filters = vec![
+            filter("audit", "events"),
+            filter("sales", "tmp_orders"),
+            filter("public", "sessions"),
+            filter("sales", "orders"),
+            filter("public", "users"),
+        ];
+        let mut options = options();
+        options.exclude_schemas = vec!["aud*".to_string()];
+        options.exclude_tables = vec!["*.tmp_*".to_string()];
+        options.exclude_table_data = vec!["sessions".to_string()];
+
+        let tables: Vec<String> = row_filters(&options, filters.clone())
+            .unwrap()
+            .into_iter()
+            .map(|f| f.table.qualified())
+            .collect();
+        assert_eq!(tables, [r#""sales"."orders""#, r#""public"."users""#]);
+        assert!(!is_data_selected(&options, "audit", "events"));
+
+        // Outside the included schemas nothing is dumped, so nothing is filtered
+        options.include_schemas = vec!["sales".to_string()];
+        let tables: Vec<String> = row_filters(&options, filters.clone())
+            .unwrap()
+            .into_iter()
+            .map(|f| f.table.qualified())
+            .collect();
+        assert_eq!(tables, [r#""sales"."orders""#]);
+
+        // `orders` and `sales.orders` may name the same table once resolved
+        let mut twice = filters;
+        twice.push(filter("sales", "orders"));
+        assert!(matches!(
+            row_filters(&options, twice),
+            Err(AppError::InvalidInput(_))
+        ));
+    }
+}
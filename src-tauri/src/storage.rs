@@ -1,40 +1,1867 @@
-use std::fs;
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-use crate::types::AppData;
+use chrono::{DateTime, Utc};
+use rusqlite::backup::{Backup, StepResult};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, Type, ValueRef};
+use rusqlite::{
+    params, Connection, ErrorCode, OpenFlags, OptionalExtension, Row, Transaction,
+    TransactionBehavior,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
 
-const APP_DATA_FILE: &str = "db-clone-data.json";
+use crate::error::{AppError, AppResult};
+use crate::instance;
+use crate::legacy_data::{legacy_data_path, parse_app_data, ParseError};
+use crate::types::{
+    AppDataBackup, CloneHistoryEntry, CloneStatus, CloneType, ConnectionProfile, HistoryFilter,
+    HistoryPairStats, HistoryRetention, MissedRunPolicy, OperationSchedule, SavedOperation, Tag,
+    APP_DATA_VERSION,
+};
 
-pub fn get_app_data_path() -> Option<PathBuf> {
-    dirs::data_local_dir().map(|dir| dir.join("db-clone-app").join(APP_DATA_FILE))
+const DATABASE_FILE: &str = "db-clone-data.sqlite3";
+const BACKUP_DIR: &str = "data-backups";
+const BACKUP_PREFIX: &str = "db-clone-data-";
+const BACKUP_EXTENSION: &str = ".sqlite3";
+/// Number of previous database snapshots kept in the backup directory
+const MAX_BACKUPS: usize = 10;
+/// How long a write waits for another connection (or app instance) to finish
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+const HISTORY_RETENTION_KEY: &str = "history_retention";
+const JOB_CONCURRENCY_KEY: &str = "job_concurrency";
+const MISSED_RUN_POLICY_KEY: &str = "missed_run_policy";
+const LAST_BACKUP_ERROR_KEY: &str = "last_backup_error";
+
+/// Schema changes, applied in order; `PRAGMA user_version` records how many have run
+const SCHEMA_MIGRATIONS: &[&str] = &[
+    r#"
+    CREATE TABLE tags (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        color TEXT NOT NULL
+    );
+
+    CREATE TABLE profiles (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        host TEXT NOT NULL,
+        port INTEGER NOT NULL,
+        database TEXT NOT NULL,
+        user TEXT NOT NULL,
+        password TEXT NOT NULL,
+        ssl INTEGER NOT NULL,
+        tag_id TEXT REFERENCES tags(id) ON DELETE SET NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+
+    CREATE TABLE saved_operations (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        source_id TEXT NOT NULL,
+        destination_id TEXT NOT NULL,
+        clean_destination INTEGER NOT NULL,
+        create_backup INTEGER NOT NULL,
+        clone_type TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    );
+
+    CREATE TABLE history (
+        id TEXT PRIMARY KEY,
+        source_id TEXT NOT NULL,
+        source_name TEXT NOT NULL,
+        destination_id TEXT NOT NULL,
+        destination_name TEXT NOT NULL,
+        clone_type TEXT NOT NULL,
+        status TEXT NOT NULL,
+        started_at TEXT NOT NULL,
+        completed_at TEXT,
+        duration INTEGER,
+        error_message TEXT,
+        error_code TEXT
+    );
+
+    CREATE INDEX history_started_at ON history (started_at);
+
+    CREATE TABLE history_logs (
+        history_id TEXT NOT NULL REFERENCES history(id) ON DELETE CASCADE,
+        seq INTEGER NOT NULL,
+        line TEXT NOT NULL,
+        PRIMARY KEY (history_id, seq)
+    );
+"#,
+    r#"
+    CREATE TABLE settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+
+    CREATE INDEX history_source_id ON history (source_id);
+    CREATE INDEX history_destination_id ON history (destination_id);
+"#,
+    r#"
+    ALTER TABLE history ADD COLUMN verified_tables INTEGER;
+"#,
+    r#"
+    ALTER TABLE history ADD COLUMN options TEXT;
+    ALTER TABLE history ADD COLUMN environment TEXT;
+"#,
+    r#"
+    ALTER TABLE history ADD COLUMN copied_rows TEXT;
+"#,
+    r#"
+    ALTER TABLE saved_operations ADD COLUMN masking TEXT;
+"#,
+    r#"
+    ALTER TABLE history ADD COLUMN database_results TEXT;
+"#,
+    r#"
+    ALTER TABLE history ADD COLUMN destination_results TEXT;
+"#,
+    r#"
+    ALTER TABLE saved_operations ADD COLUMN schedule TEXT;
+    ALTER TABLE saved_operations ADD COLUMN last_scheduled_at TEXT;
+"#,
+    r#"
+    ALTER TABLE history ADD COLUMN owner TEXT;
+"#,
+    r#"
+    ALTER TABLE saved_operations ADD COLUMN options TEXT;
+    UPDATE saved_operations SET options = json_object(
+        'sourceId', source_id,
+        'destinationId', destination_id,
+        'cleanDestination', json(CASE WHEN clean_destination THEN 'true' ELSE 'false' END),
+        'createBackup', json(CASE WHEN create_backup THEN 'true' ELSE 'false' END),
+        'cloneType', clone_type,
+        'excludeTables', json('[]'),
+        'masking', json(masking)
+    );
+"#,
+];
+
+/// Databases this process already took its startup snapshot of
+static BACKED_UP: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Where each corrupt database was copied to, so it is preserved only once
+static PRESERVED_CORRUPT_COPIES: Mutex<Vec<(PathBuf, PathBuf)>> = Mutex::new(Vec::new());
+
+pub fn get_database_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("db-clone-app").join(DATABASE_FILE))
 }
 
-pub fn load_app_data() -> AppData {
-    let Some(path) = get_app_data_path() else {
-        return AppData::default();
-    };
+fn require_database_path() -> AppResult<PathBuf> {
+    get_database_path().ok_or_else(|| AppError::StorageFailed {
+        context: "Could not determine app data directory".to_string(),
+        message: "no local data directory on this platform".to_string(),
+    })
+}
+
+fn timestamp_suffix(now: DateTime<Utc>) -> String {
+    now.format("%Y%m%d_%H%M%S%3f").to_string()
+}
 
-    if !path.exists() {
-        return AppData::default();
+/// Copies a database that SQLite refuses to read next to it under a timestamped name.
+/// The original stays in place so nothing overwrites it until the user restores a backup.
+fn preserve_corrupt_file(path: &Path) -> PathBuf {
+    let mut preserved = PRESERVED_CORRUPT_COPIES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+
+    if let Some((_, copy)) = preserved
+        .iter()
+        .find(|(original, copy)| original == path && copy.exists())
+    {
+        return copy.clone();
     }
 
-    match fs::read_to_string(&path) {
-        Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
-        Err(_) => AppData::default(),
+    let copy = path.with_file_name(format!(
+        "db-clone-data.corrupt-{}{}",
+        timestamp_suffix(Utc::now()),
+        BACKUP_EXTENSION
+    ));
+    if fs::copy(path, &copy).is_ok() {
+        preserved.retain(|(original, _)| original != path);
+        preserved.push((path.to_path_buf(), copy.clone()));
     }
+    copy
 }
 
-pub fn save_app_data(data: &AppData) -> Result<(), String> {
-    let path = get_app_data_path().ok_or("Could not determine app data directory")?;
+/// Maps a SQLite error, keeping a copy of the database file at `path` when it is corrupt
+fn storage_error(path: Option<&Path>, context: &str, error: rusqlite::Error) -> AppError {
+    let corrupt = matches!(
+        error.sqlite_error_code(),
+        Some(ErrorCode::NotADatabase | ErrorCode::DatabaseCorrupt)
+    );
 
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    if corrupt {
+        if let Some(path) = path.filter(|p| p.exists()) {
+            let copy = preserve_corrupt_file(path);
+            return AppError::StorageCorrupt {
+                path: copy.display().to_string(),
+                message: error.to_string(),
+            };
+        }
     }
 
-    let content =
-        serde_json::to_string_pretty(data).map_err(|e| format!("Failed to serialize data: {}", e))?;
+    AppError::storage(context, error)
+}
+
+trait StorageContext<T> {
+    fn context(self, store: &Store, context: &str) -> AppResult<T>;
+}
+
+impl<T> StorageContext<T> for rusqlite::Result<T> {
+    fn context(self, store: &Store, context: &str) -> AppResult<T> {
+        self.map_err(|e| storage_error(store.path.as_deref(), context, e))
+    }
+}
+
+/// Enums are stored as their serde names, e.g. `both` or `success`
+fn enum_to_sql<T: Serialize>(value: &T) -> rusqlite::Result<ToSqlOutput<'static>> {
+    match serde_json::to_value(value) {
+        Ok(Value::String(text)) => Ok(ToSqlOutput::from(text)),
+        Ok(other) => Err(rusqlite::Error::ToSqlConversionFailure(
+            format!("expected a string, got {}", other).into(),
+        )),
+        Err(e) => Err(rusqlite::Error::ToSqlConversionFailure(Box::new(e))),
+    }
+}
+
+fn enum_from_sql<T: DeserializeOwned>(value: ValueRef<'_>) -> FromSqlResult<T> {
+    let text = value.as_str()?;
+    serde_json::from_value(Value::String(text.to_string()))
+        .map_err(|e| FromSqlError::Other(Box::new(e)))
+}
+
+/// Nested structs are stored as JSON text
+fn json_to_sql<T: Serialize>(value: &Option<T>) -> rusqlite::Result<Option<String>> {
+    value
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn json_from_row<T: DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<Option<T>> {
+    let text: Option<String> = row.get(index)?;
+    text.map(|text| serde_json::from_str(&text))
+        .transpose()
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e)))
+}
+
+impl ToSql for CloneType {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        enum_to_sql(self)
+    }
+}
+
+impl FromSql for CloneType {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        enum_from_sql(value)
+    }
+}
+
+impl ToSql for CloneStatus {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        enum_to_sql(self)
+    }
+}
+
+impl FromSql for CloneStatus {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        enum_from_sql(value)
+    }
+}
+
+const PROFILE_COLUMNS: &str =
+    "id, name, host, port, database, user, password, ssl, tag_id, created_at, updated_at";
+
+fn profile_from_row(row: &Row) -> rusqlite::Result<ConnectionProfile> {
+    Ok(ConnectionProfile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        host: row.get(2)?,
+        port: row.get(3)?,
+        database: row.get(4)?,
+        user: row.get(5)?,
+        password: row.get(6)?,
+        ssl: row.get(7)?,
+        tag_id: row.get(8)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+fn tag_from_row(row: &Row) -> rusqlite::Result<Tag> {
+    Ok(Tag {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        color: row.get(2)?,
+    })
+}
+
+const SAVED_OPERATION_COLUMNS: &str = "id, name, source_id, destination_id, clean_destination, \
+     create_backup, clone_type, created_at, masking, schedule, last_scheduled_at, options";
+
+fn saved_operation_from_row(row: &Row) -> rusqlite::Result<SavedOperation> {
+    Ok(SavedOperation {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        source_id: row.get(2)?,
+        destination_id: row.get(3)?,
+        clean_destination: row.get(4)?,
+        create_backup: row.get(5)?,
+        clone_type: row.get(6)?,
+        created_at: row.get(7)?,
+        masking: json_from_row(row, 8)?,
+        schedule: json_from_row(row, 9)?,
+        last_scheduled_at: row.get(10)?,
+        options: json_from_row(row, 11)?,
+    })
+}
+
+/// Conditions shared by history listings and counts, bound as `?1` to `?6`
+const HISTORY_FILTER: &str = "(?1 IS NULL OR source_id = ?1 OR destination_id = ?1)
+    AND (?2 IS NULL OR status = ?2)
+    AND (?3 IS NULL OR clone_type = ?3)
+    AND (?4 IS NULL OR started_at >= ?4)
+    AND (?5 IS NULL OR started_at < ?5)
+    AND (?6 IS NULL OR error_message LIKE ?6 ESCAPE '\\' OR EXISTS (
+        SELECT 1 FROM history_logs
+        WHERE history_logs.history_id = history.id AND history_logs.line LIKE ?6 ESCAPE '\\'
+    ))";
+
+/// Builds a LIKE pattern matching `text` anywhere, with its wildcards escaped
+fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+const HISTORY_COLUMNS: &str = "id, source_id, source_name, destination_id, destination_name, \
+     clone_type, status, started_at, completed_at, duration, error_message, error_code, \
+     verified_tables, options, environment, copied_rows, database_results, \
+     destination_results";
+
+/// Reads a history row; log lines are loaded separately
+fn history_from_row(row: &Row) -> rusqlite::Result<CloneHistoryEntry> {
+    Ok(CloneHistoryEntry {
+        id: row.get(0)?,
+        source_id: row.get(1)?,
+        source_name: row.get(2)?,
+        destination_id: row.get(3)?,
+        destination_name: row.get(4)?,
+        clone_type: row.get(5)?,
+        status: row.get(6)?,
+        started_at: row.get(7)?,
+        completed_at: row.get(8)?,
+        duration: row.get(9)?,
+        error_message: row.get(10)?,
+        error_code: row.get(11)?,
+        verified_tables: row.get(12)?,
+        options: json_from_row(row, 13)?,
+        environment: json_from_row(row, 14)?,
+        copied_rows: json_from_row(row, 15)?,
+        database_results: json_from_row(row, 16)?,
+        destination_results: json_from_row(row, 17)?,
+        logs: Vec::new(),
+    })
+}
+
+/// A profile pointing at a tag that does not exist fails the foreign key
+fn profile_write_error(
+    store: &Store,
+    profile: &ConnectionProfile,
+    error: rusqlite::Error,
+) -> AppError {
+    match (&profile.tag_id, error.sqlite_error_code()) {
+        (Some(tag_id), Some(ErrorCode::ConstraintViolation)) => {
+            AppError::TagNotFound { id: tag_id.clone() }
+        }
+        _ => storage_error(store.path.as_deref(), "Failed to save profile", error),
+    }
+}
+
+/// Handle to the app database. Each command opens its own short-lived connection;
+/// SQLite serializes writers across threads and app instances.
+pub struct Store {
+    conn: Connection,
+    /// File behind the connection; `None` for in-memory stores
+    path: Option<PathBuf>,
+}
+
+impl Store {
+    /// Opens the app database, creating or upgrading it as needed.
+    /// The first open of each process also snapshots the database into the backup directory.
+    pub fn open() -> AppResult<Store> {
+        Store::open_at(&require_database_path()?)
+    }
+
+    /// Opens the database at `path` the same way; the JSON file of earlier versions
+    /// is looked for next to it.
+    pub fn open_at(path: &Path) -> AppResult<Store> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AppError::storage("Failed to create directory", e))?;
+        }
+
+        let existed = path.exists();
+        let conn = Connection::open(path)
+            .map_err(|e| storage_error(Some(path), "Failed to open app database", e))?;
+        let store = Store::from_connection(conn, Some(path.to_path_buf()))?;
+
+        // A failed snapshot must not keep the app from starting; it is recorded for
+        // the backups list instead
+        let backup = (existed && first_open(path)).then(|| store.backup_to(&backup_dir(path)));
+
+        store.migrate(Some(&legacy_data_path(path)))?;
+        if let Some(result) = backup {
+            store.set_setting(LAST_BACKUP_ERROR_KEY, &result.err().map(|e| e.to_string()))?;
+        }
+        Ok(store)
+    }
+
+    fn from_connection(conn: Connection, path: Option<PathBuf>) -> AppResult<Store> {
+        let store = Store { conn, path };
+        store
+            .conn
+            .busy_timeout(BUSY_TIMEOUT)
+            .and_then(|_| store.conn.pragma_update(None, "foreign_keys", true))
+            .context(&store, "Failed to configure app database")?;
+        Ok(store)
+    }
+
+    fn user_version(&self) -> AppResult<usize> {
+        self.conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .context(self, "Failed to read app database")
+    }
+
+    /// Applies pending schema migrations. A brand new database also imports the JSON
+    /// data file from earlier versions, which is renamed once the import is committed.
+    fn migrate(&self, legacy_path: Option<&Path>) -> AppResult<()> {
+        let latest = SCHEMA_MIGRATIONS.len();
+        if self.user_version()? == latest {
+            return Ok(());
+        }
+
+        let tx = Transaction::new_unchecked(&self.conn, TransactionBehavior::Immediate)
+            .context(self, "Failed to lock app database")?;
+
+        // Another connection may have migrated while this one waited for the lock
+        let version = self.user_version()?;
+        if version > latest {
+            return Err(AppError::StorageFailed {
+                context: "App data was saved by a newer version of the app".to_string(),
+                message: format!("schema version {}, supported up to {}", version, latest),
+            });
+        }
+
+        for migration in &SCHEMA_MIGRATIONS[version..] {
+            tx.execute_batch(migration)
+                .context(self, "Failed to upgrade app database")?;
+        }
+
+        let legacy_path = legacy_path.filter(|p| version == 0 && p.exists());
+        if let Some(path) = legacy_path {
+            self.import_legacy_file(path)?;
+        }
+
+        tx.pragma_update(None, "user_version", latest)
+            .context(self, "Failed to upgrade app database")?;
+        tx.commit()
+            .context(self, "Failed to upgrade app database")?;
+
+        if let Some(path) = legacy_path {
+            let _ = fs::rename(path, path.with_extension("json.imported"));
+        }
+
+        Ok(())
+    }
+
+    /// Copies profiles, tags, saved operations and history from the old JSON file.
+    /// A file that cannot be parsed is moved aside under a timestamped name and
+    /// reported once; the next open starts out empty.
+    fn import_legacy_file(&self, path: &Path) -> AppResult<()> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| AppError::storage("Failed to read old app data file", e))?;
+
+        let data = match parse_app_data(&content) {
+            Ok(data) => data,
+            Err(ParseError::TooNew(version)) => {
+                return Err(AppError::StorageFailed {
+                    context: "App data was saved by a newer version of the app".to_string(),
+                    message: format!(
+                        "file version {}, supported up to {}",
+                        version, APP_DATA_VERSION
+                    ),
+                });
+            }
+            Err(ParseError::Invalid(message)) => {
+                let copy =
+                    path.with_extension(format!("json.corrupt-{}", timestamp_suffix(Utc::now())));
+                fs::rename(path, &copy)
+                    .map_err(|e| AppError::storage("Failed to move corrupt app data file", e))?;
+                return Err(AppError::StorageCorrupt {
+                    path: copy.display().to_string(),
+                    message,
+                });
+            }
+        };
+
+        let mut tag_ids = HashSet::new();
+        for tag in &data.tags {
+            if tag_ids.insert(tag.id.clone()) {
+                self.insert_tag(tag)?;
+            }
+        }
+
+        let mut profile_ids = HashSet::new();
+        for profile in &data.profiles {
+            if !profile_ids.insert(profile.id.clone()) {
+                continue;
+            }
+            let mut profile = profile.clone();
+            // The JSON file never checked that tags exist
+            profile.tag_id = profile.tag_id.filter(|id| tag_ids.contains(id));
+            self.insert_profile(&profile)?;
+        }
+
+        let mut operation_ids = HashSet::new();
+        for operation in &data.saved_operations {
+            if operation_ids.insert(operation.id.clone()) {
+                self.insert_saved_operation(operation)?;
+            }
+        }
+
+        let mut history_ids = HashSet::new();
+        for entry in &data.history {
+            if history_ids.insert(entry.id.clone()) {
+                self.write_history_entry(entry, None)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Profiles
+
+    pub fn list_profiles(&self) -> AppResult<Vec<ConnectionProfile>> {
+        let sql = format!("SELECT {} FROM profiles ORDER BY rowid", PROFILE_COLUMNS);
+        let mut stmt = self
+            .conn
+            .prepare(&sql)
+            .context(self, "Failed to load profiles")?;
+        let rows = stmt
+            .query_map([], profile_from_row)
+            .and_then(|rows| rows.collect());
+        rows.context(self, "Failed to load profiles")
+    }
+
+    pub fn get_profile(&self, id: &str) -> AppResult<Option<ConnectionProfile>> {
+        let sql = format!("SELECT {} FROM profiles WHERE id = ?1", PROFILE_COLUMNS);
+        self.conn
+            .query_row(&sql, [id], profile_from_row)
+            .optional()
+            .context(self, "Failed to load profile")
+    }
+
+    pub fn insert_profile(&self, profile: &ConnectionProfile) -> AppResult<()> {
+        let sql = format!(
+            "INSERT INTO profiles ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            PROFILE_COLUMNS
+        );
+        self.conn
+            .execute(
+                &sql,
+                params![
+                    profile.id,
+                    profile.name,
+                    profile.host,
+                    profile.port,
+                    profile.database,
+                    profile.user,
+                    profile.password,
+                    profile.ssl,
+                    profile.tag_id,
+                    profile.created_at,
+                    profile.updated_at,
+                ],
+            )
+            .map_err(|e| profile_write_error(self, profile, e))?;
+        Ok(())
+    }
+
+    pub fn update_profile(&self, profile: &ConnectionProfile) -> AppResult<()> {
+        let updated = self
+            .conn
+            .execute(
+                "UPDATE profiles SET name = ?2, host = ?3, port = ?4, database = ?5, user = ?6,
+                    password = ?7, ssl = ?8, tag_id = ?9, updated_at = ?10
+                 WHERE id = ?1",
+                params![
+                    profile.id,
+                    profile.name,
+                    profile.host,
+                    profile.port,
+                    profile.database,
+                    profile.user,
+                    profile.password,
+                    profile.ssl,
+                    profile.tag_id,
+                    profile.updated_at,
+                ],
+            )
+            .map_err(|e| profile_write_error(self, profile, e))?;
+
+        if updated == 0 {
+            return Err(AppError::ProfileNotFound {
+                id: profile.id.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn delete_profile(&self, id: &str) -> AppResult<()> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM profiles WHERE id = ?1", [id])
+            .context(self, "Failed to delete profile")?;
+
+        if deleted == 0 {
+            return Err(AppError::ProfileNotFound { id: id.to_string() });
+        }
+        Ok(())
+    }
+
+    // Tags
+
+    pub fn list_tags(&self) -> AppResult<Vec<Tag>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, name, color FROM tags ORDER BY rowid")
+            .context(self, "Failed to load tags")?;
+        let rows = stmt
+            .query_map([], tag_from_row)
+            .and_then(|rows| rows.collect());
+        rows.context(self, "Failed to load tags")
+    }
+
+    pub fn insert_tag(&self, tag: &Tag) -> AppResult<()> {
+        self.conn
+            .execute(
+                "INSERT INTO tags (id, name, color) VALUES (?1, ?2, ?3)",
+                params![tag.id, tag.name, tag.color],
+            )
+            .context(self, "Failed to save tag")?;
+        Ok(())
+    }
+
+    pub fn update_tag(&self, tag: &Tag) -> AppResult<()> {
+        let updated = self
+            .conn
+            .execute(
+                "UPDATE tags SET name = ?2, color = ?3 WHERE id = ?1",
+                params![tag.id, tag.name, tag.color],
+            )
+            .context(self, "Failed to save tag")?;
+
+        if updated == 0 {
+            return Err(AppError::TagNotFound { id: tag.id.clone() });
+        }
+        Ok(())
+    }
+
+    /// Deletes a tag; profiles using it are left untagged
+    pub fn delete_tag(&self, id: &str) -> AppResult<()> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM tags WHERE id = ?1", [id])
+            .context(self, "Failed to delete tag")?;
+
+        if deleted == 0 {
+            return Err(AppError::TagNotFound { id: id.to_string() });
+        }
+        Ok(())
+    }
+
+    // Saved operations
+
+    pub fn list_saved_operations(&self) -> AppResult<Vec<SavedOperation>> {
+        let sql = format!(
+            "SELECT {} FROM saved_operations ORDER BY rowid",
+            SAVED_OPERATION_COLUMNS
+        );
+        let mut stmt = self
+            .conn
+            .prepare(&sql)
+            .context(self, "Failed to load saved operations")?;
+        let rows = stmt
+            .query_map([], saved_operation_from_row)
+            .and_then(|rows| rows.collect());
+        rows.context(self, "Failed to load saved operations")
+    }
+
+    pub fn insert_saved_operation(&self, operation: &SavedOperation) -> AppResult<()> {
+        let sql = format!(
+            "INSERT INTO saved_operations ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            SAVED_OPERATION_COLUMNS
+        );
+        self.conn
+            .execute(
+                &sql,
+                params![
+                    operation.id,
+                    operation.name,
+                    operation.source_id,
+                    operation.destination_id,
+                    operation.clean_destination,
+                    operation.create_backup,
+                    operation.clone_type,
+                    operation.created_at,
+                    json_to_sql(&operation.masking).context(self, "Failed to save operation")?,
+                    json_to_sql(&operation.schedule).context(self, "Failed to save operation")?,
+                    operation.last_scheduled_at,
+                    json_to_sql(&Some(operation.clone_options()))
+                        .context(self, "Failed to save operation")?,
+                ],
+            )
+            .context(self, "Failed to save operation")?;
+        Ok(())
+    }
+
+    /// Replaces the schedule of an operation; `since` is where the next run is counted from
+    pub fn set_operation_schedule(
+        &self,
+        id: &str,
+        schedule: &Option<OperationSchedule>,
+        since: DateTime<Utc>,
+    ) -> AppResult<()> {
+        let updated = self
+            .conn
+            .execute(
+                "UPDATE saved_operations SET schedule = ?2, last_scheduled_at = ?3 WHERE id = ?1",
+                params![
+                    id,
+                    json_to_sql(schedule).context(self, "Failed to save schedule")?,
+                    since
+                ],
+            )
+            .context(self, "Failed to save schedule")?;
+
+        if updated == 0 {
+            return Err(AppError::SavedOperationNotFound { id: id.to_string() });
+        }
+        Ok(())
+    }
+
+    /// Marks a scheduled run as handled, only if no one else did since `seen` was read.
+    /// Returns whether this caller got the run.
+    pub fn claim_scheduled_run(
+        &self,
+        id: &str,
+        seen: Option<DateTime<Utc>>,
+        at: DateTime<Utc>,
+    ) -> AppResult<bool> {
+        let claimed = self
+            .conn
+            .execute(
+                "UPDATE saved_operations SET last_scheduled_at = ?3
+                 WHERE id = ?1 AND last_scheduled_at IS ?2",
+                params![id, seen, at],
+            )
+            .context(self, "Failed to save schedule")?;
+        Ok(claimed == 1)
+    }
+
+    pub fn delete_saved_operation(&self, id: &str) -> AppResult<()> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM saved_operations WHERE id = ?1", [id])
+            .context(self, "Failed to delete saved operation")?;
+
+        if deleted == 0 {
+            return Err(AppError::SavedOperationNotFound { id: id.to_string() });
+        }
+        Ok(())
+    }
+
+    // History
+
+    /// Stores a clone together with its log lines, updating any earlier snapshot of it
+    pub fn save_history_entry(&self, entry: &CloneHistoryEntry) -> AppResult<()> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .context(self, "Failed to save history")?;
+        self.write_history_entry(entry, Some(instance::id()))?;
+        tx.commit().context(self, "Failed to save history")
+    }
+
+    /// Marks clones left running by processes that have exited as interrupted.
+    /// Clones of processes for which `is_alive` holds are left alone.
+    pub fn mark_interrupted_history(&self, is_alive: impl Fn(&str) -> bool) -> AppResult<usize> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT DISTINCT owner FROM history WHERE status = ?1")
+            .context(self, "Failed to update history")?;
+        let owners = stmt
+            .query_map([CloneStatus::Running], |row| {
+                row.get::<_, Option<String>>(0)
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .context(self, "Failed to update history")?;
+
+        let mut marked = 0;
+        for owner in owners {
+            if owner.as_deref().is_some_and(&is_alive) {
+                continue;
+            }
+            marked += self
+                .conn
+                .execute(
+                    "UPDATE history SET status = ?1, error_message = ?2 \
+                     WHERE status = ?3 AND owner IS ?4",
+                    params![
+                        CloneStatus::Interrupted,
+                        "The app closed before the clone finished",
+                        CloneStatus::Running,
+                        owner,
+                    ],
+                )
+                .context(self, "Failed to update history")?;
+        }
+        Ok(marked)
+    }
+
+    /// Inserts or updates the row, recording `owner` as the process running the clone.
+    /// Log lines only ever get appended while a clone runs, so just the ones past
+    /// those already stored are written.
+    fn write_history_entry(&self, entry: &CloneHistoryEntry, owner: Option<&str>) -> AppResult<()> {
+        let updates = HISTORY_COLUMNS
+            .split(',')
+            .map(str::trim)
+            .filter(|column| *column != "id")
+            .chain(["owner"])
+            .map(|column| format!("{0} = excluded.{0}", column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!(
+            "INSERT INTO history ({}, owner) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19) \
+             ON CONFLICT (id) DO UPDATE SET {}",
+            HISTORY_COLUMNS, updates
+        );
+        self.conn
+            .execute(
+                &sql,
+                params![
+                    entry.id,
+                    entry.source_id,
+                    entry.source_name,
+                    entry.destination_id,
+                    entry.destination_name,
+                    entry.clone_type,
+                    entry.status,
+                    entry.started_at,
+                    entry.completed_at,
+                    entry.duration,
+                    entry.error_message,
+                    entry.error_code,
+                    entry.verified_tables,
+                    json_to_sql(&entry.options).context(self, "Failed to save history")?,
+                    json_to_sql(&entry.environment).context(self, "Failed to save history")?,
+                    json_to_sql(&entry.copied_rows).context(self, "Failed to save history")?,
+                    json_to_sql(&entry.database_results).context(self, "Failed to save history")?,
+                    json_to_sql(&entry.destination_results)
+                        .context(self, "Failed to save history")?,
+                    owner,
+                ],
+            )
+            .context(self, "Failed to save history")?;
+
+        let stored: usize = self
+            .conn
+            .query_row(
+                "SELECT count(*) FROM history_logs WHERE history_id = ?1",
+                [&entry.id],
+                |row| row.get(0),
+            )
+            .context(self, "Failed to save history logs")?;
+        let mut stmt = self
+            .conn
+            .prepare("INSERT INTO history_logs (history_id, seq, line) VALUES (?1, ?2, ?3)")
+            .context(self, "Failed to save history logs")?;
+        for (seq, line) in entry.logs.iter().enumerate().skip(stored) {
+            stmt.execute(params![entry.id, seq, line])
+                .context(self, "Failed to save history logs")?;
+        }
+
+        Ok(())
+    }
+
+    /// Lists history entries newest first, without their log lines
+    pub fn list_history(&self, filter: &HistoryFilter) -> AppResult<Vec<CloneHistoryEntry>> {
+        let sql = format!(
+            "SELECT {} FROM history WHERE {}
+             ORDER BY started_at DESC, rowid DESC
+             LIMIT ?7 OFFSET ?8",
+            HISTORY_COLUMNS, HISTORY_FILTER
+        );
+        // SQLite treats a negative LIMIT as no limit
+        let limit = filter.limit.map_or(-1, i64::from);
+        let offset = filter.offset.unwrap_or(0);
+        let search = filter.search.as_deref().map(like_pattern);
+
+        let mut stmt = self
+            .conn
+            .prepare(&sql)
+            .context(self, "Failed to load history")?;
+        let rows = stmt
+            .query_map(
+                params![
+                    filter.profile_id,
+                    filter.status,
+                    filter.clone_type,
+                    filter.started_after,
+                    filter.started_before,
+                    search,
+                    limit,
+                    offset,
+                ],
+                history_from_row,
+            )
+            .and_then(|rows| rows.collect());
+        rows.context(self, "Failed to load history")
+    }
+
+    /// Number of entries matching the filter, ignoring its limit and offset
+    pub fn count_history(&self, filter: &HistoryFilter) -> AppResult<u64> {
+        let sql = format!("SELECT COUNT(*) FROM history WHERE {}", HISTORY_FILTER);
+        let search = filter.search.as_deref().map(like_pattern);
+
+        self.conn
+            .query_row(
+                &sql,
+                params![
+                    filter.profile_id,
+                    filter.status,
+                    filter.clone_type,
+                    filter.started_after,
+                    filter.started_before,
+                    search,
+                ],
+                |row| row.get(0),
+            )
+            .context(self, "Failed to count history")
+    }
+
+    /// Average duration and outcome counts for every source/destination pair
+    pub fn history_stats(&self) -> AppResult<Vec<HistoryPairStats>> {
+        // With MAX(), SQLite takes the bare name columns from the latest row of each group,
+        // so renamed profiles show their current name
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT source_id, source_name, destination_id, destination_name,
+                    COUNT(*),
+                    SUM(status = 'success'),
+                    SUM(status = 'error'),
+                    AVG(CASE WHEN status = 'success' THEN duration END),
+                    MAX(started_at)
+                 FROM history
+                 GROUP BY source_id, destination_id
+                 ORDER BY MAX(started_at) DESC",
+            )
+            .context(self, "Failed to load history stats")?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(HistoryPairStats {
+                    source_id: row.get(0)?,
+                    source_name: row.get(1)?,
+                    destination_id: row.get(2)?,
+                    destination_name: row.get(3)?,
+                    runs: row.get(4)?,
+                    successes: row.get(5)?,
+                    failures: row.get(6)?,
+                    average_duration: row.get(7)?,
+                    last_run_at: row.get(8)?,
+                })
+            })
+            .and_then(|rows| rows.collect());
+        rows.context(self, "Failed to load history stats")
+    }
+
+    /// Loads one history entry with all of its log lines
+    pub fn get_history_entry(&self, id: &str) -> AppResult<Option<CloneHistoryEntry>> {
+        let sql = format!("SELECT {} FROM history WHERE id = ?1", HISTORY_COLUMNS);
+        let entry = self
+            .conn
+            .query_row(&sql, [id], history_from_row)
+            .optional()
+            .context(self, "Failed to load history")?;
+
+        let Some(mut entry) = entry else {
+            return Ok(None);
+        };
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT line FROM history_logs WHERE history_id = ?1 ORDER BY seq")
+            .context(self, "Failed to load history logs")?;
+        entry.logs = stmt
+            .query_map([id], |row| row.get(0))
+            .and_then(|rows| rows.collect())
+            .context(self, "Failed to load history logs")?;
+
+        Ok(Some(entry))
+    }
+
+    pub fn delete_history_entry(&self, id: &str) -> AppResult<()> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM history WHERE id = ?1", [id])
+            .context(self, "Failed to delete history entry")?;
+
+        if deleted == 0 {
+            return Err(AppError::HistoryEntryNotFound { id: id.to_string() });
+        }
+        Ok(())
+    }
+
+    pub fn clear_history(&self) -> AppResult<()> {
+        self.conn
+            .execute("DELETE FROM history", [])
+            .context(self, "Failed to clear history")?;
+        Ok(())
+    }
+
+    /// Deletes all but the `keep` most recent history entries
+    fn prune_history(&self, keep: u32) -> AppResult<()> {
+        self.conn
+            .execute(
+                "DELETE FROM history WHERE id NOT IN (
+                    SELECT id FROM history ORDER BY started_at DESC, rowid DESC LIMIT ?1
+                 )",
+                [keep],
+            )
+            .context(self, "Failed to prune history")?;
+        Ok(())
+    }
+
+    /// Deletes history entries that fall outside the configured retention
+    pub fn apply_history_retention(&self) -> AppResult<()> {
+        let retention = self.history_retention()?;
+
+        if let Some(days) = retention.max_age_days {
+            let cutoff = Utc::now() - chrono::Duration::days(i64::from(days));
+            self.conn
+                .execute("DELETE FROM history WHERE started_at < ?1", [cutoff])
+                .context(self, "Failed to prune history")?;
+        }
+        if let Some(max_entries) = retention.max_entries {
+            self.prune_history(max_entries)?;
+        }
+
+        Ok(())
+    }
 
-    fs::write(&path, content).map_err(|e| format!("Failed to write file: {}", e))?;
+    // Settings
+
+    fn get_setting<T: DeserializeOwned>(&self, key: &str) -> AppResult<Option<T>> {
+        let value: Option<String> = self
+            .conn
+            .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| {
+                row.get(0)
+            })
+            .optional()
+            .context(self, "Failed to load settings")?;
+
+        value
+            .map(|v| serde_json::from_str(&v))
+            .transpose()
+            .map_err(|e| AppError::storage("Failed to read setting", e))
+    }
+
+    fn set_setting<T: Serialize>(&self, key: &str, value: &T) -> AppResult<()> {
+        let value = serde_json::to_string(value)
+            .map_err(|e| AppError::storage("Failed to serialize setting", e))?;
+
+        self.conn
+            .execute(
+                "INSERT INTO settings (key, value) VALUES (?1, ?2)
+                 ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+                [key, &value],
+            )
+            .context(self, "Failed to save settings")?;
+        Ok(())
+    }
+
+    pub fn history_retention(&self) -> AppResult<HistoryRetention> {
+        Ok(self.get_setting(HISTORY_RETENTION_KEY)?.unwrap_or_default())
+    }
+
+    pub fn set_history_retention(&self, retention: &HistoryRetention) -> AppResult<()> {
+        self.set_setting(HISTORY_RETENTION_KEY, retention)
+    }
+
+    /// How many queued clones may run at once
+    pub fn job_concurrency(&self) -> AppResult<Option<usize>> {
+        self.get_setting(JOB_CONCURRENCY_KEY)
+    }
+
+    pub fn set_job_concurrency(&self, limit: usize) -> AppResult<()> {
+        self.set_setting(JOB_CONCURRENCY_KEY, &limit)
+    }
+
+    pub fn missed_run_policy(&self) -> AppResult<MissedRunPolicy> {
+        Ok(self.get_setting(MISSED_RUN_POLICY_KEY)?.unwrap_or_default())
+    }
+
+    pub fn set_missed_run_policy(&self, policy: MissedRunPolicy) -> AppResult<()> {
+        self.set_setting(MISSED_RUN_POLICY_KEY, &policy)
+    }
+
+    // Backups
+
+    /// Why the snapshot taken on this process's first open failed, if it did
+    pub fn last_backup_error(&self) -> AppResult<Option<String>> {
+        Ok(self.get_setting(LAST_BACKUP_ERROR_KEY)?.flatten())
+    }
+
+    /// Writes a consistent snapshot of the database and prunes the oldest snapshots
+    fn backup_to(&self, dir: &Path) -> AppResult<()> {
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::storage("Failed to create backup directory", e))?;
+
+        let backup = dir.join(format!(
+            "{}{}{}",
+            BACKUP_PREFIX,
+            timestamp_suffix(Utc::now()),
+            BACKUP_EXTENSION
+        ));
+        self.conn
+            .execute("VACUUM INTO ?1", [backup.display().to_string()])
+            .context(self, "Failed to back up app data")?;
+
+        for old in list_backup_files(dir).iter().skip(MAX_BACKUPS) {
+            let _ = fs::remove_file(old);
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether this is the first time this process opens the database at `path`
+fn first_open(path: &Path) -> bool {
+    let mut backed_up = BACKED_UP.lock().unwrap_or_else(|e| e.into_inner());
+    if backed_up.iter().any(|p| p == path) {
+        return false;
+    }
+    backed_up.push(path.to_path_buf());
+    true
+}
+
+fn backup_dir(path: &Path) -> PathBuf {
+    path.with_file_name(BACKUP_DIR)
+}
+
+/// Backup files in the directory, newest first
+fn list_backup_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(BACKUP_PREFIX) && n.ends_with(BACKUP_EXTENSION))
+        })
+        .collect();
+
+    // Names embed a sortable timestamp
+    files.sort();
+    files.reverse();
+    files
+}
+
+/// Checks that a file is a readable database this version of the app understands
+fn verify_database_file(path: &Path) -> Result<(), String> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| e.to_string())?;
+
+    let check: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if check != "ok" {
+        return Err(check);
+    }
+
+    let version: usize = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if version > SCHEMA_MIGRATIONS.len() {
+        return Err(format!("unsupported schema version {}", version));
+    }
+
+    Ok(())
+}
+
+/// Copies `source` to a temp file, flushes it to disk and renames it over `target`,
+/// so other connections never see a half-written database.
+fn replace_file(source: &Path, target: &Path) -> AppResult<()> {
+    let tmp_path = target.with_extension("sqlite3.tmp");
+
+    fs::copy(source, &tmp_path)
+        .and_then(|_| File::options().write(true).open(&tmp_path))
+        .and_then(|file| file.sync_all())
+        .map_err(|e| AppError::storage("Failed to write file", e))?;
+
+    fs::rename(&tmp_path, target).map_err(|e| AppError::storage("Failed to replace file", e))?;
+
+    // Make the rename itself durable
+    #[cfg(unix)]
+    if let Some(parent) = target.parent() {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
 
     Ok(())
 }
+
+pub fn list_backups() -> AppResult<Vec<AppDataBackup>> {
+    let path = require_database_path()?;
+
+    Ok(list_backup_files(&backup_dir(&path))
+        .into_iter()
+        .filter_map(|file| {
+            let metadata = fs::metadata(&file).ok()?;
+            Some(AppDataBackup {
+                name: file.file_name()?.to_str()?.to_string(),
+                created_at: metadata.modified().ok()?.into(),
+                size: metadata.len(),
+            })
+        })
+        .collect())
+}
+
+/// Copies the database at `source` over the one at `path` with SQLite's online
+/// backup, which takes the write lock for the whole copy. Connections left open on
+/// `path` read the copied data from their next statement on.
+fn copy_database_into(source: &Path, path: &Path) -> AppResult<()> {
+    let from = Connection::open_with_flags(source, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| AppError::storage("Failed to open backup", e))?;
+    let mut to = Connection::open(path)
+        .map_err(|e| storage_error(Some(path), "Failed to open app database", e))?;
+    let backup = Backup::new(&from, &mut to)
+        .map_err(|e| AppError::storage("Failed to restore backup", e))?;
+
+    // Writers on other connections get the same grace as with the busy timeout
+    let deadline = Instant::now() + BUSY_TIMEOUT;
+    loop {
+        let step = backup
+            .step(-1)
+            .map_err(|e| AppError::storage("Failed to restore backup", e))?;
+        match step {
+            StepResult::Done => return Ok(()),
+            StepResult::More => {}
+            _ if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(50)),
+            _ => {
+                return Err(AppError::storage(
+                    "Failed to restore backup",
+                    "app data is being written by another process",
+                ))
+            }
+        }
+    }
+}
+
+/// Replaces the app database with one of the rotating backups.
+/// The current database is backed up first when it is still readable.
+pub fn restore_backup(name: &str) -> AppResult<()> {
+    restore_backup_at(&require_database_path()?, name)
+}
+
+fn restore_backup_at(path: &Path, name: &str) -> AppResult<()> {
+    let dir = backup_dir(path);
+
+    let backup = list_backup_files(&dir)
+        .into_iter()
+        .find(|file| file.file_name().and_then(|n| n.to_str()) == Some(name))
+        .ok_or_else(|| AppError::InvalidInput(format!("Backup not found: {}", name)))?;
+
+    verify_database_file(&backup).map_err(|message| AppError::StorageCorrupt {
+        path: backup.display().to_string(),
+        message,
+    })?;
+
+    if verify_database_file(path).is_ok() {
+        let conn = Connection::open(path)
+            .map_err(|e| storage_error(Some(path), "Failed to open app database", e))?;
+        Store::from_connection(conn, Some(path.to_path_buf()))?.backup_to(&dir)?;
+        // The CLI or the scheduler may have the database open, so it is copied into
+        // in place rather than swapped for another file
+        copy_database_into(&backup, path)?;
+    } else {
+        // Nothing can read a corrupt database, so nobody is left holding the old file
+        replace_file(&backup, path)?;
+    }
+
+    PRESERVED_CORRUPT_COPIES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .retain(|(original, _)| original != path);
+
+    // Snapshots from older versions are upgraded right away
+    Store::open_at(path).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{CloneEnvironment, CloneOptions, TableRowFilter, ToolInfo};
+
+    /// In-memory store seeded from a legacy JSON fixture
+    fn store_from_fixture(content: &str) -> Store {
+        let dir = std::env::temp_dir().join(format!("db-clone-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let legacy = dir.join("db-clone-data.json");
+        fs::write(&legacy, content).unwrap();
+
+        let store = Store::from_connection(Connection::open_in_memory().unwrap(), None).unwrap();
+        store.migrate(Some(&legacy)).unwrap();
+
+        assert!(!legacy.exists());
+        assert!(dir.join("db-clone-data.json.imported").exists());
+        let _ = fs::remove_dir_all(&dir);
+        store
+    }
+
+    /// Database path in a fresh temp directory
+    fn temp_database() -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("db-clone-test-{}", uuid::Uuid::new_v4()))
+            .join(DATABASE_FILE)
+    }
+
+    #[test]
+    fn imports_legacy_json_on_first_start() {
+        let store = store_from_fixture(include_str!("../tests/fixtures/app-data-v0.json"));
+
+        let profiles = store.list_profiles().unwrap();
+        let tags = store.list_tags().unwrap();
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(tags.len(), 1);
+        assert_eq!(profiles[0].tag_id.as_deref(), Some(tags[0].id.as_str()));
+        assert_eq!(store.list_saved_operations().unwrap().len(), 1);
+
+        let history = store.list_history(&HistoryFilter::default()).unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(history[0].logs.is_empty());
+
+        let entry = store.get_history_entry(&history[0].id).unwrap().unwrap();
+        assert_eq!(entry.logs.len(), 3);
+    }
+
+    #[test]
+    fn sets_aside_a_corrupt_legacy_file() {
+        let dir = std::env::temp_dir().join(format!("db-clone-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let legacy = dir.join("db-clone-data.json");
+        fs::write(&legacy, "{ not json").unwrap();
+
+        let store = Store::from_connection(Connection::open_in_memory().unwrap(), None).unwrap();
+        let reported = match store.migrate(Some(&legacy)) {
+            Err(AppError::StorageCorrupt { path, .. }) => PathBuf::from(path),
+            other => panic!("expected a corrupt file error, got {:?}", other),
+        };
+
+        // Once set aside, the file no longer keeps the store from opening
+        store.migrate(Some(&legacy)).unwrap();
+        assert!(store.list_profiles().unwrap().is_empty());
+        assert!(!legacy.exists());
+        let moved: Vec<PathBuf> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(moved, [reported]);
+        assert!(moved[0]
+            .to_string_lossy()
+            .contains("db-clone-data.json.corrupt-"));
+        assert_eq!(fs::read_to_string(&moved[0]).unwrap(), "{ not json");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    /// Fixture history (one old run) plus five runs in the other direction, alternating
+    /// between success and error
+    fn store_with_runs() -> (Store, Vec<ConnectionProfile>) {
+        let store = store_from_fixture(include_str!("../tests/fixtures/app-data-v0-initial.json"));
+        let profiles = store.list_profiles().unwrap();
+        let options = CloneOptions {
+            clean_destination: true,
+            exclude_tables: vec!["audit_log".to_string()],
+            ..CloneOptions::new(
+                profiles[1].id.clone(),
+                profiles[0].id.clone(),
+                CloneType::Data,
+            )
+        };
+
+        for i in 0..5 {
+            let mut entry = CloneHistoryEntry::new(&profiles[1], &profiles[0], &options);
+            entry.started_at = Utc::now() + chrono::Duration::seconds(i);
+            let status = if i % 2 == 0 {
+                CloneStatus::Success
+            } else {
+                CloneStatus::Error
+            };
+            entry.complete(status, None);
+            entry.add_log(format!("run {}", i));
+            store.save_history_entry(&entry).unwrap();
+        }
+
+        (store, profiles)
+    }
+
+    #[test]
+    fn keeps_clone_options_and_environment() {
+        let (store, profiles) = store_with_runs();
+        let latest = store.list_history(&HistoryFilter::default()).unwrap();
+
+        let mut entry = store.get_history_entry(&latest[0].id).unwrap().unwrap();
+        let options = entry.options.clone().unwrap();
+        assert_eq!(options.source_id, profiles[1].id);
+        assert!(options.clean_destination);
+        assert_eq!(options.exclude_tables, ["audit_log"]);
+
+        entry.id = "with-environment".to_string();
+        entry.environment = Some(CloneEnvironment {
+            parallel_jobs: Some(4),
+            pg_dump: ToolInfo {
+                path: "/usr/bin/pg_dump".to_string(),
+                version: Some("pg_dump (PostgreSQL) 16.2".to_string()),
+            },
+            pg_restore: ToolInfo {
+                path: "/usr/bin/pg_restore".to_string(),
+                version: None,
+            },
+            psql: ToolInfo {
+                path: "/usr/bin/psql".to_string(),
+                version: None,
+            },
+            source_server_version: Some("15.6".to_string()),
+            destination_server_version: None,
+        });
+        store.save_history_entry(&entry).unwrap();
+
+        let environment = store
+            .get_history_entry("with-environment")
+            .unwrap()
+            .unwrap()
+            .environment
+            .unwrap();
+        assert_eq!(environment.parallel_jobs, Some(4));
+        assert_eq!(environment.pg_dump.path, "/usr/bin/pg_dump");
+        assert_eq!(environment.source_server_version.as_deref(), Some("15.6"));
+
+        // Entries imported from the JSON file predate recorded options
+        let imported = store.get_history_entry(&latest[5].id).unwrap().unwrap();
+        assert!(imported.options.is_none());
+    }
+
+    #[test]
+    fn marks_running_clones_interrupted() {
+        let (store, _) = store_with_runs();
+        let mut entry = store
+            .list_history(&HistoryFilter::default())
+            .unwrap()
+            .remove(0);
+        entry.id = "running".to_string();
+        entry.status = CloneStatus::Running;
+        store.save_history_entry(&entry).unwrap();
+
+        // Later snapshots update the row and append the new log lines
+        entry.add_log("dumped".to_string());
+        store.save_history_entry(&entry).unwrap();
+        entry.add_log("restored".to_string());
+        entry.verified_tables = Some(2);
+        store.save_history_entry(&entry).unwrap();
+        let saved = store.get_history_entry("running").unwrap().unwrap();
+        assert_eq!(saved.logs, ["dumped", "restored"]);
+        assert_eq!(saved.verified_tables, Some(2));
+
+        // Clones of a process that is still running are not touched
+        assert_eq!(store.mark_interrupted_history(|_| true).unwrap(), 0);
+        assert!(matches!(
+            store.get_history_entry("running").unwrap().unwrap().status,
+            CloneStatus::Running
+        ));
+
+        assert_eq!(store.mark_interrupted_history(|_| false).unwrap(), 1);
+        let entry = store.get_history_entry("running").unwrap().unwrap();
+        assert!(matches!(entry.status, CloneStatus::Interrupted));
+        assert!(entry.error_message.is_some());
+        assert_eq!(store.mark_interrupted_history(|_| false).unwrap(), 0);
+    }
+
+    fn count(store: &Store, filter: HistoryFilter) -> u64 {
+        store.count_history(&filter).unwrap()
+    }
+
+    #[test]
+    fn filters_and_pages_history() {
+        let (store, profiles) = store_with_runs();
+
+        let all = store.list_history(&HistoryFilter::default()).unwrap();
+        assert_eq!(all.len(), 6);
+        assert!(all.windows(2).all(|w| w[0].started_at >= w[1].started_at));
+
+        let page = store
+            .list_history(&HistoryFilter {
+                profile_id: Some(profiles[1].id.clone()),
+                limit: Some(2),
+                offset: Some(2),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].id, all[2].id);
+
+        let failed = HistoryFilter {
+            status: Some(CloneStatus::Error),
+            ..Default::default()
+        };
+        assert_eq!(count(&store, failed), 2);
+
+        let full_clones = HistoryFilter {
+            clone_type: Some(CloneType::Both),
+            ..Default::default()
+        };
+        assert_eq!(count(&store, full_clones), 1);
+
+        let in_2025 = HistoryFilter {
+            started_after: Some("2025-01-01T00:00:00Z".parse().unwrap()),
+            started_before: Some("2026-01-01T00:00:00Z".parse().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(count(&store, in_2025), 1);
+
+        let search = |text: &str| HistoryFilter {
+            search: Some(text.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(count(&store, search("RUN 3")), 1);
+        assert_eq!(count(&store, search("starting clone")), 1);
+        // Wildcards in the search text are matched literally
+        assert_eq!(count(&store, search("run_3")), 0);
+    }
+
+    #[test]
+    fn applies_history_retention() {
+        let (store, _) = store_with_runs();
+        assert_eq!(store.history_retention().unwrap().max_entries, Some(50));
+
+        store
+            .set_history_retention(&HistoryRetention {
+                max_entries: None,
+                max_age_days: Some(30),
+            })
+            .unwrap();
+        store.apply_history_retention().unwrap();
+        assert_eq!(count(&store, HistoryFilter::default()), 5);
+
+        store
+            .set_history_retention(&HistoryRetention {
+                max_entries: Some(3),
+                max_age_days: None,
+            })
+            .unwrap();
+        store.apply_history_retention().unwrap();
+        let kept = store.list_history(&HistoryFilter::default()).unwrap();
+        assert_eq!(kept.len(), 3);
+        assert_eq!(kept[0].logs.len(), 0);
+        assert_eq!(
+            store.get_history_entry(&kept[0].id).unwrap().unwrap().logs,
+            vec!["run 4".to_string()]
+        );
+    }
+
+    #[test]
+    fn computes_stats_per_pair() {
+        let (store, profiles) = store_with_runs();
+
+        let stats = store.history_stats().unwrap();
+        assert_eq!(stats.len(), 2);
+
+        assert_eq!(stats[0].source_id, profiles[1].id);
+        assert_eq!(stats[0].runs, 5);
+        assert_eq!(stats[0].successes, 3);
+        assert_eq!(stats[0].failures, 2);
+
+        assert_eq!(stats[1].source_name, "Production");
+        assert_eq!(stats[1].runs, 1);
+        assert_eq!(stats[1].average_duration, Some(150.0));
+    }
+
+    #[test]
+    fn keeps_every_option_of_saved_operations() {
+        // A row saved before operations kept their full options
+        let conn = Connection::open_in_memory().unwrap();
+        let previous = SCHEMA_MIGRATIONS.len() - 1;
+        for migration in &SCHEMA_MIGRATIONS[..previous] {
+            conn.execute_batch(migration).unwrap();
+        }
+        conn.pragma_update(None, "user_version", previous).unwrap();
+        conn.execute(
+            "INSERT INTO saved_operations (id, name, source_id, destination_id, clean_destination,
+                 create_backup, clone_type, created_at, masking)
+             VALUES ('old', 'Nightly', 'a', 'b', 1, 0, 'data', '2025-01-01T00:00:00Z',
+                 '{\"rules\": [], \"seed\": \"s\"}')",
+            [],
+        )
+        .unwrap();
+        let store = Store::from_connection(conn, None).unwrap();
+        store.migrate(None).unwrap();
+
+        let old = store.list_saved_operations().unwrap().remove(0);
+        let options = old.options.clone().unwrap();
+        assert!(options.clean_destination && !options.create_backup);
+        assert!(matches!(options.clone_type, CloneType::Data));
+        assert_eq!(options.masking.unwrap().seed, "s");
+
+        let operation = SavedOperation::new(
+            "Filtered".to_string(),
+            CloneOptions {
+                include_schemas: vec!["billing".to_string()],
+                row_filters: vec![TableRowFilter {
+                    table: "orders".to_string(),
+                    where_clause: "total > 100".to_string(),
+                }],
+                keep_ownership: true,
+                ..CloneOptions::new("a".to_string(), "b".to_string(), CloneType::Both)
+            },
+        );
+        store.insert_saved_operation(&operation).unwrap();
+
+        let saved = store
+            .list_saved_operations()
+            .unwrap()
+            .remove(1)
+            .clone_options();
+        assert_eq!(saved.include_schemas, ["billing"]);
+        assert_eq!(saved.row_filters[0].where_clause, "total > 100");
+        assert!(saved.keep_ownership);
+    }
+
+    #[test]
+    fn claims_a_scheduled_run_once() {
+        let store = store_from_fixture(include_str!("../tests/fixtures/app-data-v0.json"));
+        let operation = store.list_saved_operations().unwrap().remove(0);
+        let seen = operation.last_scheduled_at;
+        let now = Utc::now();
+
+        assert!(store.claim_scheduled_run(&operation.id, seen, now).unwrap());
+        // A second scheduler that read the same state loses the race
+        assert!(!store.claim_scheduled_run(&operation.id, seen, now).unwrap());
+        assert!(store
+            .claim_scheduled_run(&operation.id, Some(now), Utc::now())
+            .unwrap());
+    }
+
+    #[test]
+    fn deleting_a_tag_untags_its_profiles() {
+        let store = store_from_fixture(include_str!("../tests/fixtures/app-data-v0.json"));
+        let tag = store.list_tags().unwrap().remove(0);
+
+        store.delete_tag(&tag.id).unwrap();
+
+        let profiles = store.list_profiles().unwrap();
+        assert!(profiles.iter().all(|p| p.tag_id.is_none()));
+        assert!(matches!(
+            store.delete_tag(&tag.id),
+            Err(AppError::TagNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn preserves_a_corrupt_database() {
+        let path = temp_database();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "not a database, just some text").unwrap();
+
+        let copy = match Store::open_at(&path) {
+            Err(AppError::StorageCorrupt { path, .. }) => PathBuf::from(path),
+            other => panic!("expected a corrupt database error, got {:?}", other.err()),
+        };
+        assert_ne!(copy, path);
+        assert_eq!(fs::read(&copy).unwrap(), fs::read(&path).unwrap());
+
+        // Later failures point at the same copy instead of making new ones
+        match Store::open_at(&path) {
+            Err(AppError::StorageCorrupt { path, .. }) => assert_eq!(PathBuf::from(path), copy),
+            other => panic!("expected a corrupt database error, got {:?}", other.err()),
+        }
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn records_a_failed_startup_backup() {
+        let path = temp_database();
+        Store::open_at(&path).unwrap();
+        // A file where the backup directory should be makes the snapshot fail
+        fs::write(backup_dir(&path), "").unwrap();
+
+        let store = Store::open_at(&path).unwrap();
+        let error = store.last_backup_error().unwrap().unwrap();
+        assert!(
+            error.starts_with("Failed to create backup directory"),
+            "{}",
+            error
+        );
+
+        fs::remove_file(backup_dir(&path)).unwrap();
+        store.backup_to(&backup_dir(&path)).unwrap();
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn keeps_only_the_newest_backups() {
+        let path = temp_database();
+        let store = Store::open_at(&path).unwrap();
+        let dir = backup_dir(&path);
+
+        let mut names = Vec::new();
+        for _ in 0..MAX_BACKUPS + 2 {
+            store.backup_to(&dir).unwrap();
+            names.push(list_backup_files(&dir)[0].clone());
+            // Backup names have millisecond precision
+            std::thread::sleep(Duration::from_millis(2));
+        }
+
+        let kept = list_backup_files(&dir);
+        assert_eq!(kept.len(), MAX_BACKUPS);
+        names.reverse();
+        assert_eq!(kept, names[..MAX_BACKUPS]);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn restores_a_backup() {
+        let path = temp_database();
+        let store = Store::open_at(&path).unwrap();
+        let kept = Tag::new("Production".to_string(), "#ff0000".to_string());
+        store.insert_tag(&kept).unwrap();
+        store.backup_to(&backup_dir(&path)).unwrap();
+        let name = list_backup_files(&backup_dir(&path))[0]
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+
+        let added = Tag::new("Staging".to_string(), "#00ff00".to_string());
+        store.insert_tag(&added).unwrap();
+        drop(store);
+        std::thread::sleep(Duration::from_millis(2));
+
+        restore_backup_at(&path, &name).unwrap();
+
+        let tags = Store::open_at(&path).unwrap().list_tags().unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].id, kept.id);
+        // The database being replaced was backed up first
+        assert!(list_backup_files(&backup_dir(&path)).iter().any(|backup| {
+            Connection::open(backup)
+                .and_then(|conn| conn.query_row("SELECT count(*) FROM tags", [], |r| r.get(0)))
+                .is_ok_and(|count: i64| count == 2)
+        }));
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn restores_under_an_open_connection() {
+        let path = temp_database();
+        let store = Store::open_at(&path).unwrap();
+        let kept = Tag::new("Production".to_string(), "#ff0000".to_string());
+        store.insert_tag(&kept).unwrap();
+        store.backup_to(&backup_dir(&path)).unwrap();
+        let name = list_backup_files(&backup_dir(&path))[0]
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        store
+            .insert_tag(&Tag::new("Staging".to_string(), "#00ff00".to_string()))
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+
+        // Stands in for the CLI or the scheduler holding the database open
+        let open = Store::open_at(&path).unwrap();
+        restore_backup_at(&path, &name).unwrap();
+
+        assert_eq!(open.list_tags().unwrap().len(), 1);
+        let added = Tag::new("QA".to_string(), "#0000ff".to_string());
+        open.insert_tag(&added).unwrap();
+        // The open connection wrote to the restored database, not to a replaced file
+        let tags = Store::open_at(&path).unwrap().list_tags().unwrap();
+        let mut ids: Vec<&str> = tags.iter().map(|t| t.id.as_str()).collect();
+        ids.sort();
+        let mut expected = vec![kept.id.as_str(), added.id.as_str()];
+        expected.sort();
+        assert_eq!(ids, expected);
+        assert_eq!(store.list_tags().unwrap().len(), 2);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn replaces_files_in_one_step() {
+        let path = temp_database();
+        let dir = path.parent().unwrap();
+        fs::create_dir_all(dir).unwrap();
+        let source = dir.join("new.sqlite3");
+        fs::write(&path, "old").unwrap();
+        fs::write(&source, "new").unwrap();
+
+        replace_file(&source, &path).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&source).unwrap(), "new");
+        // Only the source and the replaced file are left; no temp file
+        assert_eq!(fs::read_dir(dir).unwrap().count(), 2);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn waits_for_another_writer() {
+        let path = temp_database();
+        let holder = Store::open_at(&path).unwrap();
+        let writer = Store::open_at(&path).unwrap();
+
+        let (locked, wait_for_lock) = std::sync::mpsc::channel();
+        let handle = std::thread::spawn(move || {
+            holder.conn.execute_batch("BEGIN IMMEDIATE").unwrap();
+            locked.send(()).unwrap();
+            std::thread::sleep(Duration::from_millis(300));
+            holder.conn.execute_batch("COMMIT").unwrap();
+        });
+        wait_for_lock.recv().unwrap();
+
+        // Blocks until the other connection commits instead of failing as busy
+        let tag = Tag::new("Production".to_string(), "#ff0000".to_string());
+        writer.insert_tag(&tag).unwrap();
+        handle.join().unwrap();
+
+        assert_eq!(writer.list_tags().unwrap().len(), 1);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
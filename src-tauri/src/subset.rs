@@ -0,0 +1,430 @@
+use std::collections::{HashMap, VecDeque};
+
+use tokio_postgres::Client;
+
+use crate::connection::{fetch_foreign_keys, fetch_tables};
+use crate::error::{AppError, AppResult};
+use crate::masking::Masking;
+use crate::table_copy::{copy_table, quote_ident, resolve_table, TableName};
+use crate::types::{SubsetOptions, TableRowCount};
+
+/// A foreign key between two tables of the plan, by index
+#[derive(Debug)]
+struct Edge {
+    child: usize,
+    parent: usize,
+    /// `t.col = p.col` conditions joining the child (`t`) to the parent (`p`)
+    join: String,
+}
+
+/// A root selection: table index plus the `TABLESAMPLE`/`WHERE` suffix of its query
+#[derive(Debug)]
+struct Root {
+    table: usize,
+    selection: String,
+}
+
+/// Which tables a subset touches and how to walk between them
+#[derive(Debug)]
+pub struct SubsetPlan {
+    /// Tables connected to a root through foreign keys. Their data comes only from
+    /// the subset; every other table is cloned in full.
+    pub tables: Vec<TableName>,
+    roots: Vec<Root>,
+    edges: Vec<Edge>,
+    include_referencing: bool,
+    /// Tables whose selected rows are not copied, by index
+    data_excluded: Vec<usize>,
+    /// Tables the walk over the foreign keys never selects rows from, by index
+    unreached: Vec<usize>,
+}
+
+/// Temp table holding the `ctid`s selected from table `index`
+fn selection_table(index: usize) -> String {
+    format!("clone_subset_{}", index)
+}
+
+/// Resolves the root tables and collects every table linked to them by foreign keys.
+/// The rows are selected in temp tables, so a read-only standby cannot be the source.
+pub async fn plan(client: &Client, options: &SubsetOptions) -> AppResult<SubsetPlan> {
+    let in_recovery: bool = client
+        .query_one("SELECT pg_is_in_recovery()", &[])
+        .await
+        .map_err(|e| AppError::query("Failed to check the source server", &e))?
+        .get(0);
+    if in_recovery {
+        return Err(AppError::InvalidInput(
+            "A subset cannot be taken from a read-only standby; use the primary server as the source"
+                .to_string(),
+        ));
+    }
+
+    let names: Vec<TableName> = fetch_tables(client)
+        .await?
+        .into_iter()
+        .map(|t| TableName {
+            schema: t.schema,
+            name: t.name,
+        })
+        .collect();
+    let index: HashMap<String, usize> = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.qualified(), i))
+        .collect();
+
+    let mut roots = Vec::new();
+    for root in &options.roots {
+        let name = resolve_table(client, &root.table).await?.qualified();
+        let table = *index.get(&name).ok_or_else(|| {
+            AppError::InvalidInput(format!("Subset root is not a table: {}", root.table))
+        })?;
+
+        let mut selection = String::new();
+        if let Some(percent) = root.percent {
+            // A fixed seed keeps the sample stable while the plan is walked
+            selection.push_str(&format!(
+                " TABLESAMPLE BERNOULLI ({}) REPEATABLE (0)",
+                percent
+            ));
+        }
+        if let Some(clause) = root
+            .where_clause
+            .as_deref()
+            .filter(|c| !c.trim().is_empty())
+        {
+            selection.push_str(&format!(" WHERE {}", clause));
+        }
+        roots.push(Root { table, selection });
+    }
+
+    let mut edges = Vec::new();
+    for fk in fetch_foreign_keys(client).await? {
+        let child = index.get(
+            &TableName {
+                schema: fk.schema,
+                name: fk.table,
+            }
+            .qualified(),
+        );
+        let parent = index.get(
+            &TableName {
+                schema: fk.referenced_schema,
+                name: fk.referenced_table,
+            }
+            .qualified(),
+        );
+        if let (Some(&child), Some(&parent)) = (child, parent) {
+            let join = fk
+                .columns
+                .iter()
+                .zip(&fk.referenced_columns)
+                .map(|(c, p)| format!("t.{} = p.{}", quote_ident(c), quote_ident(p)))
+                .collect::<Vec<_>>()
+                .join(" AND ");
+            edges.push(Edge {
+                child,
+                parent,
+                join,
+            });
+        }
+    }
+
+    let connected = connected_tables(
+        names.len(),
+        roots.iter().map(|r| r.table),
+        edges.iter().map(|e| (e.child, e.parent)),
+    );
+
+    let roots: Vec<Root> = roots
+        .into_iter()
+        .map(|r| Root {
+            table: position(&connected, r.table),
+            selection: r.selection,
+        })
+        .collect();
+    let edges: Vec<Edge> = edges
+        .into_iter()
+        .filter(|e| connected.contains(&e.child))
+        .map(|e| Edge {
+            child: position(&connected, e.child),
+            parent: position(&connected, e.parent),
+            join: e.join,
+        })
+        .collect();
+    let reached = reached_tables(
+        connected.len(),
+        roots.iter().map(|r| r.table),
+        edges.iter().map(|e| (e.child, e.parent)),
+        options.include_referencing,
+    );
+
+    Ok(SubsetPlan {
+        tables: connected.iter().map(|&i| names[i].clone()).collect(),
+        unreached: (0..connected.len())
+            .filter(|i| !reached.contains(i))
+            .collect(),
+        roots,
+        edges,
+        include_referencing: options.include_referencing,
+        data_excluded: Vec::new(),
+    })
+}
+
+impl SubsetPlan {
+    /// Keeps `excluded` tables in the walk, so their foreign keys still pull in rows
+    /// elsewhere, but copies none of their rows
+    pub fn exclude_data(&mut self, excluded: impl Fn(&TableName) -> bool) {
+        self.data_excluded = (0..self.tables.len())
+            .filter(|&i| excluded(&self.tables[i]))
+            .collect();
+    }
+
+    /// Copied tables that end up empty: they are linked to the subset only through
+    /// rows they reference, which never pulls rows into them
+    pub fn unreached_tables(&self) -> impl Iterator<Item = &TableName> {
+        self.unreached
+            .iter()
+            .filter(|i| !self.data_excluded.contains(i))
+            .map(|&i| &self.tables[i])
+    }
+}
+
+fn position(tables: &[usize], table: usize) -> usize {
+    tables.iter().position(|&t| t == table).unwrap()
+}
+
+/// Tables reachable from the roots following foreign keys in either direction, sorted
+fn connected_tables(
+    count: usize,
+    roots: impl Iterator<Item = usize>,
+    edges: impl Iterator<Item = (usize, usize)>,
+) -> Vec<usize> {
+    let mut neighbours = vec![Vec::new(); count];
+    for (child, parent) in edges {
+        neighbours[child].push(parent);
+        neighbours[parent].push(child);
+    }
+
+    let seen = walk(count, roots.collect(), &neighbours);
+    (0..count).filter(|&t| seen[t]).collect()
+}
+
+/// Tables [`copy`] selects rows from, sorted: the roots, with referencing rows the
+/// tables below them, and then every table above those. A table that only references
+/// rows pulled in from above, like a sibling of a root sharing its parent, is not
+/// reached, since following it down again would pull in most of the database.
+fn reached_tables(
+    count: usize,
+    roots: impl Iterator<Item = usize>,
+    edges: impl Iterator<Item = (usize, usize)>,
+    include_referencing: bool,
+) -> Vec<usize> {
+    let mut children = vec![Vec::new(); count];
+    let mut parents = vec![Vec::new(); count];
+    for (child, parent) in edges {
+        children[parent].push(child);
+        parents[child].push(parent);
+    }
+
+    let down = if include_referencing {
+        children
+    } else {
+        vec![Vec::new(); count]
+    };
+    let below = walk(count, roots.collect(), &down);
+    let reached = walk(count, (0..count).filter(|&t| below[t]).collect(), &parents);
+    (0..count).filter(|&t| reached[t]).collect()
+}
+
+/// Marks the tables reached from `start` following `next`
+fn walk(count: usize, start: Vec<usize>, next: &[Vec<usize>]) -> Vec<bool> {
+    let mut seen = vec![false; count];
+    let mut queue = VecDeque::from(start);
+    while let Some(table) = queue.pop_front() {
+        if !std::mem::replace(&mut seen[table], true) {
+            queue.extend(&next[table]);
+        }
+    }
+    seen
+}
+
+/// Adds rows reachable over `edges` until nothing changes. Returns rows added.
+async fn close_over(
+    client: &Client,
+    plan: &SubsetPlan,
+    referencing: bool,
+) -> Result<u64, tokio_postgres::Error> {
+    let mut total = 0;
+    loop {
+        let mut added = 0;
+        for edge in &plan.edges {
+            // Child rows of selected parents, or parent rows of selected children
+            let (target, sql) = if referencing {
+                (
+                    edge.child,
+                    format!(
+                        "SELECT t.ctid FROM {child} t JOIN {parent} p ON {join} \
+                         JOIN {selected} s ON s.row_id = p.ctid",
+                        child = plan.tables[edge.child].qualified(),
+                        parent = plan.tables[edge.parent].qualified(),
+                        join = edge.join,
+                        selected = selection_table(edge.parent),
+                    ),
+                )
+            } else {
+                (
+                    edge.parent,
+                    format!(
+                        "SELECT p.ctid FROM {parent} p JOIN {child} t ON {join} \
+                         JOIN {selected} s ON s.row_id = t.ctid",
+                        child = plan.tables[edge.child].qualified(),
+                        parent = plan.tables[edge.parent].qualified(),
+                        join = edge.join,
+                        selected = selection_table(edge.child),
+                    ),
+                )
+            };
+            added += client
+                .execute(
+                    &format!(
+                        "INSERT INTO {} {} ON CONFLICT DO NOTHING",
+                        selection_table(target),
+                        sql
+                    ),
+                    &[],
+                )
+                .await?;
+        }
+        if added == 0 {
+            return Ok(total);
+        }
+        total += added;
+    }
+}
+
+/// Selects the subset on the source in one snapshot and copies it into the destination
+pub async fn copy(
+    plan: &SubsetPlan,
+    source: &Client,
+    destination: &Client,
+    masking: &Masking,
+    add_log: &(dyn Fn(&str) + Sync),
+) -> AppResult<Vec<TableRowCount>> {
+    // Temp tables live in this transaction only and vanish with the rollback. READ
+    // WRITE lets them be created for roles whose transactions default to read only.
+    source
+        .batch_execute("BEGIN ISOLATION LEVEL REPEATABLE READ, READ WRITE")
+        .await
+        .map_err(|e| AppError::query("Failed to select subset", &e))?;
+    let copied = select_and_copy(plan, source, destination, masking, add_log).await;
+    // Also on failure, so the connection is not left in an aborted transaction
+    let _ = source.batch_execute("ROLLBACK").await;
+    copied
+}
+
+async fn select_and_copy(
+    plan: &SubsetPlan,
+    source: &Client,
+    destination: &Client,
+    masking: &Masking,
+    add_log: &(dyn Fn(&str) + Sync),
+) -> AppResult<Vec<TableRowCount>> {
+    let select_error = |e: tokio_postgres::Error| AppError::query("Failed to select subset", &e);
+
+    for index in 0..plan.tables.len() {
+        source
+            .batch_execute(&format!(
+                "CREATE TEMP TABLE {} (row_id tid PRIMARY KEY)",
+                selection_table(index)
+            ))
+            .await
+            .map_err(select_error)?;
+    }
+
+    for root in &plan.roots {
+        let selected = source
+            .execute(
+                &format!(
+                    "INSERT INTO {} SELECT ctid FROM {}{} ON CONFLICT DO NOTHING",
+                    selection_table(root.table),
+                    plan.tables[root.table].qualified(),
+                    root.selection
+                ),
+                &[],
+            )
+            .await
+            .map_err(select_error)?;
+        add_log(&format!(
+            "[INFO] Subset root {}: {} rows",
+            plan.tables[root.table].qualified(),
+            selected
+        ));
+    }
+
+    if plan.include_referencing {
+        let added = close_over(source, plan, true).await.map_err(select_error)?;
+        add_log(&format!("[INFO] Added {} referencing rows", added));
+    }
+    let added = close_over(source, plan, false)
+        .await
+        .map_err(select_error)?;
+    add_log(&format!("[INFO] Added {} referenced rows", added));
+
+    let mut copied = Vec::new();
+    for (index, table) in plan.tables.iter().enumerate() {
+        if plan.data_excluded.contains(&index) {
+            continue;
+        }
+        let condition = format!(
+            "t.ctid = ANY (ARRAY(SELECT row_id FROM {}))",
+            selection_table(index)
+        );
+        let rows = copy_table(source, destination, table, Some(&condition), masking).await?;
+        add_log(&format!(
+            "[SUCCESS] Copied {} rows into {}",
+            rows,
+            table.qualified()
+        ));
+        copied.push(TableRowCount {
+            table: table.qualified(),
+            rows,
+        });
+    }
+
+    Ok(copied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_foreign_keys_both_ways() {
+        // 0 customers <- 1 orders <- 2 order_items -> 3 products; 4 audit_log is unrelated
+        let edges = [(1, 0), (2, 1), (2, 3)];
+        assert_eq!(
+            connected_tables(5, [1].into_iter(), edges.into_iter()),
+            [0, 1, 2, 3]
+        );
+        assert_eq!(connected_tables(5, [4].into_iter(), edges.into_iter()), [4]);
+    }
+
+    #[test]
+    fn selects_rows_above_the_roots_and_below_them_when_asked() {
+        // 0 customers <- 1 orders <- 2 order_items -> 3 products; 4 addresses -> 0 customers
+        let edges = [(1, 0), (2, 1), (2, 3), (4, 0)];
+        let reached = |roots: &[usize], referencing| {
+            reached_tables(5, roots.iter().copied(), edges.into_iter(), referencing)
+        };
+
+        // Orders pull in their customers only
+        assert_eq!(reached(&[1], false), [0, 1]);
+        // Their items too, and the products those reference
+        assert_eq!(reached(&[1], true), [0, 1, 2, 3]);
+        // Addresses share the customer with orders but never get rows through it
+        assert!(!reached(&[1], true).contains(&4));
+        assert_eq!(reached(&[0], true), [0, 1, 2, 3, 4]);
+        assert_eq!(reached(&[1, 4], false), [0, 1, 4]);
+    }
+}
@@ -0,0 +1,129 @@
+use futures_util::{pin_mut, SinkExt, StreamExt};
+use tokio_postgres::Client;
+
+use crate::error::{AppError, AppResult};
+use crate::masking::Masking;
+
+/// Quotes an identifier the way PostgreSQL's `quote_ident` does, but always
+pub fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// A table as named in the catalog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableName {
+    pub schema: String,
+    pub name: String,
+}
+
+impl TableName {
+    /// Quoted `"schema"."name"`, usable in SQL and as a pg_dump pattern
+    pub fn qualified(&self) -> String {
+        format!("{}.{}", quote_ident(&self.schema), quote_ident(&self.name))
+    }
+}
+
+/// Looks a table up the way a query would, search_path included
+pub async fn resolve_table(client: &Client, name: &str) -> AppResult<TableName> {
+    let row = client
+        .query_one(
+            "SELECT n.nspname::text, c.relname::text
+             FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace
+             WHERE c.oid = $1::text::regclass",
+            &[&name.trim()],
+        )
+        .await
+        .map_err(|_| AppError::InvalidInput(format!("Table not found: {}", name)))?;
+
+    Ok(TableName {
+        schema: row.get(0),
+        name: row.get(1),
+    })
+}
+
+/// Columns COPY can write, in table order; generated columns are left out
+async fn table_columns(client: &Client, table: &TableName) -> AppResult<Vec<String>> {
+    let rows = client
+        .query(
+            "SELECT column_name::text FROM information_schema.columns
+             WHERE table_schema = $1 AND table_name = $2 AND is_generated = 'NEVER'
+             ORDER BY ordinal_position",
+            &[&table.schema, &table.name],
+        )
+        .await
+        .map_err(|e| AppError::query("Failed to get columns", &e))?;
+
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+/// Copies the rows of `table` matching `condition` (all rows when `None`), masking
+/// columns on the way
+pub async fn copy_table(
+    source: &Client,
+    destination: &Client,
+    table: &TableName,
+    condition: Option<&str>,
+    masking: &Masking,
+) -> AppResult<u64> {
+    let columns = table_columns(source, table).await?;
+    let qualified = table.qualified();
+    let query = masking.select_query(&qualified, &columns, condition)?;
+    let target = format!(
+        "{} ({})",
+        qualified,
+        columns
+            .iter()
+            .map(|c| quote_ident(c))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    copy_rows(source, destination, &query, &target).await
+}
+
+/// Streams the rows returned by `query` on the source into `table` on the destination
+async fn copy_rows(
+    source: &Client,
+    destination: &Client,
+    query: &str,
+    table: &str,
+) -> AppResult<u64> {
+    let context = format!("Failed to copy rows into {}", table);
+
+    let reader = source
+        .copy_out(&format!("COPY ({}) TO STDOUT", query))
+        .await
+        .map_err(|e| AppError::query(&context, &e))?;
+    let writer = destination
+        .copy_in(&format!("COPY {} FROM STDIN", table))
+        .await
+        .map_err(|e| AppError::query(&context, &e))?;
+    pin_mut!(reader, writer);
+
+    while let Some(chunk) = reader.next().await {
+        let chunk = chunk.map_err(|e| AppError::query(&context, &e))?;
+        writer
+            .send(chunk)
+            .await
+            .map_err(|e| AppError::query(&context, &e))?;
+    }
+
+    writer
+        .finish()
+        .await
+        .map_err(|e| AppError::query(&context, &e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_table_names() {
+        let table = TableName {
+            schema: "Sales".to_string(),
+            name: "order\"items".to_string(),
+        };
+        assert_eq!(table.qualified(), "\"Sales\".\"order\"\"items\"");
+    }
+}
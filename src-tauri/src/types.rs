@@ -1,7 +1,9 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::error::AppError;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tag {
     pub id: String,
@@ -38,6 +40,7 @@ pub struct ConnectionProfile {
 }
 
 impl ConnectionProfile {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         name: String,
         host: String,
@@ -118,14 +121,289 @@ pub struct CloneOptions {
     pub source_id: String,
     #[serde(rename = "destinationId")]
     pub destination_id: String,
+    /// More destinations restored from the same dump as `destination_id`
+    #[serde(rename = "destinationIds", default)]
+    pub destination_ids: Vec<String>,
+    /// How many destinations are restored at the same time
+    #[serde(rename = "restoreConcurrency", default = "default_restore_concurrency")]
+    pub restore_concurrency: usize,
     #[serde(rename = "cleanDestination")]
     pub clean_destination: bool,
     #[serde(rename = "createBackup")]
     pub create_backup: bool,
     #[serde(rename = "cloneType")]
     pub clone_type: CloneType,
+    /// Databases cloned one by one into namesakes on the destination server
+    /// (empty = just the profiles' own databases)
+    #[serde(default)]
+    pub databases: Vec<String>,
+    /// Table patterns (`*` and `?` wildcards) left out of the clone
     #[serde(rename = "excludeTables")]
     pub exclude_tables: Vec<String>,
+    /// Schema patterns to clone (empty = all schemas)
+    #[serde(rename = "includeSchemas", default)]
+    pub include_schemas: Vec<String>,
+    #[serde(rename = "excludeSchemas", default)]
+    pub exclude_schemas: Vec<String>,
+    /// Table patterns to clone; when set, the schema lists are ignored
+    #[serde(rename = "includeTables", default)]
+    pub include_tables: Vec<String>,
+    /// Table patterns cloned without their rows
+    #[serde(rename = "excludeTableData", default)]
+    pub exclude_table_data: Vec<String>,
+    /// Source schemas restored under another name
+    #[serde(rename = "schemaRenames", default)]
+    pub schema_renames: Vec<SchemaRename>,
+    /// Restores owners and grants instead of leaving everything to the restoring user
+    #[serde(rename = "keepOwnership", default)]
+    pub keep_ownership: bool,
+    /// Source roles whose objects and grants go to another role on the destination
+    #[serde(rename = "roleMap", default)]
+    pub role_map: Vec<RoleMapping>,
+    /// Tables whose data is copied only partially; the rest is cloned as usual
+    #[serde(rename = "rowFilters", default)]
+    pub row_filters: Vec<TableRowFilter>,
+    /// Copies a foreign-key-consistent subset instead of all rows
+    #[serde(default)]
+    pub subset: Option<SubsetOptions>,
+    #[serde(default)]
+    pub masking: Option<MaskingOptions>,
+}
+
+impl CloneOptions {
+    /// A plain clone of every table, without cleaning or backing up the destination
+    pub fn new(source_id: String, destination_id: String, clone_type: CloneType) -> Self {
+        Self {
+            source_id,
+            destination_id,
+            destination_ids: Vec::new(),
+            restore_concurrency: default_restore_concurrency(),
+            clean_destination: false,
+            create_backup: false,
+            clone_type,
+            databases: Vec::new(),
+            exclude_tables: Vec::new(),
+            include_schemas: Vec::new(),
+            exclude_schemas: Vec::new(),
+            include_tables: Vec::new(),
+            exclude_table_data: Vec::new(),
+            schema_renames: Vec::new(),
+            keep_ownership: false,
+            role_map: Vec::new(),
+            row_filters: Vec::new(),
+            subset: None,
+            masking: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaskingOptions {
+    pub rules: Vec<MaskingRule>,
+    /// Mixed into every hash, so the same value masks the same way in every table
+    pub seed: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaskingRule {
+    /// Table name, optionally schema-qualified
+    pub table: String,
+    pub column: String,
+    pub strategy: MaskStrategy,
+}
+
+/// How a masked column's values are replaced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum MaskStrategy {
+    FakeEmail,
+    Hash,
+    Null,
+    Constant {
+        value: String,
+    },
+    /// Moves the column's values between rows
+    Shuffle,
+    /// Replaces every digit, keeping length and punctuation
+    KeepFormat,
+    /// SQL evaluated per row; columns are available as `t.<column>`
+    Expression {
+        sql: String,
+    },
+}
+
+/// What a column likely holds, as guessed by `scan_pii`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PiiKind {
+    Email,
+    Phone,
+    CreditCard,
+    NationalId,
+    IpAddress,
+    Name,
+    Address,
+    BirthDate,
+    Secret,
+}
+
+impl PiiKind {
+    /// A reasonable default masking rule for the kind
+    pub fn suggested_strategy(self) -> MaskStrategy {
+        match self {
+            PiiKind::Email => MaskStrategy::FakeEmail,
+            PiiKind::Phone | PiiKind::CreditCard | PiiKind::NationalId => MaskStrategy::KeepFormat,
+            PiiKind::IpAddress | PiiKind::Name | PiiKind::BirthDate => MaskStrategy::Shuffle,
+            PiiKind::Address => MaskStrategy::Hash,
+            PiiKind::Secret => MaskStrategy::Null,
+        }
+    }
+}
+
+/// A column that likely holds personal data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PiiColumn {
+    pub schema: String,
+    pub table: String,
+    pub column: String,
+    #[serde(rename = "dataType")]
+    pub data_type: String,
+    pub kind: PiiKind,
+    /// 0 to 1; name and sampled values agreeing scores highest
+    pub confidence: f64,
+    #[serde(rename = "sampledValues")]
+    pub sampled_values: usize,
+    #[serde(rename = "matchedValues")]
+    pub matched_values: usize,
+    #[serde(rename = "suggestedStrategy")]
+    pub suggested_strategy: MaskStrategy,
+}
+
+/// A table whose values could not be read, so its columns were judged by name only
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PiiSkippedTable {
+    pub schema: String,
+    pub table: String,
+    pub reason: String,
+}
+
+/// Result of a PII scan: likely columns, most likely first, and the tables not sampled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PiiScan {
+    pub columns: Vec<PiiColumn>,
+    #[serde(rename = "skippedTables")]
+    pub skipped_tables: Vec<PiiSkippedTable>,
+}
+
+/// Restores source schema `from` as `to` on the destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaRename {
+    pub from: String,
+    pub to: String,
+}
+
+/// Source role `from` is known as `to` on the destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleMapping {
+    pub from: String,
+    pub to: String,
+}
+
+/// Copies only the rows of `table` matching `where_clause`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableRowFilter {
+    /// Table name, optionally schema-qualified
+    pub table: String,
+    #[serde(rename = "whereClause")]
+    pub where_clause: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubsetOptions {
+    pub roots: Vec<SubsetRoot>,
+    /// Also pull in rows referencing the selected rows, not only the rows they reference.
+    /// Only rows below the roots are followed, so tables that merely reference a parent
+    /// of a root stay empty unless they are roots themselves.
+    #[serde(rename = "includeReferencing", default)]
+    pub include_referencing: bool,
+}
+
+/// A table the subset starts from, narrowed by a WHERE clause, a sample percentage or both
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubsetRoot {
+    pub table: String,
+    #[serde(rename = "whereClause", default)]
+    pub where_clause: Option<String>,
+    #[serde(default)]
+    pub percent: Option<f64>,
+}
+
+/// Rows copied into one table by a partial data clone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableRowCount {
+    pub table: String,
+    pub rows: u64,
+}
+
+fn default_restore_concurrency() -> usize {
+    2
+}
+
+/// Outcome of one destination of a fan-out clone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DestinationCloneResult {
+    #[serde(rename = "destinationId")]
+    pub destination_id: String,
+    #[serde(rename = "destinationName")]
+    pub destination_name: String,
+    pub status: CloneStatus,
+    pub duration: i64,
+    #[serde(rename = "errorMessage")]
+    pub error_message: Option<String>,
+    #[serde(rename = "errorCode")]
+    pub error_code: Option<String>,
+    #[serde(rename = "verifiedTables")]
+    pub verified_tables: Option<i64>,
+    #[serde(rename = "serverVersion", default)]
+    pub server_version: Option<String>,
+}
+
+/// Outcome of one database of a server clone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseCloneResult {
+    pub database: String,
+    pub status: CloneStatus,
+    pub duration: i64,
+    #[serde(rename = "errorMessage")]
+    pub error_message: Option<String>,
+    #[serde(rename = "errorCode")]
+    pub error_code: Option<String>,
+    #[serde(rename = "verifiedTables")]
+    pub verified_tables: Option<i64>,
+}
+
+/// A PostgreSQL client program used by a clone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolInfo {
+    pub path: String,
+    pub version: Option<String>,
+}
+
+/// How a clone actually ran, besides the options it was given
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloneEnvironment {
+    /// pg_restore jobs; not set for data-only clones, which restore with psql
+    #[serde(rename = "parallelJobs")]
+    pub parallel_jobs: Option<usize>,
+    #[serde(rename = "pgDump")]
+    pub pg_dump: ToolInfo,
+    #[serde(rename = "pgRestore")]
+    pub pg_restore: ToolInfo,
+    pub psql: ToolInfo,
+    #[serde(rename = "sourceServerVersion")]
+    pub source_server_version: Option<String>,
+    #[serde(rename = "destinationServerVersion")]
+    pub destination_server_version: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -137,6 +415,11 @@ pub struct CloneProgress {
     pub is_complete: bool,
     #[serde(rename = "isError")]
     pub is_error: bool,
+    #[serde(rename = "errorCode")]
+    pub error_code: Option<String>,
+    /// Destination the stage is about; unset for stages shared by all destinations
+    #[serde(rename = "destinationId")]
+    pub destination_id: Option<String>,
 }
 
 impl CloneProgress {
@@ -147,6 +430,8 @@ impl CloneProgress {
             message: message.to_string(),
             is_complete: false,
             is_error: false,
+            error_code: None,
+            destination_id: None,
         }
     }
 
@@ -157,26 +442,39 @@ impl CloneProgress {
             message: message.to_string(),
             is_complete: true,
             is_error: false,
+            error_code: None,
+            destination_id: None,
         }
     }
 
-    pub fn error(message: &str) -> Self {
+    pub fn error(error: &AppError) -> Self {
         Self {
             stage: "error".to_string(),
             progress: 0,
-            message: message.to_string(),
+            message: error.to_string(),
             is_complete: true,
             is_error: true,
+            error_code: Some(error.code().to_string()),
+            destination_id: None,
         }
     }
+
+    pub fn for_destination(mut self, destination: &ConnectionProfile) -> Self {
+        self.destination_id = Some(destination.id.clone());
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum CloneStatus {
+    /// Saved at the start of a clone and updated as its stages complete
+    Running,
     Success,
     Error,
     Cancelled,
+    /// The app exited while the clone was still running
+    Interrupted,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -200,6 +498,25 @@ pub struct CloneHistoryEntry {
     pub duration: Option<i64>,
     #[serde(rename = "errorMessage")]
     pub error_message: Option<String>,
+    #[serde(rename = "errorCode", default)]
+    pub error_code: Option<String>,
+    /// Tables found in the destination by the verification step
+    #[serde(rename = "verifiedTables", default)]
+    pub verified_tables: Option<i64>,
+    /// Options the clone was started with, enough to re-run it
+    #[serde(default)]
+    pub options: Option<CloneOptions>,
+    #[serde(default)]
+    pub environment: Option<CloneEnvironment>,
+    /// Rows copied per table by row filters or subsetting
+    #[serde(rename = "copiedRows", default)]
+    pub copied_rows: Option<Vec<TableRowCount>>,
+    /// Per-database results of a server clone
+    #[serde(rename = "databaseResults", default)]
+    pub database_results: Option<Vec<DatabaseCloneResult>>,
+    /// Per-destination results of a fan-out clone
+    #[serde(rename = "destinationResults", default)]
+    pub destination_results: Option<Vec<DestinationCloneResult>>,
     pub logs: Vec<String>,
 }
 
@@ -207,7 +524,7 @@ impl CloneHistoryEntry {
     pub fn new(
         source: &ConnectionProfile,
         destination: &ConnectionProfile,
-        clone_type: CloneType,
+        options: &CloneOptions,
     ) -> Self {
         Self {
             id: Uuid::new_v4().to_string(),
@@ -215,22 +532,30 @@ impl CloneHistoryEntry {
             source_name: source.name.clone(),
             destination_id: destination.id.clone(),
             destination_name: destination.name.clone(),
-            clone_type,
-            status: CloneStatus::Success,
+            clone_type: options.clone_type.clone(),
+            status: CloneStatus::Running,
             started_at: Utc::now(),
             completed_at: None,
             duration: None,
             error_message: None,
+            error_code: None,
+            verified_tables: None,
+            options: Some(options.clone()),
+            environment: None,
+            copied_rows: None,
+            database_results: None,
+            destination_results: None,
             logs: Vec::new(),
         }
     }
 
-    pub fn complete(&mut self, status: CloneStatus, error_message: Option<String>) {
+    pub fn complete(&mut self, status: CloneStatus, error: Option<&AppError>) {
         let now = Utc::now();
         self.completed_at = Some(now);
         self.duration = Some((now - self.started_at).num_seconds());
         self.status = status;
-        self.error_message = error_message;
+        self.error_message = error.map(|e| e.to_string());
+        self.error_code = error.map(|e| e.code().to_string());
     }
 
     pub fn add_log(&mut self, log: String) {
@@ -252,42 +577,245 @@ pub struct SavedOperation {
     pub create_backup: bool,
     #[serde(rename = "cloneType")]
     pub clone_type: CloneType,
+    #[serde(default)]
+    pub masking: Option<MaskingOptions>,
     #[serde(rename = "createdAt")]
     pub created_at: DateTime<Utc>,
+    /// Runs the operation automatically while the app is open
+    #[serde(default)]
+    pub schedule: Option<OperationSchedule>,
+    /// Last time the scheduler ran or skipped the operation
+    #[serde(rename = "lastScheduledAt", default)]
+    pub last_scheduled_at: Option<DateTime<Utc>>,
+    /// Every option of the clone; operations saved by older versions kept only the
+    /// fields above
+    #[serde(default)]
+    pub options: Option<CloneOptions>,
 }
 
 impl SavedOperation {
-    pub fn new(
-        name: String,
-        source_id: String,
-        destination_id: String,
-        clean_destination: bool,
-        create_backup: bool,
-        clone_type: CloneType,
-    ) -> Self {
+    pub fn new(name: String, options: CloneOptions) -> Self {
         Self {
             id: Uuid::new_v4().to_string(),
             name,
-            source_id,
-            destination_id,
-            clean_destination,
-            create_backup,
-            clone_type,
+            source_id: options.source_id.clone(),
+            destination_id: options.destination_id.clone(),
+            clean_destination: options.clean_destination,
+            create_backup: options.create_backup,
+            clone_type: options.clone_type.clone(),
+            masking: options.masking.clone(),
             created_at: Utc::now(),
+            schedule: None,
+            last_scheduled_at: None,
+            options: Some(options),
+        }
+    }
+
+    /// Options of a clone that runs the operation
+    pub fn clone_options(&self) -> CloneOptions {
+        let options = self.options.clone().unwrap_or_else(|| CloneOptions {
+            clean_destination: self.clean_destination,
+            create_backup: self.create_backup,
+            masking: self.masking.clone(),
+            ..CloneOptions::new(String::new(), String::new(), self.clone_type.clone())
+        });
+        CloneOptions {
+            source_id: self.source_id.clone(),
+            destination_id: self.destination_id.clone(),
+            ..options
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+/// When a saved operation runs by itself, in the computer's local time zone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum OperationSchedule {
+    /// Five-field cron expression (`minute hour day month weekday`)
+    Cron {
+        expression: String,
+    },
+    /// Every day at `time` (`HH:MM`)
+    Daily {
+        time: String,
+    },
+    Weekly {
+        day: Weekday,
+        time: String,
+    },
+}
+
+/// What happens on start to scheduled runs that fell while the app was closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MissedRunPolicy {
+    /// Runs the operation once, however many runs were missed
+    #[default]
+    RunOnce,
+    Skip,
+}
+
+/// Last version of the JSON data file; older files are upgraded by `legacy_data::migrate`
+pub const APP_DATA_VERSION: u32 = 1;
+
+/// Contents of the JSON data file used before the SQLite store, read once to import it
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppData {
+    pub version: u32,
     pub profiles: Vec<ConnectionProfile>,
     pub history: Vec<CloneHistoryEntry>,
-    #[serde(default)]
     pub tags: Vec<Tag>,
-    #[serde(default)]
     pub saved_operations: Vec<SavedOperation>,
 }
 
+impl Default for AppData {
+    fn default() -> Self {
+        Self {
+            version: APP_DATA_VERSION,
+            profiles: Vec::new(),
+            history: Vec::new(),
+            tags: Vec::new(),
+            saved_operations: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Markdown,
+    Html,
+}
+
+/// Narrows a history query; entries are returned newest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryFilter {
+    /// Matches entries where the profile was either the source or the destination
+    #[serde(rename = "profileId", default)]
+    pub profile_id: Option<String>,
+    #[serde(default)]
+    pub status: Option<CloneStatus>,
+    #[serde(rename = "cloneType", default)]
+    pub clone_type: Option<CloneType>,
+    #[serde(rename = "startedAfter", default)]
+    pub started_after: Option<DateTime<Utc>>,
+    #[serde(rename = "startedBefore", default)]
+    pub started_before: Option<DateTime<Utc>>,
+    /// Case-insensitive text searched in log lines and error messages
+    #[serde(default)]
+    pub search: Option<String>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+    #[serde(default)]
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryPage {
+    pub entries: Vec<CloneHistoryEntry>,
+    /// Number of entries matching the filter across all pages
+    pub total: u64,
+}
+
+/// How much clone history is kept; entries outside either limit are deleted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryRetention {
+    #[serde(rename = "maxEntries", default)]
+    pub max_entries: Option<u32>,
+    #[serde(rename = "maxAgeDays", default)]
+    pub max_age_days: Option<u32>,
+}
+
+impl Default for HistoryRetention {
+    fn default() -> Self {
+        Self {
+            max_entries: Some(50),
+            max_age_days: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryPairStats {
+    #[serde(rename = "sourceId")]
+    pub source_id: String,
+    #[serde(rename = "sourceName")]
+    pub source_name: String,
+    #[serde(rename = "destinationId")]
+    pub destination_id: String,
+    #[serde(rename = "destinationName")]
+    pub destination_name: String,
+    pub runs: u64,
+    pub successes: u64,
+    pub failures: u64,
+    /// Average duration in seconds of successful runs
+    #[serde(rename = "averageDuration")]
+    pub average_duration: Option<f64>,
+    #[serde(rename = "lastRunAt")]
+    pub last_run_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Waiting for a free slot or for its destination to be released
+    Queued,
+    Running,
+    Success,
+    Error,
+}
+
+/// A clone in the job queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloneJob {
+    /// Same as the id of the history entry the job writes
+    pub id: String,
+    #[serde(rename = "sourceName")]
+    pub source_name: String,
+    #[serde(rename = "destinationName")]
+    pub destination_name: String,
+    #[serde(rename = "cloneType")]
+    pub clone_type: CloneType,
+    pub status: JobStatus,
+    /// Destination databases as `host:port/database`; jobs sharing one never run together
+    pub locks: Vec<String>,
+    #[serde(rename = "queuedAt")]
+    pub queued_at: DateTime<Utc>,
+    #[serde(rename = "startedAt")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(rename = "finishedAt")]
+    pub finished_at: Option<DateTime<Utc>>,
+    #[serde(rename = "errorMessage")]
+    pub error_message: Option<String>,
+}
+
+impl CloneJob {
+    pub fn new(entry: &CloneHistoryEntry, locks: Vec<String>) -> Self {
+        Self {
+            id: entry.id.clone(),
+            source_name: entry.source_name.clone(),
+            destination_name: entry.destination_name.clone(),
+            clone_type: entry.clone_type.clone(),
+            status: JobStatus::Queued,
+            locks,
+            queued_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+            error_message: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppDataBackup {
+    pub name: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    pub size: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SchemaProgress {
     pub stage: String,
@@ -383,3 +911,34 @@ pub struct SchemaExportOptions {
 fn default_true() -> bool {
     true
 }
+
+/// Copies cluster-level objects, which belong to no database, between servers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalsCloneOptions {
+    #[serde(rename = "sourceId")]
+    pub source_id: String,
+    #[serde(rename = "destinationId")]
+    pub destination_id: String,
+    /// Roles and their memberships
+    #[serde(rename = "includeRoles", default = "default_true")]
+    pub include_roles: bool,
+    #[serde(rename = "includeTablespaces", default = "default_true")]
+    pub include_tablespaces: bool,
+    /// Leaves password hashes behind, so copied roles cannot log in until given one
+    #[serde(rename = "stripPasswords", default = "default_true")]
+    pub strip_passwords: bool,
+    /// Role name patterns to copy (empty = all roles)
+    #[serde(rename = "roleFilter", default)]
+    pub role_filter: Vec<String>,
+}
+
+/// Outcome of a globals clone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalsCloneResult {
+    /// Statements the destination accepted
+    pub applied: usize,
+    /// Objects left alone because the destination already has them
+    pub skipped: Vec<String>,
+    /// Statements the destination rejected, with the reason
+    pub failed: Vec<String>,
+}
@@ -0,0 +1,665 @@
+// Runs the clone engine against a real server. The tests are ignored by default; run
+// them with `cargo test --test engine -- --ignored`. The server is read from the usual
+// PGHOST, PGPORT, PGUSER and PGPASSWORD variables and needs pg_dump, pg_restore and
+// psql on the PATH.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use app_clone_db_lib::engine::{
+    execute_schema_download, prepare_clone_between, run_clone, AppError, CloneOptions,
+    CloneProgress, CloneStatus, CloneType, ConnectionProfile, ProgressSink, SchemaExportOptions,
+};
+use tokio_postgres::{Client, NoTls};
+
+/// Collects everything the engine reports
+struct RecordingSink<P> {
+    progress: Mutex<Vec<P>>,
+    log: Mutex<Vec<String>>,
+}
+
+impl<P> RecordingSink<P> {
+    fn new() -> Self {
+        Self {
+            progress: Mutex::new(Vec::new()),
+            log: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<P: Send> ProgressSink<P> for RecordingSink<P> {
+    fn progress(&self, progress: P) {
+        self.progress.lock().unwrap().push(progress);
+    }
+
+    fn log(&self, line: &str) {
+        self.log.lock().unwrap().push(line.to_string());
+    }
+}
+
+struct Server {
+    host: String,
+    port: u16,
+    user: String,
+    password: String,
+    /// App database the clones save their history to
+    store: PathBuf,
+}
+
+impl Server {
+    /// The test server
+    fn from_env() -> Self {
+        let var = |name: &str, default: &str| std::env::var(name).unwrap_or(default.to_string());
+        Self {
+            host: var("PGHOST", "localhost"),
+            port: var("PGPORT", "5432")
+                .parse()
+                .expect("PGPORT must be a port"),
+            user: var("PGUSER", "postgres"),
+            password: var("PGPASSWORD", ""),
+            store: std::env::temp_dir()
+                .join(format!("db-clone-test-{}", uuid::Uuid::new_v4()))
+                .join("db-clone-data.sqlite3"),
+        }
+    }
+
+    fn profile(&self, database: &str) -> ConnectionProfile {
+        ConnectionProfile::new(
+            database.to_string(),
+            self.host.clone(),
+            self.port,
+            database.to_string(),
+            self.user.clone(),
+            self.password.clone(),
+            false,
+            None,
+        )
+    }
+
+    async fn connect(&self, database: &str) -> Client {
+        let (client, connection) = tokio_postgres::Config::new()
+            .host(&self.host)
+            .port(self.port)
+            .user(&self.user)
+            .password(&self.password)
+            .dbname(database)
+            .connect(NoTls)
+            .await
+            .expect("failed to connect to the test server");
+        tokio::spawn(connection);
+        client
+    }
+
+    /// Creates an empty database with a name unique to this run
+    async fn create_database(&self, prefix: &str) -> String {
+        let name = format!(
+            "{}_{}",
+            prefix,
+            uuid::Uuid::new_v4().simple().to_string().get(..8).unwrap()
+        );
+        self.connect("postgres")
+            .await
+            .batch_execute(&format!("CREATE DATABASE {}", name))
+            .await
+            .unwrap();
+        name
+    }
+
+    async fn drop_database(&self, name: &str) {
+        let _ = self
+            .connect("postgres")
+            .await
+            .batch_execute(&format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", name))
+            .await;
+    }
+
+    /// A database holding two related tables with a few rows
+    async fn create_source(&self) -> String {
+        let name = self.create_database("dbclone_src").await;
+        self.connect(&name)
+            .await
+            .batch_execute(
+                "CREATE TABLE customers (id serial PRIMARY KEY, name text NOT NULL);
+                 CREATE TABLE orders (
+                     id serial PRIMARY KEY,
+                     customer_id int NOT NULL REFERENCES customers (id),
+                     total numeric NOT NULL
+                 );
+                 INSERT INTO customers (name) VALUES ('Ana'), ('Luis'), ('Marta');
+                 INSERT INTO orders (customer_id, total) VALUES (1, 10.5), (1, 3), (3, 99);",
+            )
+            .await
+            .unwrap();
+        name
+    }
+}
+
+fn clone_options(
+    source: &ConnectionProfile,
+    destination: &ConnectionProfile,
+    clone_type: CloneType,
+) -> CloneOptions {
+    CloneOptions {
+        clean_destination: true,
+        ..CloneOptions::new(source.id.clone(), destination.id.clone(), clone_type)
+    }
+}
+
+async fn count(client: &Client, table: &str) -> i64 {
+    client
+        .query_one(&format!("SELECT count(*) FROM {}", table), &[])
+        .await
+        .unwrap()
+        .get(0)
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn clones_structure_and_data() {
+    let server = Server::from_env();
+    let source_db = server.create_source().await;
+    let destination_db = server.create_database("dbclone_dst").await;
+    let source = server.profile(&source_db);
+    let destination = server.profile(&destination_db);
+
+    let options = clone_options(&source, &destination, CloneType::Both);
+    let prepared = prepare_clone_between(source, vec![destination], options)
+        .unwrap()
+        .with_store(&server.store);
+    let sink = Arc::new(RecordingSink::<CloneProgress>::new());
+    let (entry, result) = run_clone(sink.clone(), prepared).await;
+
+    let client = server.connect(&destination_db).await;
+    let counts = (
+        count(&client, "customers").await,
+        count(&client, "orders").await,
+    );
+    drop(client);
+    server.drop_database(&source_db).await;
+    server.drop_database(&destination_db).await;
+
+    result.unwrap();
+    assert_eq!(counts, (3, 3));
+    assert!(matches!(entry.status, CloneStatus::Success));
+    assert_eq!(entry.verified_tables, Some(2));
+    assert!(sink.progress.lock().unwrap().last().unwrap().is_complete);
+    assert!(!sink.log.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn clones_structure_only() {
+    let server = Server::from_env();
+    let source_db = server.create_source().await;
+    let destination_db = server.create_database("dbclone_dst").await;
+    let source = server.profile(&source_db);
+    let destination = server.profile(&destination_db);
+
+    let options = clone_options(&source, &destination, CloneType::Structure);
+    let prepared = prepare_clone_between(source, vec![destination], options)
+        .unwrap()
+        .with_store(&server.store);
+    let (entry, result) = run_clone(Arc::new(RecordingSink::new()), prepared).await;
+
+    let client = server.connect(&destination_db).await;
+    let orders = count(&client, "orders").await;
+    drop(client);
+    server.drop_database(&source_db).await;
+    server.drop_database(&destination_db).await;
+
+    result.unwrap();
+    assert_eq!(orders, 0);
+    assert!(matches!(entry.status, CloneStatus::Success));
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn verifies_the_tables_of_renamed_schemas() {
+    let server = Server::from_env();
+    let source_db = server.create_source().await;
+    server
+        .connect(&source_db)
+        .await
+        .batch_execute("CREATE SCHEMA sales; CREATE TABLE sales.invoices (id int PRIMARY KEY);")
+        .await
+        .unwrap();
+    let destination_db = server.create_database("dbclone_dst").await;
+    let source = server.profile(&source_db);
+    let destination = server.profile(&destination_db);
+
+    let mut options = clone_options(&source, &destination, CloneType::Both);
+    options.include_schemas = vec!["sales".to_string()];
+    options.schema_renames = serde_json::from_value(serde_json::json!([
+        { "from": "sales", "to": "archive" },
+    ]))
+    .unwrap();
+    let prepared = prepare_clone_between(source, vec![destination], options)
+        .unwrap()
+        .with_store(&server.store);
+    let (entry, result) = run_clone(Arc::new(RecordingSink::new()), prepared).await;
+
+    let client = server.connect(&destination_db).await;
+    let invoices = count(&client, "archive.invoices").await;
+    drop(client);
+    server.drop_database(&source_db).await;
+    server.drop_database(&destination_db).await;
+
+    result.unwrap();
+    assert_eq!(invoices, 0);
+    assert_eq!(entry.verified_tables, Some(1));
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn ignores_row_filters_on_tables_left_out() {
+    let server = Server::from_env();
+    let source_db = server.create_source().await;
+    server
+        .connect(&source_db)
+        .await
+        .batch_execute(
+            "CREATE SCHEMA audit;
+             CREATE TABLE audit.events (id int PRIMARY KEY);
+             INSERT INTO audit.events VALUES (1), (2);",
+        )
+        .await
+        .unwrap();
+    let destination_db = server.create_database("dbclone_dst").await;
+    let source = server.profile(&source_db);
+    let destination = server.profile(&destination_db);
+
+    let mut options = clone_options(&source, &destination, CloneType::Both);
+    options.exclude_schemas = vec!["aud*".to_string()];
+    options.row_filters = serde_json::from_value(serde_json::json!([
+        { "table": "audit.events", "whereClause": "id = 1" },
+        { "table": "orders", "whereClause": "total > 5" },
+    ]))
+    .unwrap();
+    let prepared = prepare_clone_between(source, vec![destination], options)
+        .unwrap()
+        .with_store(&server.store);
+    let (entry, result) = run_clone(Arc::new(RecordingSink::new()), prepared).await;
+
+    let client = server.connect(&destination_db).await;
+    let orders = count(&client, "orders").await;
+    let audit: i64 = client
+        .query_one(
+            "SELECT count(*) FROM information_schema.schemata WHERE schema_name = 'audit'",
+            &[],
+        )
+        .await
+        .unwrap()
+        .get(0);
+    drop(client);
+    server.drop_database(&source_db).await;
+    server.drop_database(&destination_db).await;
+
+    result.unwrap();
+    assert_eq!((orders, audit), (2, 0));
+    assert_eq!(entry.verified_tables, Some(2));
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn copies_a_subset_with_the_rows_it_references() {
+    let server = Server::from_env();
+    let source_db = server.create_source().await;
+    server
+        .connect(&source_db)
+        .await
+        .batch_execute(
+            "CREATE TABLE addresses (
+                 id serial PRIMARY KEY,
+                 customer_id int NOT NULL REFERENCES customers (id)
+             );
+             INSERT INTO addresses (customer_id) VALUES (1), (3);",
+        )
+        .await
+        .unwrap();
+    let destination_db = server.create_database("dbclone_dst").await;
+    let source = server.profile(&source_db);
+    let destination = server.profile(&destination_db);
+
+    let mut options = clone_options(&source, &destination, CloneType::Both);
+    options.subset = serde_json::from_value(serde_json::json!({
+        "roots": [{ "table": "orders", "whereClause": "total > 50" }],
+    }))
+    .unwrap();
+    let prepared = prepare_clone_between(source, vec![destination], options)
+        .unwrap()
+        .with_store(&server.store);
+    let sink = Arc::new(RecordingSink::new());
+    let (_, result) = run_clone(sink.clone(), prepared).await;
+
+    let client = server.connect(&destination_db).await;
+    let counts = (
+        count(&client, "customers").await,
+        count(&client, "orders").await,
+        count(&client, "addresses").await,
+    );
+    drop(client);
+    server.drop_database(&source_db).await;
+    server.drop_database(&destination_db).await;
+
+    result.unwrap();
+    assert_eq!(counts, (1, 1, 0));
+    // Addresses only share the customer with orders, which the log points out
+    assert!(sink
+        .log
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|line| line
+            .starts_with("[WARNING] No rows will be copied into \"public\".\"addresses\"")));
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn masks_columns_that_are_not_text() {
+    let server = Server::from_env();
+    let source_db = server.create_source().await;
+    server
+        .connect(&source_db)
+        .await
+        .batch_execute(
+            "ALTER TABLE customers ADD COLUMN email varchar(20) NOT NULL DEFAULT 'ana@shop.es';
+             ALTER TABLE customers ADD COLUMN token uuid NOT NULL DEFAULT gen_random_uuid();",
+        )
+        .await
+        .unwrap();
+    let destination_db = server.create_database("dbclone_dst").await;
+    let source = server.profile(&source_db);
+    let destination = server.profile(&destination_db);
+
+    let mut options = clone_options(&source, &destination, CloneType::Both);
+    options.masking = serde_json::from_value(serde_json::json!({
+        "seed": "test",
+        "rules": [
+            { "table": "customers", "column": "email", "strategy": { "kind": "fakeEmail" } },
+            { "table": "customers", "column": "token", "strategy": { "kind": "hash" } },
+            { "table": "orders", "column": "total", "strategy": { "kind": "keepFormat" } },
+        ],
+    }))
+    .unwrap();
+    // A hash is no number, so that rule is refused before anything is restored
+    let mut refused_options = options.clone();
+    refused_options.masking.as_mut().unwrap().rules[2].strategy =
+        serde_json::from_value(serde_json::json!({ "kind": "hash" })).unwrap();
+    let prepared =
+        prepare_clone_between(source.clone(), vec![destination.clone()], refused_options)
+            .unwrap()
+            .with_store(&server.store);
+    let (_, refused) = run_clone(Arc::new(RecordingSink::new()), prepared).await;
+
+    let prepared = prepare_clone_between(source, vec![destination], options)
+        .unwrap()
+        .with_store(&server.store);
+    let (_, result) = run_clone(Arc::new(RecordingSink::new()), prepared).await;
+
+    let client = server.connect(&destination_db).await;
+    let emails: Vec<String> = client
+        .query("SELECT email FROM customers", &[])
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    let orders = count(&client, "orders").await;
+    drop(client);
+    server.drop_database(&source_db).await;
+    server.drop_database(&destination_db).await;
+
+    result.unwrap();
+    assert_eq!((emails.len(), orders), (3, 3));
+    assert!(emails
+        .iter()
+        .all(|e| e.starts_with("user_") && e.len() == 20));
+    assert!(matches!(refused, Err(AppError::InvalidInput(_))));
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn refuses_a_server_clone_onto_the_same_server() {
+    let server = Server::from_env();
+    if server.host != "localhost" {
+        return;
+    }
+    let source_db = server.create_source().await;
+    let source = server.profile("postgres");
+    // The same server under another address
+    let mut destination = server.profile("postgres");
+    destination.host = "127.0.0.1".to_string();
+
+    let mut options = clone_options(&source, &destination, CloneType::Both);
+    options.databases = vec![source_db.clone()];
+    let prepared = prepare_clone_between(source, vec![destination], options)
+        .unwrap()
+        .with_store(&server.store);
+    let (entry, result) = run_clone(Arc::new(RecordingSink::new()), prepared).await;
+    server.drop_database(&source_db).await;
+
+    assert!(matches!(result, Err(AppError::InvalidInput(_))));
+    assert!(matches!(entry.status, CloneStatus::Error));
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn clones_to_every_destination() {
+    let server = Server::from_env();
+    let source_db = server.create_source().await;
+    let first_db = server.create_database("dbclone_dst").await;
+    let second_db = server.create_database("dbclone_dst").await;
+    let source = server.profile(&source_db);
+    let destinations = vec![server.profile(&first_db), server.profile(&second_db)];
+
+    let options = clone_options(&source, &destinations[0], CloneType::Both);
+    let prepared = prepare_clone_between(source, destinations, options)
+        .unwrap()
+        .with_store(&server.store);
+    let (entry, result) = run_clone(Arc::new(RecordingSink::new()), prepared).await;
+
+    let mut counts = Vec::new();
+    for database in [&first_db, &second_db] {
+        counts.push(count(&server.connect(database).await, "orders").await);
+    }
+    server.drop_database(&source_db).await;
+    server.drop_database(&first_db).await;
+    server.drop_database(&second_db).await;
+
+    result.unwrap();
+    assert_eq!(counts, [3, 3]);
+    assert!(matches!(entry.status, CloneStatus::Success));
+    assert_eq!(entry.verified_tables, Some(4));
+    let results = entry.destination_results.unwrap();
+    assert_eq!(results.len(), 2);
+    for result in &results {
+        assert!(matches!(result.status, CloneStatus::Success));
+        assert_eq!(result.verified_tables, Some(2));
+        assert!(result.server_version.is_some());
+    }
+    let environment = entry.environment.unwrap();
+    assert!(environment.source_server_version.is_some());
+    assert!(environment.destination_server_version.is_some());
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn reports_an_unreachable_destination() {
+    let server = Server::from_env();
+    let source_db = server.create_source().await;
+    let destination_db = server.create_database("dbclone_dst").await;
+    let source = server.profile(&source_db);
+    let reachable = server.profile(&destination_db);
+    // Nothing listens on port 1
+    let mut unreachable = server.profile(&destination_db);
+    unreachable.port = 1;
+    let (reachable_id, unreachable_id) = (reachable.id.clone(), unreachable.id.clone());
+
+    let options = clone_options(&source, &reachable, CloneType::Both);
+    let prepared = prepare_clone_between(source, vec![reachable, unreachable], options)
+        .unwrap()
+        .with_store(&server.store);
+    let (entry, result) = run_clone(Arc::new(RecordingSink::new()), prepared).await;
+
+    let orders = count(&server.connect(&destination_db).await, "orders").await;
+    server.drop_database(&source_db).await;
+    server.drop_database(&destination_db).await;
+
+    assert!(matches!(
+        result,
+        Err(AppError::PartialFailure {
+            failed: 1,
+            total: 2,
+            ..
+        })
+    ));
+    assert!(matches!(entry.status, CloneStatus::Error));
+    assert_eq!(orders, 3);
+
+    let results = entry.destination_results.unwrap();
+    let reached = results
+        .iter()
+        .find(|r| r.destination_id == reachable_id)
+        .unwrap();
+    assert!(matches!(reached.status, CloneStatus::Success));
+    assert_eq!(reached.verified_tables, Some(2));
+    let missed = results
+        .iter()
+        .find(|r| r.destination_id == unreachable_id)
+        .unwrap();
+    assert!(matches!(missed.status, CloneStatus::Error));
+    assert_eq!(missed.error_code.as_deref(), Some("CONNECTION_FAILED"));
+    assert!(missed.server_version.is_none());
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn waits_for_another_clone_into_the_destination() {
+    let server = Server::from_env();
+    let source_db = server.create_source().await;
+    let destination_db = server.create_database("dbclone_dst").await;
+    let source = server.profile(&source_db);
+    let destination = server.profile(&destination_db);
+
+    // Stands in for a clone run by another process, which locks in the server's
+    // postgres database whatever database its profile names
+    let other = server.connect("postgres").await;
+    other
+        .execute(
+            "SELECT pg_advisory_lock(1684169580, hashtext($1))",
+            &[&destination_db],
+        )
+        .await
+        .unwrap();
+
+    let options = clone_options(&source, &destination, CloneType::Both);
+    let prepared = prepare_clone_between(source, vec![destination], options)
+        .unwrap()
+        .with_store(&server.store);
+    let sink = Arc::new(RecordingSink::<CloneProgress>::new());
+    let clone = tokio::spawn(run_clone(sink.clone(), prepared));
+
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    let waiting = !clone.is_finished();
+    drop(other);
+    let (entry, result) = clone.await.unwrap();
+
+    server.drop_database(&source_db).await;
+    server.drop_database(&destination_db).await;
+
+    assert!(waiting);
+    result.unwrap();
+    assert!(matches!(entry.status, CloneStatus::Success));
+    assert!(sink
+        .log
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|line| line.contains("Waiting for another clone")));
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn gives_up_on_a_destination_held_too_long() {
+    let server = Server::from_env();
+    let source_db = server.create_source().await;
+    let destination_db = server.create_database("dbclone_dst").await;
+    let source = server.profile(&source_db);
+    let destination = server.profile(&destination_db);
+
+    let other = server.connect("postgres").await;
+    other
+        .execute(
+            "SELECT pg_advisory_lock(1684169580, hashtext($1))",
+            &[&destination_db],
+        )
+        .await
+        .unwrap();
+
+    let options = clone_options(&source, &destination, CloneType::Both);
+    let prepared = prepare_clone_between(source, vec![destination], options)
+        .unwrap()
+        .with_store(&server.store)
+        .with_lock_timeout(std::time::Duration::from_secs(1));
+    let (entry, result) = run_clone(Arc::new(RecordingSink::new()), prepared).await;
+
+    let client = server.connect(&destination_db).await;
+    let tables: i64 = client
+        .query_one(
+            "SELECT count(*) FROM information_schema.tables WHERE table_schema = 'public'",
+            &[],
+        )
+        .await
+        .unwrap()
+        .get(0);
+    drop(client);
+    drop(other);
+    server.drop_database(&source_db).await;
+    server.drop_database(&destination_db).await;
+
+    assert!(matches!(result, Err(AppError::DestinationBusy { .. })));
+    assert!(matches!(entry.status, CloneStatus::Error));
+    assert_eq!(tables, 0);
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn reports_a_missing_source() {
+    let server = Server::from_env();
+    let destination_db = server.create_database("dbclone_dst").await;
+    let source = server.profile("dbclone_missing");
+    let destination = server.profile(&destination_db);
+
+    let options = clone_options(&source, &destination, CloneType::Both);
+    let prepared = prepare_clone_between(source, vec![destination], options)
+        .unwrap()
+        .with_store(&server.store);
+    let sink = Arc::new(RecordingSink::<CloneProgress>::new());
+    let (entry, result) = run_clone(sink.clone(), prepared).await;
+    server.drop_database(&destination_db).await;
+
+    assert!(result.is_err());
+    assert!(matches!(entry.status, CloneStatus::Error));
+    assert!(sink.progress.lock().unwrap().last().unwrap().is_error);
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn exports_the_schema() {
+    let server = Server::from_env();
+    let source_db = server.create_source().await;
+    let profile = server.profile(&source_db);
+    let options: SchemaExportOptions = serde_json::from_value(serde_json::json!({
+        "profileId": profile.id,
+    }))
+    .unwrap();
+
+    let sink = RecordingSink::new();
+    let schema = execute_schema_download(&sink, &profile, &options).await;
+    server.drop_database(&source_db).await;
+
+    let schema = schema.unwrap();
+    assert!(schema.contains("CREATE TABLE public.customers"));
+    assert!(schema.contains("CREATE TABLE public.orders"));
+    assert!(!schema.contains("Ana"));
+}
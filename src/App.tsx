@@ -9,6 +9,8 @@ import { Clone } from "@/pages/Clone";
 import { History } from "@/pages/History";
 import { Settings } from "@/pages/Settings";
 import { DownloadSchema } from "@/pages/DownloadSchema";
+import { CloneGlobals } from "@/pages/CloneGlobals";
+import { Jobs } from "@/pages/Jobs";
 import { useColorTheme } from "@/hooks/use-color-theme";
 
 function App() {
@@ -27,6 +29,8 @@ function App() {
             <Route path="/history" element={<History />} />
             <Route path="/settings" element={<Settings />} />
             <Route path="/download-schema" element={<DownloadSchema />} />
+            <Route path="/globals" element={<CloneGlobals />} />
+            <Route path="/jobs" element={<Jobs />} />
           </Routes>
         </Layout>
         <Toaster />
@@ -14,7 +14,7 @@ import {
 } from '@/components/ui/dialog'
 import { testConnectionById, deleteProfile } from '@/hooks/use-tauri'
 import type { ConnectionProfile, DatabaseInfo, Tag } from '@/types'
-import { formatBytes } from '@/lib/utils'
+import { formatBytes, getErrorMessage } from '@/lib/utils'
 
 interface ConnectionCardProps {
   profile: ConnectionProfile
@@ -38,7 +38,7 @@ export function ConnectionCard({ profile, tag, onDelete }: ConnectionCardProps)
       const result = await testConnectionById(profile.id)
       setTestResult(result)
     } catch (error) {
-      setTestError(error as string)
+      setTestError(getErrorMessage(error))
     } finally {
       setTesting(false)
     }
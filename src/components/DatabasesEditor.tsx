@@ -0,0 +1,78 @@
+import { useEffect, useState } from "react";
+import { Loader2 } from "lucide-react";
+import { Checkbox } from "@/components/ui/checkbox";
+import { Label } from "@/components/ui/label";
+import { listDatabases } from "@/hooks/use-tauri";
+import { getErrorMessage } from "@/lib/utils";
+
+interface DatabasesEditorProps {
+  sourceId: string;
+  databases: string[];
+  onChange: (databases: string[]) => void;
+}
+
+export function DatabasesEditor({
+  sourceId,
+  databases,
+  onChange,
+}: DatabasesEditorProps) {
+  const [available, setAvailable] = useState<string[]>([]);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!sourceId) return;
+    setLoading(true);
+    setError(null);
+    listDatabases(sourceId)
+      .then(setAvailable)
+      .catch((e) => setError(getErrorMessage(e)))
+      .finally(() => setLoading(false));
+  }, [sourceId]);
+
+  const toggle = (database: string, checked: boolean) => {
+    onChange(
+      checked
+        ? [...databases, database]
+        : databases.filter((d) => d !== database)
+    );
+  };
+
+  return (
+    <div className="space-y-3">
+      <div>
+        <Label>Databases</Label>
+        <p className="text-sm text-muted-foreground">
+          Each one is cloned into the database of the same name on the
+          destination server, which is created if missing
+        </p>
+      </div>
+
+      {loading && (
+        <div className="flex items-center gap-2 text-sm text-muted-foreground">
+          <Loader2 className="h-4 w-4 animate-spin" />
+          Loading databases...
+        </div>
+      )}
+      {error && <p className="text-sm text-red-600">{error}</p>}
+
+      <div className="grid grid-cols-2 md:grid-cols-3 gap-2">
+        {available.map((database) => (
+          <div key={database} className="flex items-center space-x-2">
+            <Checkbox
+              id={`database-${database}`}
+              checked={databases.includes(database)}
+              onCheckedChange={(c) => toggle(database, c as boolean)}
+            />
+            <Label
+              htmlFor={`database-${database}`}
+              className="cursor-pointer font-mono"
+            >
+              {database}
+            </Label>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,69 @@
+import { Checkbox } from "@/components/ui/checkbox";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import type { ConnectionProfile } from "@/types";
+
+interface DestinationsEditorProps {
+  profiles: ConnectionProfile[];
+  destinationIds: string[];
+  concurrency: number;
+  onChange: (destinationIds: string[]) => void;
+  onConcurrencyChange: (concurrency: number) => void;
+}
+
+export function DestinationsEditor({
+  profiles,
+  destinationIds,
+  concurrency,
+  onChange,
+  onConcurrencyChange,
+}: DestinationsEditorProps) {
+  const toggle = (id: string, checked: boolean) => {
+    onChange(
+      checked ? [...destinationIds, id] : destinationIds.filter((d) => d !== id)
+    );
+  };
+
+  return (
+    <div className="space-y-3">
+      <div>
+        <Label>Additional Destinations</Label>
+        <p className="text-sm text-muted-foreground">
+          The source is dumped once and restored into each of them
+        </p>
+      </div>
+
+      <div className="grid grid-cols-2 md:grid-cols-3 gap-2">
+        {profiles.map((profile) => (
+          <div key={profile.id} className="flex items-center space-x-2">
+            <Checkbox
+              id={`destination-${profile.id}`}
+              checked={destinationIds.includes(profile.id)}
+              onCheckedChange={(c) => toggle(profile.id, c as boolean)}
+            />
+            <Label
+              htmlFor={`destination-${profile.id}`}
+              className="cursor-pointer"
+            >
+              {profile.name}
+            </Label>
+          </div>
+        ))}
+      </div>
+
+      <div className="flex items-center gap-3">
+        <Label htmlFor="restoreConcurrency">Restore at a time</Label>
+        <Input
+          id="restoreConcurrency"
+          type="number"
+          min={1}
+          className="w-20"
+          value={concurrency}
+          onChange={(e) =>
+            onConcurrencyChange(Math.max(1, Number(e.target.value) || 1))
+          }
+        />
+      </div>
+    </div>
+  );
+}
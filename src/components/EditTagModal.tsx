@@ -12,6 +12,7 @@ import {
 } from "@/components/ui/dialog";
 import { ColorPicker } from "@/components/ColorPicker";
 import { Tag } from "@/types";
+import { getErrorMessage } from "@/lib/utils";
 
 interface EditTagModalProps {
   open: boolean;
@@ -56,7 +57,7 @@ export function EditTagModal({
       onTagUpdated();
       onOpenChange(false);
     } catch (err) {
-      setError(err as string);
+      setError(getErrorMessage(err));
     } finally {
       setLoading(false);
     }
@@ -73,7 +74,7 @@ export function EditTagModal({
       onTagDeleted(tag.id);
       onOpenChange(false);
     } catch (err) {
-      setError(err as string);
+      setError(getErrorMessage(err));
     } finally {
       setDeleting(false);
     }
@@ -9,6 +9,8 @@ import {
   ChevronLeft,
   ChevronRight,
   FileDown,
+  Users,
+  ListOrdered,
 } from "lucide-react";
 import { cn } from "@/lib/utils";
 import {
@@ -27,6 +29,8 @@ const navItems = [
   { href: "/", icon: Home, label: "Home" },
   { href: "/clone", icon: Database, label: "Clone" },
   { href: "/download-schema", icon: FileDown, label: "Schema" },
+  { href: "/globals", icon: Users, label: "Globals" },
+  { href: "/jobs", icon: ListOrdered, label: "Queue" },
   { href: "/history", icon: History, label: "History" },
   { href: "/settings", icon: Settings, label: "Settings" },
 ];
@@ -1,4 +1,11 @@
-import { Star, Trash2, ArrowRight, Calendar } from "lucide-react";
+import { useState } from "react";
+import {
+  Star,
+  Trash2,
+  ArrowRight,
+  Calendar,
+  CalendarClock,
+} from "lucide-react";
 import {
   Dialog,
   DialogContent,
@@ -7,8 +14,13 @@ import {
 } from "@/components/ui/dialog";
 import { ScrollArea } from "@/components/ui/scroll-area";
 import { Button } from "@/components/ui/button";
+import { ScheduleDialog } from "@/components/ScheduleDialog";
 import { cn } from "@/lib/utils";
-import type { SavedOperation, ConnectionProfile } from "@/types";
+import type {
+  SavedOperation,
+  ConnectionProfile,
+  OperationSchedule,
+} from "@/types";
 
 interface LoadOperationModalProps {
   open: boolean;
@@ -17,6 +29,7 @@ interface LoadOperationModalProps {
   profiles: ConnectionProfile[];
   onLoad: (operation: SavedOperation) => void;
   onDelete: (id: string) => void;
+  onScheduled: () => void;
 }
 
 export function LoadOperationModal({
@@ -26,7 +39,10 @@ export function LoadOperationModal({
   profiles,
   onLoad,
   onDelete,
+  onScheduled,
 }: LoadOperationModalProps) {
+  const [scheduling, setScheduling] = useState<SavedOperation | null>(null);
+
   const getProfileName = (id: string) => {
     const profile = profiles.find((p) => p.id === id);
     return profile?.name || "Unknown";
@@ -45,6 +61,17 @@ export function LoadOperationModal({
     }
   };
 
+  const getScheduleLabel = (schedule: OperationSchedule) => {
+    switch (schedule.kind) {
+      case "daily":
+        return `Daily at ${schedule.time}`;
+      case "weekly":
+        return `${schedule.day} at ${schedule.time}`;
+      case "cron":
+        return schedule.expression;
+    }
+  };
+
   const formatDate = (dateString: string) => {
     return new Date(dateString).toLocaleDateString(undefined, {
       year: "numeric",
@@ -130,6 +157,14 @@ export function LoadOperationModal({
                             <Calendar className="h-3 w-3" />
                             {formatDate(operation.createdAt)}
                           </span>
+                          {operation.schedule && (
+                            <span className="flex items-center gap-1 text-primary">
+                              <CalendarClock className="h-3 w-3" />
+                              <span className="font-mono">
+                                {getScheduleLabel(operation.schedule)}
+                              </span>
+                            </span>
+                          )}
                         </div>
 
                         {!isValid && (
@@ -140,6 +175,14 @@ export function LoadOperationModal({
                       </div>
 
                       <div className="flex items-center gap-2 flex-shrink-0">
+                        <Button
+                          variant="outline"
+                          size="sm"
+                          onClick={() => setScheduling(operation)}
+                          disabled={!isValid}
+                        >
+                          <CalendarClock className="h-4 w-4" />
+                        </Button>
                         <Button
                           variant="outline"
                           size="sm"
@@ -167,6 +210,12 @@ export function LoadOperationModal({
           </div>
         </ScrollArea>
       </DialogContent>
+
+      <ScheduleDialog
+        operation={scheduling}
+        onOpenChange={(open) => !open && setScheduling(null)}
+        onSaved={onScheduled}
+      />
     </Dialog>
   );
 }
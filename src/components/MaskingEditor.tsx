@@ -0,0 +1,229 @@
+import { useState } from "react";
+import { Loader2, Plus, ScanSearch, Trash2 } from "lucide-react";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { scanPii } from "@/hooks/use-tauri";
+import { useNotification } from "@/hooks/use-notification";
+import { getErrorMessage } from "@/lib/utils";
+import type { MaskingOptions, MaskingRule, MaskStrategy } from "@/types";
+
+const STRATEGIES: { kind: MaskStrategy["kind"]; label: string }[] = [
+  { kind: "fakeEmail", label: "Fake email" },
+  { kind: "hash", label: "Hash" },
+  { kind: "null", label: "Null" },
+  { kind: "constant", label: "Constant" },
+  { kind: "shuffle", label: "Shuffle within column" },
+  { kind: "keepFormat", label: "Keep format" },
+  { kind: "expression", label: "SQL expression" },
+];
+
+function strategyOf(kind: MaskStrategy["kind"]): MaskStrategy {
+  switch (kind) {
+    case "constant":
+      return { kind, value: "" };
+    case "expression":
+      return { kind, sql: "" };
+    default:
+      return { kind };
+  }
+}
+
+interface MaskingEditorProps {
+  sourceId: string;
+  masking: MaskingOptions;
+  onChange: (masking: MaskingOptions) => void;
+}
+
+export function MaskingEditor({
+  sourceId,
+  masking,
+  onChange,
+}: MaskingEditorProps) {
+  const [scanning, setScanning] = useState(false);
+  const { notifyError, notifyInfo } = useNotification();
+
+  // Adds a suggested rule for every detected column not masked yet
+  const handleDetect = async () => {
+    setScanning(true);
+    try {
+      const { columns, skippedTables } = await scanPii(sourceId);
+      const rules = masking.rules.filter(
+        (r) => r.table.trim() || r.column.trim()
+      );
+      const added = columns
+        .filter(
+          (c) =>
+            !rules.some(
+              (r) =>
+                r.column === c.column &&
+                (r.table === c.table || r.table === `${c.schema}.${c.table}`)
+            )
+        )
+        .map((c) => ({
+          table: `${c.schema}.${c.table}`,
+          column: c.column,
+          strategy: c.suggestedStrategy,
+        }));
+      onChange({ ...masking, rules: [...rules, ...added] });
+      const skipped = skippedTables.length
+        ? ` ${skippedTables.length} tables could not be read and were judged by column names only: ${skippedTables
+            .map((t) => `${t.schema}.${t.table}`)
+            .join(", ")}`
+        : "";
+      notifyInfo(
+        "PII Scan Complete",
+        (added.length
+          ? `Added ${added.length} suggested rules; review them before cloning.`
+          : "No new sensitive columns found.") + skipped
+      );
+    } catch (error) {
+      notifyError("PII Scan Failed", getErrorMessage(error));
+    } finally {
+      setScanning(false);
+    }
+  };
+
+  const updateRule = (index: number, patch: Partial<MaskingRule>) => {
+    onChange({
+      ...masking,
+      rules: masking.rules.map((r, i) => (i === index ? { ...r, ...patch } : r)),
+    });
+  };
+
+  return (
+    <div className="space-y-3">
+      <div className="flex items-center justify-between">
+        <div>
+          <Label>Masking Rules</Label>
+          <p className="text-sm text-muted-foreground">
+            Values are masked before they reach the destination; the same seed
+            always gives the same output
+          </p>
+        </div>
+        <div className="flex gap-2">
+          <Button
+            variant="outline"
+            size="sm"
+            disabled={!sourceId || scanning}
+            onClick={handleDetect}
+          >
+            {scanning ? (
+              <Loader2 className="h-4 w-4 mr-2 animate-spin" />
+            ) : (
+              <ScanSearch className="h-4 w-4 mr-2" />
+            )}
+            Detect PII
+          </Button>
+          <Button
+            variant="outline"
+            size="sm"
+            onClick={() =>
+              onChange({
+                ...masking,
+                rules: [
+                  ...masking.rules,
+                  { table: "", column: "", strategy: { kind: "fakeEmail" } },
+                ],
+              })
+            }
+          >
+            <Plus className="h-4 w-4 mr-2" />
+            Add Rule
+          </Button>
+        </div>
+      </div>
+
+      {masking.rules.map((rule, index) => (
+        <div key={index} className="flex items-center gap-2">
+          <Input
+            className="w-40 font-mono"
+            placeholder="public.users"
+            value={rule.table}
+            onChange={(e) => updateRule(index, { table: e.target.value })}
+          />
+          <Input
+            className="w-32 font-mono"
+            placeholder="email"
+            value={rule.column}
+            onChange={(e) => updateRule(index, { column: e.target.value })}
+          />
+          <Select
+            value={rule.strategy.kind}
+            onValueChange={(kind) =>
+              updateRule(index, {
+                strategy: strategyOf(kind as MaskStrategy["kind"]),
+              })
+            }
+          >
+            <SelectTrigger className="w-48">
+              <SelectValue />
+            </SelectTrigger>
+            <SelectContent>
+              {STRATEGIES.map((s) => (
+                <SelectItem key={s.kind} value={s.kind}>
+                  {s.label}
+                </SelectItem>
+              ))}
+            </SelectContent>
+          </Select>
+          {rule.strategy.kind === "constant" && (
+            <Input
+              className="flex-1"
+              placeholder="Value"
+              value={rule.strategy.value}
+              onChange={(e) =>
+                updateRule(index, {
+                  strategy: { kind: "constant", value: e.target.value },
+                })
+              }
+            />
+          )}
+          {rule.strategy.kind === "expression" && (
+            <Input
+              className="flex-1 font-mono"
+              placeholder="upper(t.name)"
+              value={rule.strategy.sql}
+              onChange={(e) =>
+                updateRule(index, {
+                  strategy: { kind: "expression", sql: e.target.value },
+                })
+              }
+            />
+          )}
+          <Button
+            variant="ghost"
+            size="icon"
+            onClick={() =>
+              onChange({
+                ...masking,
+                rules: masking.rules.filter((_, i) => i !== index),
+              })
+            }
+          >
+            <Trash2 className="h-4 w-4" />
+          </Button>
+        </div>
+      ))}
+
+      <div className="flex items-center gap-2">
+        <Label htmlFor="masking-seed" className="whitespace-nowrap">
+          Seed
+        </Label>
+        <Input
+          id="masking-seed"
+          className="font-mono"
+          value={masking.seed}
+          onChange={(e) => onChange({ ...masking, seed: e.target.value })}
+        />
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,65 @@
+import { ArrowRight, Plus, Trash2 } from "lucide-react";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import type { RoleMapping } from "@/types";
+
+interface RoleMapEditorProps {
+  mappings: RoleMapping[];
+  onChange: (mappings: RoleMapping[]) => void;
+}
+
+export function RoleMapEditor({
+  mappings,
+  onChange,
+}: RoleMapEditorProps) {
+  const update = (index: number, patch: Partial<RoleMapping>) => {
+    onChange(mappings.map((r, i) => (i === index ? { ...r, ...patch } : r)));
+  };
+
+  return (
+    <div className="space-y-3">
+      <div className="flex items-center justify-between">
+        <div>
+          <Label>Role Mapping</Label>
+          <p className="text-sm text-muted-foreground">
+            Give objects and grants of a source role to another destination role
+          </p>
+        </div>
+        <Button
+          variant="outline"
+          size="sm"
+          onClick={() => onChange([...mappings, { from: "", to: "" }])}
+        >
+          <Plus className="h-4 w-4 mr-2" />
+          Add Role
+        </Button>
+      </div>
+
+      {mappings.map((mapping, index) => (
+        <div key={index} className="flex items-center gap-2">
+          <Input
+            className="flex-1 font-mono"
+            placeholder="app_owner"
+            value={mapping.from}
+            onChange={(e) => update(index, { from: e.target.value })}
+          />
+          <ArrowRight className="h-4 w-4 text-muted-foreground" />
+          <Input
+            className="flex-1 font-mono"
+            placeholder="staging_owner"
+            value={mapping.to}
+            onChange={(e) => update(index, { to: e.target.value })}
+          />
+          <Button
+            variant="ghost"
+            size="icon"
+            onClick={() => onChange(mappings.filter((_, i) => i !== index))}
+          >
+            <Trash2 className="h-4 w-4" />
+          </Button>
+        </div>
+      ))}
+    </div>
+  );
+}
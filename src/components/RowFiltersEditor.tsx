@@ -0,0 +1,63 @@
+import { Plus, Trash2 } from "lucide-react";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import type { TableRowFilter } from "@/types";
+
+interface RowFiltersEditorProps {
+  filters: TableRowFilter[];
+  onChange: (filters: TableRowFilter[]) => void;
+}
+
+export function RowFiltersEditor({ filters, onChange }: RowFiltersEditorProps) {
+  const update = (index: number, patch: Partial<TableRowFilter>) => {
+    onChange(filters.map((f, i) => (i === index ? { ...f, ...patch } : f)));
+  };
+
+  return (
+    <div className="space-y-3">
+      <div className="flex items-center justify-between">
+        <div>
+          <Label>Row Filters</Label>
+          <p className="text-sm text-muted-foreground">
+            Copy only matching rows of large tables; everything else is cloned
+            in full
+          </p>
+        </div>
+        <Button
+          variant="outline"
+          size="sm"
+          onClick={() => onChange([...filters, { table: "", whereClause: "" }])}
+        >
+          <Plus className="h-4 w-4 mr-2" />
+          Add Filter
+        </Button>
+      </div>
+
+      {filters.map((filter, index) => (
+        <div key={index} className="flex items-center gap-2">
+          <Input
+            className="w-48 font-mono"
+            placeholder="public.orders"
+            value={filter.table}
+            onChange={(e) => update(index, { table: e.target.value })}
+          />
+          <span className="text-sm text-muted-foreground">WHERE</span>
+          <Input
+            className="flex-1 font-mono"
+            placeholder="created_at > now() - interval '90 days'"
+            value={filter.whereClause}
+            onChange={(e) => update(index, { whereClause: e.target.value })}
+          />
+          <Button
+            variant="ghost"
+            size="icon"
+            onClick={() => onChange(filters.filter((_, i) => i !== index))}
+          >
+            <Trash2 className="h-4 w-4" />
+          </Button>
+        </div>
+      ))}
+    </div>
+  );
+}
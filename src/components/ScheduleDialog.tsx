@@ -0,0 +1,198 @@
+import { useEffect, useState } from "react";
+import { CalendarClock, Loader2 } from "lucide-react";
+import { Button } from "@/components/ui/button";
+import {
+  Dialog,
+  DialogContent,
+  DialogDescription,
+  DialogFooter,
+  DialogHeader,
+  DialogTitle,
+} from "@/components/ui/dialog";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { previewSchedule, setOperationSchedule } from "@/hooks/use-tauri";
+import { formatDate, getErrorMessage } from "@/lib/utils";
+import type { OperationSchedule, SavedOperation, Weekday } from "@/types";
+
+type ScheduleKind = "none" | OperationSchedule["kind"];
+
+const weekdays: { value: Weekday; label: string }[] = [
+  { value: "Mon", label: "Monday" },
+  { value: "Tue", label: "Tuesday" },
+  { value: "Wed", label: "Wednesday" },
+  { value: "Thu", label: "Thursday" },
+  { value: "Fri", label: "Friday" },
+  { value: "Sat", label: "Saturday" },
+  { value: "Sun", label: "Sunday" },
+];
+
+interface ScheduleDialogProps {
+  operation: SavedOperation | null;
+  onOpenChange: (open: boolean) => void;
+  onSaved: (operation: SavedOperation) => void;
+}
+
+export function ScheduleDialog({
+  operation,
+  onOpenChange,
+  onSaved,
+}: ScheduleDialogProps) {
+  const [kind, setKind] = useState<ScheduleKind>("none");
+  const [time, setTime] = useState("02:00");
+  const [day, setDay] = useState<Weekday>("Mon");
+  const [expression, setExpression] = useState("0 2 * * 1-5");
+  const [preview, setPreview] = useState<string[]>([]);
+  const [error, setError] = useState<string | null>(null);
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    const schedule = operation?.schedule;
+    setKind(schedule?.kind ?? "none");
+    if (schedule && schedule.kind !== "cron") setTime(schedule.time);
+    if (schedule?.kind === "weekly") setDay(schedule.day);
+    if (schedule?.kind === "cron") setExpression(schedule.expression);
+  }, [operation]);
+
+  const schedule: OperationSchedule | null =
+    kind === "daily"
+      ? { kind, time }
+      : kind === "weekly"
+      ? { kind, day, time }
+      : kind === "cron"
+      ? { kind, expression }
+      : null;
+  const scheduleKey = JSON.stringify(schedule);
+
+  // Check the schedule as it is edited
+  useEffect(() => {
+    setError(null);
+    setPreview([]);
+    if (!schedule) return;
+    const timeout = setTimeout(() => {
+      previewSchedule(schedule)
+        .then(setPreview)
+        .catch((e) => setError(getErrorMessage(e)));
+    }, 300);
+    return () => clearTimeout(timeout);
+  }, [scheduleKey]);
+
+  const handleSave = async () => {
+    if (!operation) return;
+    setSaving(true);
+    try {
+      onSaved(await setOperationSchedule(operation.id, schedule));
+      onOpenChange(false);
+    } catch (e) {
+      setError(getErrorMessage(e));
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  return (
+    <Dialog open={!!operation} onOpenChange={onOpenChange}>
+      <DialogContent className="max-w-md">
+        <DialogHeader>
+          <DialogTitle className="flex items-center gap-2">
+            <CalendarClock className="h-5 w-5" />
+            Schedule
+          </DialogTitle>
+          <DialogDescription>
+            {operation?.name} runs by itself while the app is open
+          </DialogDescription>
+        </DialogHeader>
+
+        <div className="space-y-4">
+          <div className="space-y-2">
+            <Label>Repeat</Label>
+            <Select
+              value={kind}
+              onValueChange={(v) => setKind(v as ScheduleKind)}
+            >
+              <SelectTrigger>
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value="none">Not scheduled</SelectItem>
+                <SelectItem value="daily">Every day</SelectItem>
+                <SelectItem value="weekly">Every week</SelectItem>
+                <SelectItem value="cron">Cron expression</SelectItem>
+              </SelectContent>
+            </Select>
+          </div>
+
+          {kind === "weekly" && (
+            <div className="space-y-2">
+              <Label>Day</Label>
+              <Select value={day} onValueChange={(v) => setDay(v as Weekday)}>
+                <SelectTrigger>
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  {weekdays.map((d) => (
+                    <SelectItem key={d.value} value={d.value}>
+                      {d.label}
+                    </SelectItem>
+                  ))}
+                </SelectContent>
+              </Select>
+            </div>
+          )}
+
+          {(kind === "daily" || kind === "weekly") && (
+            <div className="space-y-2">
+              <Label htmlFor="scheduleTime">Time</Label>
+              <Input
+                id="scheduleTime"
+                type="time"
+                value={time}
+                onChange={(e) => setTime(e.target.value)}
+              />
+            </div>
+          )}
+
+          {kind === "cron" && (
+            <div className="space-y-2">
+              <Label htmlFor="scheduleCron">Expression</Label>
+              <Input
+                id="scheduleCron"
+                className="font-mono"
+                placeholder="minute hour day month weekday"
+                value={expression}
+                onChange={(e) => setExpression(e.target.value)}
+              />
+            </div>
+          )}
+
+          {error && <p className="text-sm text-red-600">{error}</p>}
+          {preview.length > 0 && (
+            <div className="text-sm text-muted-foreground">
+              <p>Next runs:</p>
+              {preview.map((run) => (
+                <p key={run}>{formatDate(run)}</p>
+              ))}
+            </div>
+          )}
+        </div>
+
+        <DialogFooter>
+          <Button variant="outline" onClick={() => onOpenChange(false)}>
+            Cancel
+          </Button>
+          <Button onClick={handleSave} disabled={saving || !!error}>
+            {saving && <Loader2 className="h-4 w-4 mr-2 animate-spin" />}
+            Save
+          </Button>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+}
@@ -0,0 +1,65 @@
+import { ArrowRight, Plus, Trash2 } from "lucide-react";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import type { SchemaRename } from "@/types";
+
+interface SchemaRenamesEditorProps {
+  renames: SchemaRename[];
+  onChange: (renames: SchemaRename[]) => void;
+}
+
+export function SchemaRenamesEditor({
+  renames,
+  onChange,
+}: SchemaRenamesEditorProps) {
+  const update = (index: number, patch: Partial<SchemaRename>) => {
+    onChange(renames.map((r, i) => (i === index ? { ...r, ...patch } : r)));
+  };
+
+  return (
+    <div className="space-y-3">
+      <div className="flex items-center justify-between">
+        <div>
+          <Label>Schema Renames</Label>
+          <p className="text-sm text-muted-foreground">
+            Restore a source schema under another name on the destination
+          </p>
+        </div>
+        <Button
+          variant="outline"
+          size="sm"
+          onClick={() => onChange([...renames, { from: "", to: "" }])}
+        >
+          <Plus className="h-4 w-4 mr-2" />
+          Add Rename
+        </Button>
+      </div>
+
+      {renames.map((rename, index) => (
+        <div key={index} className="flex items-center gap-2">
+          <Input
+            className="flex-1 font-mono"
+            placeholder="public"
+            value={rename.from}
+            onChange={(e) => update(index, { from: e.target.value })}
+          />
+          <ArrowRight className="h-4 w-4 text-muted-foreground" />
+          <Input
+            className="flex-1 font-mono"
+            placeholder="tenant_42"
+            value={rename.to}
+            onChange={(e) => update(index, { to: e.target.value })}
+          />
+          <Button
+            variant="ghost"
+            size="icon"
+            onClick={() => onChange(renames.filter((_, i) => i !== index))}
+          >
+            <Trash2 className="h-4 w-4" />
+          </Button>
+        </div>
+      ))}
+    </div>
+  );
+}
@@ -0,0 +1,108 @@
+import { Plus, Trash2 } from "lucide-react";
+import { Button } from "@/components/ui/button";
+import { Checkbox } from "@/components/ui/checkbox";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import type { SubsetOptions, SubsetRoot } from "@/types";
+
+interface SubsetEditorProps {
+  subset: SubsetOptions;
+  onChange: (subset: SubsetOptions) => void;
+}
+
+export function SubsetEditor({ subset, onChange }: SubsetEditorProps) {
+  const updateRoot = (index: number, patch: Partial<SubsetRoot>) => {
+    onChange({
+      ...subset,
+      roots: subset.roots.map((r, i) => (i === index ? { ...r, ...patch } : r)),
+    });
+  };
+
+  return (
+    <div className="space-y-3">
+      <div className="flex items-center justify-between">
+        <div>
+          <Label>Subset Roots</Label>
+          <p className="text-sm text-muted-foreground">
+            Rows referenced by the selected rows are pulled in so no foreign key
+            dangles
+          </p>
+        </div>
+        <Button
+          variant="outline"
+          size="sm"
+          onClick={() =>
+            onChange({
+              ...subset,
+              roots: [
+                ...subset.roots,
+                { table: "", whereClause: null, percent: null },
+              ],
+            })
+          }
+        >
+          <Plus className="h-4 w-4 mr-2" />
+          Add Root
+        </Button>
+      </div>
+
+      {subset.roots.map((root, index) => (
+        <div key={index} className="flex items-center gap-2">
+          <Input
+            className="w-40 font-mono"
+            placeholder="public.customers"
+            value={root.table}
+            onChange={(e) => updateRoot(index, { table: e.target.value })}
+          />
+          <span className="text-sm text-muted-foreground">WHERE</span>
+          <Input
+            className="flex-1 font-mono"
+            placeholder="country = 'PE'"
+            value={root.whereClause ?? ""}
+            onChange={(e) =>
+              updateRoot(index, { whereClause: e.target.value || null })
+            }
+          />
+          <Input
+            className="w-20"
+            type="number"
+            min={0}
+            max={100}
+            placeholder="%"
+            value={root.percent ?? ""}
+            onChange={(e) =>
+              updateRoot(index, {
+                percent: e.target.value === "" ? null : Number(e.target.value),
+              })
+            }
+          />
+          <Button
+            variant="ghost"
+            size="icon"
+            onClick={() =>
+              onChange({
+                ...subset,
+                roots: subset.roots.filter((_, i) => i !== index),
+              })
+            }
+          >
+            <Trash2 className="h-4 w-4" />
+          </Button>
+        </div>
+      ))}
+
+      <div className="flex items-center space-x-2">
+        <Checkbox
+          id="include-referencing"
+          checked={subset.includeReferencing}
+          onCheckedChange={(c) =>
+            onChange({ ...subset, includeReferencing: c as boolean })
+          }
+        />
+        <Label htmlFor="include-referencing" className="cursor-pointer">
+          Also include rows that reference the selected rows
+        </Label>
+      </div>
+    </div>
+  );
+}
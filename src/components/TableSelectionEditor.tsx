@@ -0,0 +1,75 @@
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import type { TableSelection } from "@/types";
+
+const FIELDS: {
+  key: keyof TableSelection;
+  label: string;
+  placeholder: string;
+}[] = [
+  { key: "includeSchemas", label: "Include schemas", placeholder: "billing" },
+  {
+    key: "excludeSchemas",
+    label: "Exclude schemas",
+    placeholder: "audit, tmp_*",
+  },
+  {
+    key: "includeTables",
+    label: "Include tables",
+    placeholder: "public.users",
+  },
+  { key: "excludeTables", label: "Exclude tables", placeholder: "*.sessions" },
+  {
+    key: "excludeTableData",
+    label: "Exclude table data",
+    placeholder: "audit_log, event_store",
+  },
+];
+
+interface TableSelectionEditorProps {
+  selection: TableSelection;
+  onChange: (selection: TableSelection) => void;
+}
+
+function parsePatterns(text: string): string[] {
+  return text
+    .split(",")
+    .map((p) => p.trim())
+    .filter(Boolean);
+}
+
+export function TableSelectionEditor({
+  selection,
+  onChange,
+}: TableSelectionEditorProps) {
+  return (
+    <div className="space-y-3">
+      <div>
+        <Label>Schemas and Tables</Label>
+        <p className="text-sm text-muted-foreground">
+          Comma-separated patterns; <code>*</code> and <code>?</code> are
+          wildcards. Included tables take precedence over the schema lists;
+          tables with excluded data keep their definition but no rows.
+        </p>
+      </div>
+      <div className="grid grid-cols-2 gap-3">
+        {FIELDS.map(({ key, label, placeholder }) => (
+          <div key={key} className="space-y-1">
+            <Label htmlFor={key} className="text-sm">
+              {label}
+            </Label>
+            <Input
+              id={key}
+              className="font-mono"
+              placeholder={placeholder}
+              defaultValue={selection[key].join(", ")}
+              onBlur={(e) =>
+                onChange({ ...selection, [key]: parsePatterns(e.target.value) })
+              }
+            />
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
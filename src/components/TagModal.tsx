@@ -12,6 +12,7 @@ import {
 } from "@/components/ui/dialog";
 import { ColorPicker } from "@/components/ColorPicker";
 import { Tag } from "@/types";
+import { getErrorMessage } from "@/lib/utils";
 
 interface TagModalProps {
   open: boolean;
@@ -45,7 +46,7 @@ export function TagModal({ open, onOpenChange, onTagCreated }: TagModalProps) {
       setName("");
       setColor("#3B82F6");
     } catch (err) {
-      setError(err as string);
+      setError(getErrorMessage(err));
     } finally {
       setLoading(false);
     }
@@ -7,13 +7,27 @@ import type {
   DatabaseStructure,
   CloneOptions,
   CloneProgress,
+  CloneProgressEvent,
+  CloneLogEvent,
   CloneHistoryEntry,
+  CloneJob,
   Tag,
+  PiiScan,
   SavedOperation,
-  CloneType,
   SchemaProgress,
-  SchemaExportOptions
+  SchemaExportOptions,
+  AppDataBackup,
+  ExportFormat,
+  GlobalsCloneOptions,
+  GlobalsCloneResult,
+  HistoryFilter,
+  HistoryPage,
+  HistoryPairStats,
+  HistoryRetention,
+  MissedRunPolicy,
+  OperationSchedule
 } from '@/types'
+import { getErrorMessage } from '@/lib/utils'
 
 // Profile hooks
 export function useProfiles() {
@@ -29,7 +43,7 @@ export function useProfiles() {
       setProfiles(result)
       setError(null)
     } catch (e) {
-      setError(e as string)
+      setError(getErrorMessage(e))
     } finally {
       if (isInitial) {
         setLoading(false)
@@ -131,21 +145,49 @@ export async function startClone(options: CloneOptions): Promise<string> {
   return invoke<string>('start_clone', { options })
 }
 
-export function useCloneProgress() {
-  const [progress, setProgress] = useState<CloneProgress | null>(null)
-  const [logs, setLogs] = useState<string[]>([])
+interface JobProgress {
+  progress: CloneProgress | null
+  destinationProgress: Record<string, CloneProgress>
+  logs: string[]
+}
+
+const EMPTY_JOB_PROGRESS: JobProgress = { progress: null, destinationProgress: {}, logs: [] }
+
+// Events are kept per job so clones running side by side do not mix, and so
+// events that arrive before startClone returns the job id are not lost
+export function useCloneProgress(jobId: string | null) {
+  const [jobs, setJobs] = useState<Record<string, JobProgress>>({})
 
   useEffect(() => {
     let unlistenProgress: UnlistenFn | undefined
     let unlistenLog: UnlistenFn | undefined
 
     const setup = async () => {
-      unlistenProgress = await listen<CloneProgress>('clone-progress', (event) => {
-        setProgress(event.payload)
+      unlistenProgress = await listen<CloneProgressEvent>('clone-progress', (event) => {
+        const { jobId, ...payload } = event.payload
+        const destinationId = payload.destinationId
+        setJobs((prev) => {
+          const job = prev[jobId] ?? EMPTY_JOB_PROGRESS
+          return {
+            ...prev,
+            [jobId]: {
+              ...job,
+              destinationProgress: destinationId
+                ? { ...job.destinationProgress, [destinationId]: payload }
+                : job.destinationProgress,
+              // One destination finishing does not finish a clone into several
+              progress: !destinationId || !payload.isComplete ? payload : job.progress,
+            },
+          }
+        })
       })
 
-      unlistenLog = await listen<string>('clone-log', (event) => {
-        setLogs((prev) => [...prev, event.payload])
+      unlistenLog = await listen<CloneLogEvent>('clone-log', (event) => {
+        const { jobId, line } = event.payload
+        setJobs((prev) => {
+          const job = prev[jobId] ?? EMPTY_JOB_PROGRESS
+          return { ...prev, [jobId]: { ...job, logs: [...job.logs, line] } }
+        })
       })
     }
 
@@ -158,55 +200,91 @@ export function useCloneProgress() {
   }, [])
 
   const reset = useCallback(() => {
-    setProgress(null)
-    setLogs([])
+    setJobs({})
   }, [])
 
-  return { progress, logs, reset }
+  const job = (jobId && jobs[jobId]) || EMPTY_JOB_PROGRESS
+  return {
+    progress: job.progress,
+    destinationProgress: job.destinationProgress,
+    logs: job.logs,
+    reset,
+  }
 }
 
 // History hooks
-export function useHistory() {
+export function useHistory(filter: HistoryFilter = {}) {
   const [history, setHistory] = useState<CloneHistoryEntry[]>([])
+  const [total, setTotal] = useState(0)
   const [loading, setLoading] = useState(true)
   const [error, setError] = useState<string | null>(null)
   const [initialized, setInitialized] = useState(false)
 
+  // Re-query whenever the filter values change, not on every render
+  const filterKey = JSON.stringify(filter)
+
   const fetchHistory = useCallback(async (isInitial = false) => {
     try {
       if (isInitial) setLoading(true)
-      const result = await invoke<CloneHistoryEntry[]>('get_history')
-      setHistory(result)
+      const result = await invoke<HistoryPage>('query_history', { filter: JSON.parse(filterKey) })
+      setHistory(result.entries)
+      setTotal(result.total)
       setError(null)
     } catch (e) {
-      setError(e as string)
+      setError(getErrorMessage(e))
     } finally {
       if (isInitial) {
         setLoading(false)
         setInitialized(true)
       }
     }
-  }, [])
+  }, [filterKey])
 
   useEffect(() => {
-    if (!initialized) {
-      fetchHistory(true)
-    }
-  }, [fetchHistory, initialized])
+    fetchHistory(!initialized)
+  }, [fetchHistory])
 
   const refetch = useCallback(() => fetchHistory(false), [fetchHistory])
 
-  return { history, loading, error, refetch }
+  return { history, total, loading, error, refetch }
 }
 
 export async function getHistoryEntry(id: string): Promise<CloneHistoryEntry | null> {
   return invoke<CloneHistoryEntry | null>('get_history_entry', { id })
 }
 
+export async function deleteHistoryEntry(id: string): Promise<void> {
+  return invoke<void>('delete_history_entry', { id })
+}
+
+export async function rerunHistoryEntry(id: string): Promise<string> {
+  return invoke<string>('rerun_history_entry', { id })
+}
+
 export async function clearHistory(): Promise<void> {
   return invoke<void>('clear_history')
 }
 
+export async function queryHistory(filter: HistoryFilter): Promise<HistoryPage> {
+  return invoke<HistoryPage>('query_history', { filter })
+}
+
+export async function exportHistory(ids: string[], format: ExportFormat, path: string): Promise<void> {
+  return invoke<void>('export_history', { ids, format, path })
+}
+
+export async function getHistoryStats(): Promise<HistoryPairStats[]> {
+  return invoke<HistoryPairStats[]>('get_history_stats')
+}
+
+export async function getHistoryRetention(): Promise<HistoryRetention> {
+  return invoke<HistoryRetention>('get_history_retention')
+}
+
+export async function setHistoryRetention(retention: HistoryRetention): Promise<HistoryRetention> {
+  return invoke<HistoryRetention>('set_history_retention', { retention })
+}
+
 // Tag hooks
 export function useTags() {
   const [tags, setTags] = useState<Tag[]>([])
@@ -221,7 +299,7 @@ export function useTags() {
       setTags(result)
       setError(null)
     } catch (e) {
-      setError(e as string)
+      setError(getErrorMessage(e))
     } finally {
       if (isInitial) {
         setLoading(false)
@@ -267,7 +345,7 @@ export function useSavedOperations() {
       setSavedOperations(result)
       setError(null)
     } catch (e) {
-      setError(e as string)
+      setError(getErrorMessage(e))
     } finally {
       if (isInitial) {
         setLoading(false)
@@ -289,26 +367,34 @@ export function useSavedOperations() {
 
 export async function createSavedOperation(
   name: string,
-  sourceId: string,
-  destinationId: string,
-  cleanDestination: boolean,
-  createBackup: boolean,
-  cloneType: CloneType
+  options: CloneOptions
 ): Promise<SavedOperation> {
-  return invoke<SavedOperation>('create_saved_operation', {
-    name,
-    sourceId,
-    destinationId,
-    cleanDestination,
-    createBackup,
-    cloneType
-  })
+  return invoke<SavedOperation>('create_saved_operation', { name, options })
 }
 
 export async function deleteSavedOperation(id: string): Promise<void> {
   return invoke<void>('delete_saved_operation', { id })
 }
 
+export async function setOperationSchedule(
+  id: string,
+  schedule: OperationSchedule | null
+): Promise<SavedOperation> {
+  return invoke<SavedOperation>('set_operation_schedule', { id, schedule })
+}
+
+export async function previewSchedule(schedule: OperationSchedule): Promise<string[]> {
+  return invoke<string[]>('preview_schedule', { schedule })
+}
+
+export async function getMissedRunPolicy(): Promise<MissedRunPolicy> {
+  return invoke<MissedRunPolicy>('get_missed_run_policy')
+}
+
+export async function setMissedRunPolicy(policy: MissedRunPolicy): Promise<MissedRunPolicy> {
+  return invoke<MissedRunPolicy>('set_missed_run_policy', { policy })
+}
+
 // Schema download hooks
 export async function downloadSchema(options: SchemaExportOptions): Promise<string> {
   return invoke<string>('download_schema', { options })
@@ -318,6 +404,14 @@ export async function getDatabaseStructure(profileId: string): Promise<DatabaseS
   return invoke<DatabaseStructure>('get_database_structure', { profileId })
 }
 
+export async function listDatabases(profileId: string): Promise<string[]> {
+  return invoke<string[]>('list_databases', { profileId })
+}
+
+export async function scanPii(profileId: string): Promise<PiiScan> {
+  return invoke<PiiScan>('scan_pii', { profileId })
+}
+
 export function useSchemaProgress() {
   const [progress, setProgress] = useState<SchemaProgress | null>(null)
   const [logs, setLogs] = useState<string[]>([])
@@ -351,3 +445,87 @@ export function useSchemaProgress() {
 
   return { progress, logs, reset }
 }
+
+// Job queue hooks
+export function useJobs() {
+  const [jobs, setJobs] = useState<CloneJob[]>([])
+
+  useEffect(() => {
+    let unlisten: UnlistenFn | undefined
+
+    const setup = async () => {
+      unlisten = await listen<CloneJob[]>('jobs-changed', (event) => {
+        setJobs(event.payload)
+      })
+      setJobs(await invoke<CloneJob[]>('list_jobs'))
+    }
+
+    setup()
+
+    return () => {
+      unlisten?.()
+    }
+  }, [])
+
+  return { jobs, setJobs }
+}
+
+export async function moveJob(id: string, position: number): Promise<CloneJob[]> {
+  return invoke<CloneJob[]>('move_job', { id, position })
+}
+
+export async function removeJob(id: string): Promise<CloneJob[]> {
+  return invoke<CloneJob[]>('remove_job', { id })
+}
+
+export async function getJobConcurrency(): Promise<number> {
+  return invoke<number>('get_job_concurrency')
+}
+
+export async function setJobConcurrency(limit: number): Promise<number> {
+  return invoke<number>('set_job_concurrency', { limit })
+}
+
+// Globals hooks
+export async function cloneGlobals(options: GlobalsCloneOptions): Promise<GlobalsCloneResult> {
+  return invoke<GlobalsCloneResult>('clone_globals', { options })
+}
+
+export function useGlobalsLogs() {
+  const [logs, setLogs] = useState<string[]>([])
+
+  useEffect(() => {
+    let unlisten: UnlistenFn | undefined
+
+    const setup = async () => {
+      unlisten = await listen<string>('globals-log', (event) => {
+        setLogs((prev) => [...prev, event.payload])
+      })
+    }
+
+    setup()
+
+    return () => {
+      unlisten?.()
+    }
+  }, [])
+
+  const reset = useCallback(() => {
+    setLogs([])
+  }, [])
+
+  return { logs, reset }
+}
+
+// App data backup hooks
+export async function listAppDataBackups(): Promise<AppDataBackup[]> {
+  return invoke<AppDataBackup[]>('list_app_data_backups')
+}
+
+export async function getAppDataBackupError(): Promise<string | null> {
+  return invoke<string | null>('get_app_data_backup_error')
+}
+
+export async function restoreAppDataBackup(name: string): Promise<void> {
+  await invoke('restore_app_data_backup', { name })
+}
@@ -1,10 +1,21 @@
 import { type ClassValue, clsx } from "clsx"
 import { twMerge } from "tailwind-merge"
+import type { AppError } from "@/types"
 
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs))
 }
 
+export function isAppError(error: unknown): error is AppError {
+  return typeof error === 'object' && error !== null && 'code' in error && 'message' in error
+}
+
+export function getErrorMessage(error: unknown): string {
+  if (isAppError(error)) return error.message
+  if (error instanceof Error) return error.message
+  return String(error)
+}
+
 export function parseConnectionUrl(url: string): {
   host: string
   port: number
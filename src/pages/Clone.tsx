@@ -1,4 +1,4 @@
-import { useState, useEffect, useRef } from "react";
+import { useState, useEffect, useRef, useCallback } from "react";
 import { useNavigate, useSearchParams } from "react-router-dom";
 import {
   ArrowLeft,
@@ -36,6 +36,8 @@ import {
   useTags,
   useCloneProgress,
   startClone,
+  getHistoryEntry,
+  rerunHistoryEntry,
   useSavedOperations,
   createSavedOperation,
   deleteSavedOperation,
@@ -43,23 +45,54 @@ import {
 import { DatabaseSelectorModal } from "@/components/DatabaseSelectorModal";
 import { SaveOperationModal } from "@/components/SaveOperationModal";
 import { LoadOperationModal } from "@/components/LoadOperationModal";
+import { RowFiltersEditor } from "@/components/RowFiltersEditor";
+import { SubsetEditor } from "@/components/SubsetEditor";
+import { TableSelectionEditor } from "@/components/TableSelectionEditor";
+import { DatabasesEditor } from "@/components/DatabasesEditor";
+import { DestinationsEditor } from "@/components/DestinationsEditor";
+import { RoleMapEditor } from "@/components/RoleMapEditor";
+import { SchemaRenamesEditor } from "@/components/SchemaRenamesEditor";
+import { MaskingEditor } from "@/components/MaskingEditor";
 import { useNotification } from "@/hooks/use-notification";
-import type { CloneOptions, CloneType, SavedOperation } from "@/types";
-import { cn } from "@/lib/utils";
+import type {
+  CloneOptions,
+  CloneType,
+  MaskingOptions,
+  SavedOperation,
+  RoleMapping,
+  SchemaRename,
+  SubsetOptions,
+  TableRowFilter,
+  TableSelection,
+} from "@/types";
+import { cn, getErrorMessage } from "@/lib/utils";
 
 type Step = "databases" | "options" | "progress";
 
+const EMPTY_SELECTION: TableSelection = {
+  includeSchemas: [],
+  excludeSchemas: [],
+  includeTables: [],
+  excludeTables: [],
+  excludeTableData: [],
+};
+
 export function Clone() {
   const navigate = useNavigate();
   const [searchParams] = useSearchParams();
   const { profiles, loading: profilesLoading } = useProfiles();
   const { tags } = useTags();
-  const { progress, logs, reset } = useCloneProgress();
+  const [jobId, setJobId] = useState<string | null>(null);
+  const { progress, destinationProgress, logs, reset } =
+    useCloneProgress(jobId);
   const { savedOperations, refetch: refetchSavedOperations } =
     useSavedOperations();
   const { notifySuccess, notifyError } = useNotification();
   const logsEndRef = useRef<HTMLDivElement>(null);
   const notifiedRef = useRef(false);
+  const rerunStartedRef = useRef(false);
+  // Options of the clone started last, saved as an operation when it succeeds
+  const startedOptionsRef = useRef<CloneOptions | null>(null);
 
   const [step, setStep] = useState<Step>("databases");
   const [sourceId, setSourceId] = useState(searchParams.get("source") || "");
@@ -67,7 +100,18 @@ export function Clone() {
   const [cleanDestination, setCleanDestination] = useState(true);
   const [createBackup, setCreateBackup] = useState(false);
   const [cloneType, setCloneType] = useState<CloneType>("both");
-  const [excludeTables] = useState<string[]>([]);
+  const [serverClone, setServerClone] = useState(false);
+  const [databases, setDatabases] = useState<string[]>([]);
+  const [fanOut, setFanOut] = useState(false);
+  const [destinationIds, setDestinationIds] = useState<string[]>([]);
+  const [restoreConcurrency, setRestoreConcurrency] = useState(2);
+  const [selection, setSelection] = useState<TableSelection>(EMPTY_SELECTION);
+  const [schemaRenames, setSchemaRenames] = useState<SchemaRename[]>([]);
+  const [keepOwnership, setKeepOwnership] = useState(false);
+  const [roleMap, setRoleMap] = useState<RoleMapping[]>([]);
+  const [rowFilters, setRowFilters] = useState<TableRowFilter[]>([]);
+  const [subset, setSubset] = useState<SubsetOptions | null>(null);
+  const [masking, setMasking] = useState<MaskingOptions | null>(null);
   const [cloning, setCloning] = useState(false);
 
   const [sourceModalOpen, setSourceModalOpen] = useState(false);
@@ -92,25 +136,97 @@ export function Clone() {
     if (!sourceId || !destinationId) return;
 
     reset();
+    setJobId(null);
     setStep("progress");
     setCloning(true);
 
     const options: CloneOptions = {
       sourceId,
       destinationId,
+      destinationIds: fanOut
+        ? destinationIds.filter((id) => id !== sourceId && id !== destinationId)
+        : [],
+      restoreConcurrency,
       cleanDestination,
       createBackup,
       cloneType,
-      excludeTables,
+      databases: serverClone ? databases : [],
+      ...selection,
+      schemaRenames: schemaRenames.filter((r) => r.from.trim() || r.to.trim()),
+      keepOwnership,
+      roleMap: keepOwnership
+        ? roleMap.filter((m) => m.from.trim() || m.to.trim())
+        : [],
+      // Rows left completely blank are dropped; half-filled ones are rejected
+      rowFilters:
+        cloneType === "structure" || subset
+          ? []
+          : rowFilters.filter((f) => f.table.trim() || f.whereClause.trim()),
+      subset: cloneType === "structure" ? null : subset,
+      masking: cloneType === "structure" ? null : masking,
     };
 
+    startedOptionsRef.current = options;
     try {
-      await startClone(options);
+      setJobId(await startClone(options));
     } catch (error) {
       console.error("Clone failed:", error);
     }
   };
 
+  // Fills the form from the options of a past clone or a saved operation
+  const applyOptions = useCallback((options: CloneOptions) => {
+    setSourceId(options.sourceId);
+    setDestinationId(options.destinationId);
+    setCleanDestination(options.cleanDestination);
+    setCreateBackup(options.createBackup);
+    setCloneType(options.cloneType);
+    setSelection({
+      includeSchemas: options.includeSchemas ?? [],
+      excludeSchemas: options.excludeSchemas ?? [],
+      includeTables: options.includeTables ?? [],
+      excludeTables: options.excludeTables,
+      excludeTableData: options.excludeTableData ?? [],
+    });
+    setFanOut((options.destinationIds ?? []).length > 0);
+    setDestinationIds(options.destinationIds ?? []);
+    setRestoreConcurrency(options.restoreConcurrency ?? 2);
+    setServerClone((options.databases ?? []).length > 0);
+    setDatabases(options.databases ?? []);
+    setSchemaRenames(options.schemaRenames ?? []);
+    setKeepOwnership(options.keepOwnership ?? false);
+    setRoleMap(options.roleMap ?? []);
+    setRowFilters(options.rowFilters ?? []);
+    setSubset(options.subset ?? null);
+    setMasking(options.masking ?? null);
+  }, []);
+
+  // Re-run a past clone from history with its recorded options
+  useEffect(() => {
+    const rerunId = searchParams.get("rerun");
+    if (!rerunId || rerunStartedRef.current) return;
+    rerunStartedRef.current = true;
+
+    reset();
+    setJobId(null);
+    setStep("progress");
+    setCloning(true);
+
+    getHistoryEntry(rerunId)
+      .then((entry) => {
+        if (entry?.options) applyOptions(entry.options);
+      })
+      .catch((error) => console.error("Failed to load history entry:", error));
+
+    rerunHistoryEntry(rerunId)
+      .then(setJobId)
+      .catch((error) => {
+        setCloning(false);
+        setStep("databases");
+        notifyError("Re-run Failed", getErrorMessage(error));
+      });
+  }, [searchParams, reset, notifyError, applyOptions]);
+
   useEffect(() => {
     if (progress?.isComplete) {
       setCloning(false);
@@ -129,15 +245,9 @@ export function Clone() {
       }
 
       // Save operation if pending and clone was successful
-      if (pendingOperationName && !progress.isError) {
-        createSavedOperation(
-          pendingOperationName,
-          sourceId,
-          destinationId,
-          cleanDestination,
-          createBackup,
-          cloneType
-        )
+      const options = startedOptionsRef.current;
+      if (pendingOperationName && options && !progress.isError) {
+        createSavedOperation(pendingOperationName, options)
           .then(() => {
             refetchSavedOperations();
             setPendingOperationName(null);
@@ -155,11 +265,6 @@ export function Clone() {
     progress?.isError,
     progress?.message,
     pendingOperationName,
-    sourceId,
-    destinationId,
-    cleanDestination,
-    createBackup,
-    cloneType,
     sourceProfile?.name,
     destinationProfile?.name,
     refetchSavedOperations,
@@ -169,9 +274,22 @@ export function Clone() {
 
   const handleReset = () => {
     reset();
+    setJobId(null);
     setStep("databases");
     setSourceId("");
     setDestinationId("");
+    setServerClone(false);
+    setDatabases([]);
+    setFanOut(false);
+    setDestinationIds([]);
+    setRestoreConcurrency(2);
+    setSelection(EMPTY_SELECTION);
+    setSchemaRenames([]);
+    setKeepOwnership(false);
+    setRoleMap([]);
+    setRowFilters([]);
+    setSubset(null);
+    setMasking(null);
     setCloning(false);
     setPendingOperationName(null);
   };
@@ -181,11 +299,7 @@ export function Clone() {
   };
 
   const handleLoadOperation = (operation: SavedOperation) => {
-    setSourceId(operation.sourceId);
-    setDestinationId(operation.destinationId);
-    setCleanDestination(operation.cleanDestination);
-    setCreateBackup(operation.createBackup);
-    setCloneType(operation.cloneType);
+    if (operation.options) applyOptions(operation.options);
   };
 
   const handleDeleteOperation = async (id: string) => {
@@ -217,7 +331,10 @@ export function Clone() {
           sourceId !== destinationId
         );
       case "options":
-        return true;
+        return (
+          (!serverClone || databases.length > 0) &&
+          (!fanOut || destinationIds.length > 0)
+        );
       default:
         return false;
     }
@@ -539,6 +656,152 @@ export function Clone() {
               </Select>
             </div>
 
+            {!fanOut && (
+              <div className="space-y-4">
+                <div className="flex items-center space-x-2">
+                  <Checkbox
+                    id="serverClone"
+                    checked={serverClone}
+                    onCheckedChange={(c) => setServerClone(c as boolean)}
+                  />
+                  <Label htmlFor="serverClone" className="cursor-pointer font-medium">
+                    Clone several databases of the server
+                  </Label>
+                </div>
+                {serverClone && (
+                  <DatabasesEditor
+                    sourceId={sourceId}
+                    databases={databases}
+                    onChange={setDatabases}
+                  />
+                )}
+              </div>
+            )}
+
+            {!serverClone && (
+              <div className="space-y-4">
+                <div className="flex items-center space-x-2">
+                  <Checkbox
+                    id="fanOut"
+                    checked={fanOut}
+                    onCheckedChange={(c) => setFanOut(c as boolean)}
+                  />
+                  <Label htmlFor="fanOut" className="cursor-pointer font-medium">
+                    Restore into several destinations
+                  </Label>
+                </div>
+                {fanOut && (
+                  <DestinationsEditor
+                    profiles={profiles.filter(
+                      (p) => p.id !== sourceId && p.id !== destinationId
+                    )}
+                    destinationIds={destinationIds}
+                    concurrency={restoreConcurrency}
+                    onChange={setDestinationIds}
+                    onConcurrencyChange={setRestoreConcurrency}
+                  />
+                )}
+              </div>
+            )}
+
+            <TableSelectionEditor
+              selection={selection}
+              onChange={setSelection}
+            />
+
+            <SchemaRenamesEditor
+              renames={schemaRenames}
+              onChange={setSchemaRenames}
+            />
+
+            {cloneType !== "data" && (
+              <div className="space-y-4">
+                <div className="flex items-center space-x-2">
+                  <Checkbox
+                    id="keepOwnership"
+                    checked={keepOwnership}
+                    onCheckedChange={(c) => setKeepOwnership(c as boolean)}
+                  />
+                  <Label
+                    htmlFor="keepOwnership"
+                    className="cursor-pointer font-medium"
+                  >
+                    Keep ownership and privileges
+                  </Label>
+                </div>
+                {keepOwnership && (
+                  <RoleMapEditor mappings={roleMap} onChange={setRoleMap} />
+                )}
+              </div>
+            )}
+
+            {cloneType !== "structure" && (
+              <div className="space-y-4">
+                <div className="flex items-center space-x-2">
+                  <Checkbox
+                    id="subset"
+                    checked={subset !== null}
+                    onCheckedChange={(c) =>
+                      setSubset(
+                        c
+                          ? {
+                              roots: [
+                                { table: "", whereClause: null, percent: null },
+                              ],
+                              includeReferencing: false,
+                            }
+                          : null
+                      )
+                    }
+                  />
+                  <Label htmlFor="subset" className="cursor-pointer font-medium">
+                    Copy a referentially consistent subset
+                  </Label>
+                </div>
+                {subset ? (
+                  <SubsetEditor subset={subset} onChange={setSubset} />
+                ) : (
+                  <RowFiltersEditor
+                    filters={rowFilters}
+                    onChange={setRowFilters}
+                  />
+                )}
+
+                <div className="flex items-center space-x-2">
+                  <Checkbox
+                    id="masking"
+                    checked={masking !== null}
+                    onCheckedChange={(c) =>
+                      setMasking(
+                        c
+                          ? {
+                              rules: [
+                                {
+                                  table: "",
+                                  column: "",
+                                  strategy: { kind: "fakeEmail" },
+                                },
+                              ],
+                              seed: crypto.randomUUID(),
+                            }
+                          : null
+                      )
+                    }
+                  />
+                  <Label htmlFor="masking" className="cursor-pointer font-medium">
+                    Mask sensitive columns
+                  </Label>
+                </div>
+                {masking && (
+                  <MaskingEditor
+                    sourceId={sourceId}
+                    masking={masking}
+                    onChange={setMasking}
+                  />
+                )}
+              </div>
+            )}
+
             {/* Options */}
             <div className="space-y-4">
               <div className="flex items-start space-x-3 p-3 rounded-lg border hover:bg-muted/50 transition-colors">
@@ -623,6 +886,33 @@ export function Clone() {
                   {progress.message}
                 </p>
 
+                {fanOut && Object.keys(destinationProgress).length > 0 && (
+                  <div className="rounded-md border divide-y text-sm">
+                    {Object.entries(destinationProgress).map(([id, p]) => (
+                      <div key={id} className="px-3 py-2 space-y-1">
+                        <div className="flex justify-between">
+                          <span className="font-medium">
+                            {profiles.find((profile) => profile.id === id)?.name}
+                          </span>
+                          <span
+                            className={cn(
+                              "capitalize",
+                              p.isError
+                                ? "text-red-600"
+                                : p.isComplete
+                                  ? "text-green-600"
+                                  : "text-muted-foreground"
+                            )}
+                          >
+                            {p.isError ? "Failed" : p.stage}
+                          </span>
+                        </div>
+                        <Progress value={p.progress} className="h-1.5" />
+                      </div>
+                    ))}
+                  </div>
+                )}
+
                 {progress.isComplete && !progress.isError && (
                   <div className="p-4 bg-green-500/10 border border-green-500/20 rounded-lg flex items-center gap-3">
                     <CheckCircle2 className="h-6 w-6 text-green-600" />
@@ -764,6 +1054,7 @@ export function Clone() {
         profiles={profiles}
         onLoad={handleLoadOperation}
         onDelete={handleDeleteOperation}
+        onScheduled={refetchSavedOperations}
       />
     </div>
   );
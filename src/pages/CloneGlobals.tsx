@@ -0,0 +1,259 @@
+import { useState, useEffect, useRef } from "react";
+import { useNavigate } from "react-router-dom";
+import {
+  AlertTriangle,
+  ArrowLeft,
+  CheckCircle2,
+  Loader2,
+  Play,
+} from "lucide-react";
+import { Button } from "@/components/ui/button";
+import {
+  Card,
+  CardContent,
+  CardDescription,
+  CardHeader,
+  CardTitle,
+} from "@/components/ui/card";
+import { Checkbox } from "@/components/ui/checkbox";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { useProfiles, useGlobalsLogs, cloneGlobals } from "@/hooks/use-tauri";
+import { useNotification } from "@/hooks/use-notification";
+import { cn, getErrorMessage } from "@/lib/utils";
+import type { GlobalsCloneResult } from "@/types";
+
+export function CloneGlobals() {
+  const navigate = useNavigate();
+  const { profiles } = useProfiles();
+  const { logs, reset } = useGlobalsLogs();
+  const { notifySuccess, notifyError } = useNotification();
+  const logsEndRef = useRef<HTMLDivElement>(null);
+
+  const [sourceId, setSourceId] = useState("");
+  const [destinationId, setDestinationId] = useState("");
+  const [includeRoles, setIncludeRoles] = useState(true);
+  const [includeTablespaces, setIncludeTablespaces] = useState(true);
+  const [stripPasswords, setStripPasswords] = useState(true);
+  const [roleFilter, setRoleFilter] = useState("");
+  const [running, setRunning] = useState(false);
+  const [result, setResult] = useState<GlobalsCloneResult | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  // Auto-scroll logs
+  useEffect(() => {
+    if (logsEndRef.current) {
+      logsEndRef.current.scrollIntoView({ behavior: "smooth" });
+    }
+  }, [logs]);
+
+  const handleRun = async () => {
+    if (!sourceId || !destinationId) return;
+
+    reset();
+    setResult(null);
+    setError(null);
+    setRunning(true);
+
+    try {
+      const outcome = await cloneGlobals({
+        sourceId,
+        destinationId,
+        includeRoles,
+        includeTablespaces,
+        stripPasswords,
+        roleFilter: roleFilter
+          .split(",")
+          .map((p) => p.trim())
+          .filter(Boolean),
+      });
+      setResult(outcome);
+      notifySuccess(
+        "Globals Cloned",
+        `${outcome.applied} statements applied, ${outcome.failed.length} failed`
+      );
+    } catch (e) {
+      const message = getErrorMessage(e);
+      setError(message);
+      notifyError("Globals Clone Failed", message);
+    } finally {
+      setRunning(false);
+    }
+  };
+
+  const profileSelect = (
+    value: string,
+    onChange: (id: string) => void,
+    placeholder: string
+  ) => (
+    <Select value={value} onValueChange={onChange}>
+      <SelectTrigger>
+        <SelectValue placeholder={placeholder} />
+      </SelectTrigger>
+      <SelectContent>
+        {profiles.map((p) => (
+          <SelectItem key={p.id} value={p.id}>
+            {p.name} ({p.host}:{p.port})
+          </SelectItem>
+        ))}
+      </SelectContent>
+    </Select>
+  );
+
+  return (
+    <div className="space-y-6">
+      {/* Header */}
+      <div className="flex items-center gap-4">
+        <Button variant="ghost" size="icon" onClick={() => navigate("/")}>
+          <ArrowLeft className="h-5 w-5" />
+        </Button>
+        <div>
+          <h1 className="text-2xl font-bold">Clone Globals</h1>
+          <p className="text-muted-foreground">
+            Copy roles, role memberships and tablespaces between servers
+          </p>
+        </div>
+      </div>
+
+      <Card>
+        <CardHeader>
+          <CardTitle>Servers</CardTitle>
+          <CardDescription>
+            Globals belong to the server, so any profile on it will do
+          </CardDescription>
+        </CardHeader>
+        <CardContent className="space-y-6">
+          <div className="grid md:grid-cols-2 gap-4">
+            <div className="space-y-2">
+              <Label>Source</Label>
+              {profileSelect(sourceId, setSourceId, "Select source server")}
+            </div>
+            <div className="space-y-2">
+              <Label>Destination</Label>
+              {profileSelect(
+                destinationId,
+                setDestinationId,
+                "Select destination server"
+              )}
+            </div>
+          </div>
+
+          <div className="space-y-3">
+            <div className="flex items-center space-x-2">
+              <Checkbox
+                id="includeRoles"
+                checked={includeRoles}
+                onCheckedChange={(c) => setIncludeRoles(c as boolean)}
+              />
+              <Label htmlFor="includeRoles" className="cursor-pointer">
+                Roles and memberships
+              </Label>
+            </div>
+            <div className="flex items-center space-x-2">
+              <Checkbox
+                id="includeTablespaces"
+                checked={includeTablespaces}
+                onCheckedChange={(c) => setIncludeTablespaces(c as boolean)}
+              />
+              <Label htmlFor="includeTablespaces" className="cursor-pointer">
+                Tablespaces
+              </Label>
+            </div>
+            <div className="flex items-center space-x-2">
+              <Checkbox
+                id="stripPasswords"
+                checked={stripPasswords}
+                onCheckedChange={(c) => setStripPasswords(c as boolean)}
+              />
+              <Label htmlFor="stripPasswords" className="cursor-pointer">
+                Strip password hashes
+              </Label>
+            </div>
+          </div>
+
+          {includeRoles && (
+            <div className="space-y-2">
+              <Label htmlFor="roleFilter">Roles</Label>
+              <Input
+                id="roleFilter"
+                className="font-mono"
+                placeholder="app_*, readers (empty = all roles)"
+                value={roleFilter}
+                onChange={(e) => setRoleFilter(e.target.value)}
+              />
+              <p className="text-sm text-muted-foreground">
+                Roles that already exist on the destination are left unchanged
+              </p>
+            </div>
+          )}
+
+          <Button
+            onClick={handleRun}
+            disabled={
+              running ||
+              !sourceId ||
+              !destinationId ||
+              (!includeRoles && !includeTablespaces)
+            }
+          >
+            {running ? (
+              <Loader2 className="h-4 w-4 mr-2 animate-spin" />
+            ) : (
+              <Play className="h-4 w-4 mr-2" />
+            )}
+            Clone Globals
+          </Button>
+        </CardContent>
+      </Card>
+
+      {(running || result || error || logs.length > 0) && (
+        <Card>
+          <CardHeader>
+            <CardTitle>Result</CardTitle>
+          </CardHeader>
+          <CardContent className="space-y-4">
+            {result && (
+              <div className="p-4 bg-green-500/10 border border-green-500/20 rounded-lg flex items-center gap-3">
+                <CheckCircle2 className="h-6 w-6 text-green-600" />
+                <p className="text-sm">
+                  {result.applied} statements applied, {result.skipped.length}{" "}
+                  objects left unchanged, {result.failed.length} failed
+                </p>
+              </div>
+            )}
+            {error && (
+              <div className="p-4 bg-red-500/10 border border-red-500/20 rounded-lg flex items-center gap-3">
+                <AlertTriangle className="h-6 w-6 text-red-600" />
+                <p className="text-sm text-muted-foreground">{error}</p>
+              </div>
+            )}
+
+            <div className="h-64 w-full rounded-lg border bg-muted/30 overflow-auto p-4 font-mono text-sm">
+              {logs.map((log, i) => (
+                <div
+                  key={i}
+                  className={cn(
+                    "py-0.5 leading-relaxed whitespace-pre-wrap",
+                    log.includes("[WARNING]") && "text-yellow-500",
+                    log.includes("[SUCCESS]") && "text-green-500",
+                    log.includes("[INFO]") && "text-muted-foreground"
+                  )}
+                >
+                  {log}
+                </div>
+              ))}
+              <div ref={logsEndRef} />
+            </div>
+          </CardContent>
+        </Card>
+      )}
+    </div>
+  );
+}
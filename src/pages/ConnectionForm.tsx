@@ -19,7 +19,7 @@ import {
   testConnection,
   useTags,
 } from "@/hooks/use-tauri";
-import { parseConnectionUrl } from "@/lib/utils";
+import { getErrorMessage, parseConnectionUrl } from "@/lib/utils";
 import { invoke } from "@tauri-apps/api/core";
 import { TagSelect } from "@/components/TagSelect";
 import { TagModal } from "@/components/TagModal";
@@ -123,7 +123,7 @@ export function ConnectionForm() {
       );
       setTestResult(result);
     } catch (error) {
-      setTestError(error as string);
+      setTestError(getErrorMessage(error));
     } finally {
       setTesting(false);
     }
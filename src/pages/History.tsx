@@ -1,8 +1,17 @@
-import { useState } from 'react'
-import { ArrowLeft, Trash2, Loader2, CheckCircle, XCircle, Clock, ArrowRight } from 'lucide-react'
+import { useState, useEffect, useCallback } from 'react'
+import { ArrowLeft, Trash2, Loader2, CheckCircle, XCircle, Clock, ArrowRight, Search, ChevronLeft, ChevronRight, Download, RotateCcw } from 'lucide-react'
+import { save } from '@tauri-apps/plugin-dialog'
 import { useNavigate } from 'react-router-dom'
 import { Button } from '@/components/ui/button'
-import { Card, CardContent } from '@/components/ui/card'
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from '@/components/ui/card'
+import { Input } from '@/components/ui/input'
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from '@/components/ui/select'
 import { Badge } from '@/components/ui/badge'
 import { ScrollArea } from '@/components/ui/scroll-area'
 import {
@@ -13,23 +22,146 @@ import {
   DialogHeader,
   DialogTitle,
 } from '@/components/ui/dialog'
-import { useHistory, clearHistory } from '@/hooks/use-tauri'
+import {
+  useHistory,
+  clearHistory,
+  deleteHistoryEntry,
+  exportHistory,
+  getHistoryEntry,
+  getHistoryStats,
+  queryHistory,
+} from '@/hooks/use-tauri'
 import { formatDate, cn } from '@/lib/utils'
-import type { CloneHistoryEntry } from '@/types'
+import type { CloneHistoryEntry, CloneType, ExportFormat, HistoryFilter, HistoryPairStats } from '@/types'
+
+const PAGE_SIZE = 20
+
+// The format is picked from the extension chosen in the save dialog
+const EXPORT_FORMATS: Record<string, ExportFormat> = {
+  json: 'json',
+  csv: 'csv',
+  md: 'markdown',
+  html: 'html',
+}
+
+type StatusFilter = 'all' | CloneHistoryEntry['status']
+
+function statusVariant(status: CloneHistoryEntry['status']) {
+  switch (status) {
+    case 'success':
+      return 'success'
+    case 'running':
+      return 'secondary'
+    case 'interrupted':
+      return 'warning'
+    default:
+      return 'destructive'
+  }
+}
+type CloneTypeFilter = 'all' | CloneType
 
 export function History() {
   const navigate = useNavigate()
-  const { history, loading, refetch } = useHistory()
+  const [search, setSearch] = useState('')
+  const [status, setStatus] = useState<StatusFilter>('all')
+  const [cloneType, setCloneType] = useState<CloneTypeFilter>('all')
+  const [page, setPage] = useState(0)
+  const filter: HistoryFilter = {
+    search: search.trim() || null,
+    status: status === 'all' ? null : status,
+    cloneType: cloneType === 'all' ? null : cloneType,
+  }
+  const { history, total, loading, refetch } = useHistory({
+    ...filter,
+    limit: PAGE_SIZE,
+    offset: page * PAGE_SIZE,
+  })
+  const [stats, setStats] = useState<HistoryPairStats[]>([])
   const [showClearDialog, setShowClearDialog] = useState(false)
   const [clearing, setClearing] = useState(false)
+  const [deleting, setDeleting] = useState(false)
+  const [exporting, setExporting] = useState(false)
   const [selectedEntry, setSelectedEntry] = useState<CloneHistoryEntry | null>(null)
 
+  const hasFilters = search.trim() !== '' || status !== 'all' || cloneType !== 'all'
+  const pageCount = Math.max(1, Math.ceil(total / PAGE_SIZE))
+
+  const loadStats = useCallback(() => {
+    getHistoryStats()
+      .then(setStats)
+      .catch((error) => console.error('Failed to load history stats:', error))
+  }, [])
+
+  useEffect(() => {
+    loadStats()
+  }, [loadStats])
+
+  // Exports one entry, or every entry matching the current filters
+  const handleExport = async (entry?: CloneHistoryEntry) => {
+    setExporting(true)
+    try {
+      const path = await save({
+        defaultPath: entry ? `clone-${entry.id}.md` : 'clone-history.md',
+        filters: [
+          { name: 'Markdown Report', extensions: ['md'] },
+          { name: 'HTML Report', extensions: ['html'] },
+          { name: 'CSV', extensions: ['csv'] },
+          { name: 'JSON', extensions: ['json'] },
+        ],
+      })
+      if (!path) return
+
+      const extension = path.split('.').pop()?.toLowerCase() ?? ''
+      const format = EXPORT_FORMATS[extension] ?? 'markdown'
+      const ids = entry
+        ? [entry.id]
+        : (await queryHistory(filter)).entries.map((e) => e.id)
+      await exportHistory(ids, format, path)
+    } catch (error) {
+      console.error('Failed to export history:', error)
+    } finally {
+      setExporting(false)
+    }
+  }
+
+  const handleDelete = async (id: string) => {
+    setDeleting(true)
+    try {
+      await deleteHistoryEntry(id)
+      setSelectedEntry(null)
+      // Step back if the last entry of the last page was removed
+      if (history.length === 1 && page > 0) {
+        setPage(page - 1)
+      } else {
+        refetch()
+      }
+      loadStats()
+    } catch (error) {
+      console.error('Failed to delete history entry:', error)
+    } finally {
+      setDeleting(false)
+    }
+  }
+
+  // The list omits log lines, so load the full entry when one is opened
+  const handleSelect = async (entry: CloneHistoryEntry) => {
+    setSelectedEntry(entry)
+    try {
+      const full = await getHistoryEntry(entry.id)
+      if (full) setSelectedEntry(full)
+    } catch (error) {
+      console.error('Failed to load history entry:', error)
+    }
+  }
+
   const handleClear = async () => {
     setClearing(true)
     try {
       await clearHistory()
       setShowClearDialog(false)
+      setPage(0)
       refetch()
+      loadStats()
     } catch (error) {
       console.error('Failed to clear history:', error)
     } finally {
@@ -60,23 +192,125 @@ export function History() {
           </div>
         </div>
         {history.length > 0 && (
-          <Button
-            variant="outline"
-            onClick={() => setShowClearDialog(true)}
-          >
-            <Trash2 className="h-4 w-4 mr-2" />
-            Clear History
-          </Button>
+          <div className="flex items-center gap-2">
+            <Button
+              variant="outline"
+              onClick={() => handleExport()}
+              disabled={exporting}
+            >
+              {exporting ? (
+                <Loader2 className="h-4 w-4 mr-2 animate-spin" />
+              ) : (
+                <Download className="h-4 w-4 mr-2" />
+              )}
+              Export
+            </Button>
+            <Button
+              variant="outline"
+              onClick={() => setShowClearDialog(true)}
+            >
+              <Trash2 className="h-4 w-4 mr-2" />
+              Clear History
+            </Button>
+          </div>
         )}
       </div>
 
+      {stats.length > 0 && (
+        <Card>
+          <CardHeader>
+            <CardTitle className="text-base">Routes</CardTitle>
+            <CardDescription>Runs and average duration per source and destination</CardDescription>
+          </CardHeader>
+          <CardContent className="space-y-2">
+            {stats.slice(0, 5).map((pair) => (
+              <div
+                key={`${pair.sourceId}-${pair.destinationId}`}
+                className="flex items-center justify-between text-sm"
+              >
+                <div className="flex items-center gap-2">
+                  <span className="font-medium">{pair.sourceName}</span>
+                  <ArrowRight className="h-4 w-4 text-muted-foreground" />
+                  <span className="font-medium">{pair.destinationName}</span>
+                </div>
+                <div className="flex items-center gap-3 text-muted-foreground">
+                  <span>
+                    {pair.runs} runs ({pair.failures} failed)
+                  </span>
+                  <span>
+                    {pair.averageDuration !== null
+                      ? `avg ${Math.round(pair.averageDuration)}s`
+                      : 'avg N/A'}
+                  </span>
+                </div>
+              </div>
+            ))}
+          </CardContent>
+        </Card>
+      )}
+
+      <div className="flex flex-wrap items-center gap-3">
+        <div className="relative flex-1 min-w-[200px]">
+          <Search className="absolute left-3 top-1/2 -translate-y-1/2 h-4 w-4 text-muted-foreground" />
+          <Input
+            value={search}
+            onChange={(e) => {
+              setSearch(e.target.value)
+              setPage(0)
+            }}
+            placeholder="Search logs and errors..."
+            className="pl-9"
+          />
+        </div>
+        <Select
+          value={status}
+          onValueChange={(v) => {
+            setStatus(v as StatusFilter)
+            setPage(0)
+          }}
+        >
+          <SelectTrigger className="w-[140px]">
+            <SelectValue />
+          </SelectTrigger>
+          <SelectContent>
+            <SelectItem value="all">All statuses</SelectItem>
+            <SelectItem value="success">Success</SelectItem>
+            <SelectItem value="error">Error</SelectItem>
+            <SelectItem value="cancelled">Cancelled</SelectItem>
+            <SelectItem value="running">Running</SelectItem>
+            <SelectItem value="interrupted">Interrupted</SelectItem>
+          </SelectContent>
+        </Select>
+        <Select
+          value={cloneType}
+          onValueChange={(v) => {
+            setCloneType(v as CloneTypeFilter)
+            setPage(0)
+          }}
+        >
+          <SelectTrigger className="w-[140px]">
+            <SelectValue />
+          </SelectTrigger>
+          <SelectContent>
+            <SelectItem value="all">All types</SelectItem>
+            <SelectItem value="both">Full</SelectItem>
+            <SelectItem value="structure">Schema</SelectItem>
+            <SelectItem value="data">Data</SelectItem>
+          </SelectContent>
+        </Select>
+      </div>
+
       {history.length === 0 ? (
         <Card>
           <CardContent className="flex flex-col items-center justify-center h-64">
             <Clock className="h-12 w-12 text-muted-foreground mb-4" />
-            <h3 className="text-lg font-medium">No history yet</h3>
+            <h3 className="text-lg font-medium">
+              {hasFilters ? 'No matching entries' : 'No history yet'}
+            </h3>
             <p className="text-muted-foreground">
-              Clone operations will appear here
+              {hasFilters
+                ? 'Try a different search or filter'
+                : 'Clone operations will appear here'}
             </p>
           </CardContent>
         </Card>
@@ -86,7 +320,7 @@ export function History() {
             <Card
               key={entry.id}
               className="cursor-pointer hover:shadow-md transition-shadow"
-              onClick={() => setSelectedEntry(entry)}
+              onClick={() => handleSelect(entry)}
             >
               <CardContent className="py-4">
                 <div className="flex items-center justify-between">
@@ -95,6 +329,10 @@ export function History() {
                       <div className="p-2 bg-green-500/10 rounded-full">
                         <CheckCircle className="h-5 w-5 text-green-600" />
                       </div>
+                    ) : entry.status === 'running' ? (
+                      <div className="p-2 bg-blue-500/10 rounded-full">
+                        <Loader2 className="h-5 w-5 text-blue-600 animate-spin" />
+                      </div>
                     ) : (
                       <div className="p-2 bg-red-500/10 rounded-full">
                         <XCircle className="h-5 w-5 text-red-600" />
@@ -134,7 +372,7 @@ export function History() {
                         : 'Data'}
                     </Badge>
                     <Badge
-                      variant={entry.status === 'success' ? 'success' : 'destructive'}
+                      variant={statusVariant(entry.status)}
                     >
                       {entry.status}
                     </Badge>
@@ -143,6 +381,35 @@ export function History() {
               </CardContent>
             </Card>
           ))}
+
+          {pageCount > 1 && (
+            <div className="flex items-center justify-between">
+              <p className="text-sm text-muted-foreground">
+                Showing {page * PAGE_SIZE + 1}–{page * PAGE_SIZE + history.length} of {total}
+              </p>
+              <div className="flex items-center gap-2">
+                <Button
+                  variant="outline"
+                  size="icon"
+                  onClick={() => setPage(page - 1)}
+                  disabled={page === 0}
+                >
+                  <ChevronLeft className="h-4 w-4" />
+                </Button>
+                <span className="text-sm">
+                  {page + 1} / {pageCount}
+                </span>
+                <Button
+                  variant="outline"
+                  size="icon"
+                  onClick={() => setPage(page + 1)}
+                  disabled={page + 1 >= pageCount}
+                >
+                  <ChevronRight className="h-4 w-4" />
+                </Button>
+              </div>
+            </div>
+          )}
         </div>
       )}
 
@@ -162,7 +429,7 @@ export function History() {
                 <div>
                   <p className="text-sm text-muted-foreground">Status</p>
                   <Badge
-                    variant={selectedEntry.status === 'success' ? 'success' : 'destructive'}
+                    variant={statusVariant(selectedEntry.status)}
                   >
                     {selectedEntry.status}
                   </Badge>
@@ -181,8 +448,174 @@ export function History() {
                     {selectedEntry.duration ? `${selectedEntry.duration} seconds` : 'N/A'}
                   </p>
                 </div>
+                {selectedEntry.verifiedTables !== null && (
+                  <div>
+                    <p className="text-sm text-muted-foreground">Verified Tables</p>
+                    <p className="font-medium">{selectedEntry.verifiedTables}</p>
+                  </div>
+                )}
+                {selectedEntry.options && (
+                  <>
+                    <div>
+                      <p className="text-sm text-muted-foreground">Clean Destination</p>
+                      <p className="font-medium">
+                        {selectedEntry.options.cleanDestination ? 'Yes' : 'No'}
+                      </p>
+                    </div>
+                    <div>
+                      <p className="text-sm text-muted-foreground">Create Backup</p>
+                      <p className="font-medium">
+                        {selectedEntry.options.createBackup ? 'Yes' : 'No'}
+                      </p>
+                    </div>
+                    {(
+                      [
+                        ['Included Schemas', selectedEntry.options.includeSchemas],
+                        ['Excluded Schemas', selectedEntry.options.excludeSchemas],
+                        ['Included Tables', selectedEntry.options.includeTables],
+                        ['Excluded Tables', selectedEntry.options.excludeTables],
+                        ['Excluded Table Data', selectedEntry.options.excludeTableData]
+                      ] as [string, string[] | undefined][]
+                    )
+                      .filter(([, patterns]) => patterns && patterns.length > 0)
+                      .map(([label, patterns]) => (
+                        <div key={label} className="col-span-2">
+                          <p className="text-sm text-muted-foreground">{label}</p>
+                          <p className="font-medium">{patterns?.join(', ')}</p>
+                        </div>
+                      ))}
+                    {selectedEntry.options.schemaRenames.length > 0 && (
+                      <div className="col-span-2">
+                        <p className="text-sm text-muted-foreground">Schema Renames</p>
+                        <p className="font-medium">
+                          {selectedEntry.options.schemaRenames
+                            .map((r) => `${r.from} → ${r.to}`)
+                            .join(', ')}
+                        </p>
+                      </div>
+                    )}
+                    {selectedEntry.options.keepOwnership && (
+                      <div className="col-span-2">
+                        <p className="text-sm text-muted-foreground">Ownership</p>
+                        <p className="font-medium">
+                          {selectedEntry.options.roleMap?.length
+                            ? `Kept, ${selectedEntry.options.roleMap
+                                .map((m) => `${m.from} → ${m.to}`)
+                                .join(', ')}`
+                            : 'Kept'}
+                        </p>
+                      </div>
+                    )}
+                  </>
+                )}
+                {selectedEntry.environment && (
+                  <>
+                    <div>
+                      <p className="text-sm text-muted-foreground">Parallel Jobs</p>
+                      <p className="font-medium">
+                        {selectedEntry.environment.parallelJobs ?? 'N/A'}
+                      </p>
+                    </div>
+                    <div>
+                      <p className="text-sm text-muted-foreground">Server Versions</p>
+                      <p className="font-medium">
+                        {selectedEntry.environment.sourceServerVersion ?? 'unknown'} →{' '}
+                        {selectedEntry.environment.destinationServerVersion ?? 'unknown'}
+                      </p>
+                    </div>
+                    <div className="col-span-2">
+                      <p className="text-sm text-muted-foreground">Tools</p>
+                      {[
+                        selectedEntry.environment.pgDump,
+                        selectedEntry.environment.pgRestore,
+                        selectedEntry.environment.psql,
+                      ].map((tool) => (
+                        <p key={tool.path} className="text-sm font-mono break-all">
+                          {tool.version ?? tool.path}
+                        </p>
+                      ))}
+                    </div>
+                  </>
+                )}
               </div>
 
+              {selectedEntry.destinationResults && selectedEntry.destinationResults.length > 0 && (
+                <div>
+                  <p className="text-sm text-muted-foreground mb-2">Destinations</p>
+                  <div className="rounded-md border divide-y text-sm">
+                    {selectedEntry.destinationResults.map((result) => (
+                      <div key={result.destinationId} className="px-3 py-1.5">
+                        <div className="flex justify-between">
+                          <span>
+                            {result.destinationName}
+                            {result.serverVersion && (
+                              <span className="text-xs text-muted-foreground ml-2">
+                                PostgreSQL {result.serverVersion}
+                              </span>
+                            )}
+                          </span>
+                          <span
+                            className={cn(
+                              'font-medium',
+                              result.status === 'success' ? 'text-green-600' : 'text-red-600'
+                            )}
+                          >
+                            {result.status === 'success'
+                              ? `${result.verifiedTables ?? 0} tables, ${result.duration}s`
+                              : 'Failed'}
+                          </span>
+                        </div>
+                        {result.errorMessage && (
+                          <p className="text-xs text-muted-foreground mt-1">{result.errorMessage}</p>
+                        )}
+                      </div>
+                    ))}
+                  </div>
+                </div>
+              )}
+
+              {selectedEntry.databaseResults && selectedEntry.databaseResults.length > 0 && (
+                <div>
+                  <p className="text-sm text-muted-foreground mb-2">Databases</p>
+                  <div className="rounded-md border divide-y text-sm">
+                    {selectedEntry.databaseResults.map((result) => (
+                      <div key={result.database} className="px-3 py-1.5">
+                        <div className="flex justify-between">
+                          <span className="font-mono">{result.database}</span>
+                          <span
+                            className={cn(
+                              'font-medium',
+                              result.status === 'success' ? 'text-green-600' : 'text-red-600'
+                            )}
+                          >
+                            {result.status === 'success'
+                              ? `${result.verifiedTables ?? 0} tables, ${result.duration}s`
+                              : 'Failed'}
+                          </span>
+                        </div>
+                        {result.errorMessage && (
+                          <p className="text-xs text-muted-foreground mt-1">{result.errorMessage}</p>
+                        )}
+                      </div>
+                    ))}
+                  </div>
+                </div>
+              )}
+
+              {selectedEntry.copiedRows && selectedEntry.copiedRows.length > 0 && (
+                <div>
+                  <p className="text-sm text-muted-foreground mb-2">Copied Rows</p>
+                  <div className="rounded-md border divide-y text-sm">
+                    {selectedEntry.copiedRows.map((count) => (
+                      <div key={count.table} className="flex justify-between px-3 py-1.5">
+                        <span className="font-mono">{count.table}</span>
+                        <span className="font-medium">{count.rows.toLocaleString()}</span>
+                      </div>
+                    ))}
+                  </div>
+                </div>
+              )}
+
               {selectedEntry.errorMessage && (
                 <div className="p-4 bg-red-500/10 border border-red-500/20 rounded-lg">
                   <p className="text-sm font-medium text-red-600">Error Message</p>
@@ -219,6 +652,38 @@ export function History() {
               </div>
             </div>
           )}
+
+          <DialogFooter>
+            <Button
+              variant="outline"
+              onClick={() => selectedEntry && navigate(`/clone?rerun=${selectedEntry.id}`)}
+              disabled={!selectedEntry?.options}
+              title={selectedEntry?.options ? undefined : 'Options were not recorded for this clone'}
+            >
+              <RotateCcw className="h-4 w-4 mr-2" />
+              Re-run
+            </Button>
+            <Button
+              variant="outline"
+              onClick={() => selectedEntry && handleExport(selectedEntry)}
+              disabled={exporting}
+            >
+              <Download className="h-4 w-4 mr-2" />
+              Export
+            </Button>
+            <Button
+              variant="destructive"
+              onClick={() => selectedEntry && handleDelete(selectedEntry.id)}
+              disabled={deleting}
+            >
+              {deleting ? (
+                <Loader2 className="h-4 w-4 mr-2 animate-spin" />
+              ) : (
+                <Trash2 className="h-4 w-4 mr-2" />
+              )}
+              Delete Entry
+            </Button>
+          </DialogFooter>
         </DialogContent>
       </Dialog>
 
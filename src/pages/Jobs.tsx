@@ -0,0 +1,207 @@
+import { ReactNode, useEffect, useState } from "react";
+import { useNavigate } from "react-router-dom";
+import {
+  ArrowDown,
+  ArrowLeft,
+  ArrowRight,
+  ArrowUp,
+  ListOrdered,
+  Trash2,
+} from "lucide-react";
+import { Badge } from "@/components/ui/badge";
+import { Button } from "@/components/ui/button";
+import {
+  Card,
+  CardContent,
+  CardDescription,
+  CardHeader,
+  CardTitle,
+} from "@/components/ui/card";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import {
+  useJobs,
+  moveJob,
+  removeJob,
+  getJobConcurrency,
+  setJobConcurrency,
+} from "@/hooks/use-tauri";
+import { useNotification } from "@/hooks/use-notification";
+import { formatDate, getErrorMessage } from "@/lib/utils";
+import type { CloneJob, JobStatus } from "@/types";
+
+const statusVariant = (status: JobStatus) => {
+  switch (status) {
+    case "success":
+      return "success";
+    case "error":
+      return "destructive";
+    case "running":
+      return "default";
+    default:
+      return "secondary";
+  }
+};
+
+export function Jobs() {
+  const navigate = useNavigate();
+  const { jobs, setJobs } = useJobs();
+  const { notifyError } = useNotification();
+  const [concurrency, setConcurrency] = useState(2);
+
+  useEffect(() => {
+    getJobConcurrency().then(setConcurrency).catch(() => {});
+  }, []);
+
+  const queued = jobs.filter((j) => j.status === "queued");
+  const active = jobs.filter((j) => j.status === "running");
+  const finished = jobs
+    .filter((j) => j.status === "success" || j.status === "error")
+    .reverse();
+
+  const run = async (action: () => Promise<CloneJob[]>) => {
+    try {
+      setJobs(await action());
+    } catch (e) {
+      notifyError("Queue Update Failed", getErrorMessage(e));
+    }
+  };
+
+  const handleConcurrency = async (value: number) => {
+    const limit = Math.max(1, value || 1);
+    setConcurrency(limit);
+    try {
+      await setJobConcurrency(limit);
+    } catch (e) {
+      notifyError("Failed to Save Limit", getErrorMessage(e));
+    }
+  };
+
+  const jobRow = (job: CloneJob, actions?: ReactNode) => (
+    <div
+      key={job.id}
+      className="flex items-center justify-between gap-4 p-3 rounded-lg border"
+    >
+      <div className="min-w-0">
+        <div className="flex items-center gap-2">
+          <span className="font-medium">{job.sourceName}</span>
+          <ArrowRight className="h-4 w-4 text-muted-foreground" />
+          <span className="font-medium truncate">{job.destinationName}</span>
+        </div>
+        <div className="text-sm text-muted-foreground">
+          {job.startedAt
+            ? `Started ${formatDate(job.startedAt)}`
+            : `Queued ${formatDate(job.queuedAt)}`}
+        </div>
+        {job.errorMessage && (
+          <p className="text-sm text-red-600 truncate">{job.errorMessage}</p>
+        )}
+      </div>
+      <div className="flex items-center gap-2">
+        <Badge variant={statusVariant(job.status)}>{job.status}</Badge>
+        {actions}
+      </div>
+    </div>
+  );
+
+  return (
+    <div className="space-y-6">
+      {/* Header */}
+      <div className="flex items-center gap-4">
+        <Button variant="ghost" size="icon" onClick={() => navigate("/")}>
+          <ArrowLeft className="h-5 w-5" />
+        </Button>
+        <div>
+          <h1 className="text-2xl font-bold">Queue</h1>
+          <p className="text-muted-foreground">
+            Clones wait here for a free slot; two clones never write to the
+            same database at once
+          </p>
+        </div>
+      </div>
+
+      <Card>
+        <CardHeader>
+          <CardTitle>Running</CardTitle>
+          <CardDescription>
+            <span className="flex items-center gap-3">
+              <Label htmlFor="jobConcurrency">Clones at a time</Label>
+              <Input
+                id="jobConcurrency"
+                type="number"
+                min={1}
+                className="w-20"
+                value={concurrency}
+                onChange={(e) => handleConcurrency(Number(e.target.value))}
+              />
+            </span>
+          </CardDescription>
+        </CardHeader>
+        <CardContent className="space-y-2">
+          {active.length === 0 && (
+            <p className="text-sm text-muted-foreground">No clone is running</p>
+          )}
+          {active.map((job) => jobRow(job))}
+        </CardContent>
+      </Card>
+
+      <Card>
+        <CardHeader>
+          <CardTitle>Waiting</CardTitle>
+        </CardHeader>
+        <CardContent className="space-y-2">
+          {queued.length === 0 && (
+            <div className="flex items-center gap-2 text-sm text-muted-foreground">
+              <ListOrdered className="h-4 w-4" />
+              Nothing is waiting
+            </div>
+          )}
+          {queued.map((job, i) =>
+            jobRow(
+              job,
+              <>
+                <Button
+                  variant="ghost"
+                  size="icon"
+                  disabled={i === 0}
+                  onClick={() => run(() => moveJob(job.id, i - 1))}
+                >
+                  <ArrowUp className="h-4 w-4" />
+                </Button>
+                <Button
+                  variant="ghost"
+                  size="icon"
+                  disabled={i === queued.length - 1}
+                  onClick={() => run(() => moveJob(job.id, i + 1))}
+                >
+                  <ArrowDown className="h-4 w-4" />
+                </Button>
+                <Button
+                  variant="ghost"
+                  size="icon"
+                  onClick={() => run(() => removeJob(job.id))}
+                >
+                  <Trash2 className="h-4 w-4" />
+                </Button>
+              </>
+            )
+          )}
+        </CardContent>
+      </Card>
+
+      {finished.length > 0 && (
+        <Card>
+          <CardHeader>
+            <CardTitle>Finished</CardTitle>
+            <CardDescription>
+              Full details are kept in the history
+            </CardDescription>
+          </CardHeader>
+          <CardContent className="space-y-2">
+            {finished.map((job) => jobRow(job))}
+          </CardContent>
+        </Card>
+      )}
+    </div>
+  );
+}
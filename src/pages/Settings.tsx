@@ -1,5 +1,5 @@
 import { useState, useEffect } from "react";
-import { ArrowLeft, Monitor, Moon, Sun, Palette, RefreshCw, Download, CheckCircle, XCircle, Info } from "lucide-react";
+import { ArrowLeft, Monitor, Moon, Sun, Palette, RefreshCw, Download, CheckCircle, XCircle, Info, History, Clock, CalendarClock } from "lucide-react";
 import { useNavigate } from "react-router-dom";
 import { Button } from "@/components/ui/button";
 import {
@@ -9,12 +9,23 @@ import {
   CardHeader,
   CardTitle,
 } from "@/components/ui/card";
+import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
 import { Progress } from "@/components/ui/progress";
 import { useTheme, Theme } from "@/hooks/use-theme";
 import { useColorTheme } from "@/hooks/use-color-theme";
 import { useUpdater } from "@/hooks/use-updater";
-import { cn } from "@/lib/utils";
+import {
+  getAppDataBackupError,
+  getHistoryRetention,
+  getMissedRunPolicy,
+  listAppDataBackups,
+  restoreAppDataBackup,
+  setHistoryRetention,
+  setMissedRunPolicy,
+} from "@/hooks/use-tauri";
+import { cn, formatBytes, formatDate, getErrorMessage } from "@/lib/utils";
+import type { AppDataBackup, MissedRunPolicy } from "@/types";
 import { getVersion } from "@tauri-apps/api/app";
 
 const themes: { value: Theme; label: string; icon: typeof Sun }[] = [
@@ -29,11 +40,75 @@ export function Settings() {
   const { colorTheme, setColorTheme, colorThemes } = useColorTheme();
   const { available, checking, downloading, progress, version: newVersion, error, checkForUpdates, downloadAndInstall } = useUpdater();
   const [currentVersion, setCurrentVersion] = useState<string>("");
+  const [backups, setBackups] = useState<AppDataBackup[]>([]);
+  const [restoring, setRestoring] = useState<string | null>(null);
+  const [backupError, setBackupError] = useState<string | null>(null);
+  const [maxEntries, setMaxEntries] = useState("");
+  const [maxAgeDays, setMaxAgeDays] = useState("");
+  const [savingRetention, setSavingRetention] = useState(false);
+  const [retentionError, setRetentionError] = useState<string | null>(null);
+  const [retentionSaved, setRetentionSaved] = useState(false);
+  const [missedRunPolicy, setMissedRunPolicyState] = useState<MissedRunPolicy>("runOnce");
+  const [scheduleError, setScheduleError] = useState<string | null>(null);
 
   useEffect(() => {
     getVersion().then(setCurrentVersion);
+    listAppDataBackups().then(setBackups).catch((e) => setBackupError(getErrorMessage(e)));
+    getAppDataBackupError()
+      .then((error) => error && setBackupError(`The backup taken at startup failed: ${error}`))
+      .catch((e) => setBackupError(getErrorMessage(e)));
+    getHistoryRetention()
+      .then((retention) => {
+        setMaxEntries(retention.maxEntries?.toString() ?? "");
+        setMaxAgeDays(retention.maxAgeDays?.toString() ?? "");
+      })
+      .catch((e) => setRetentionError(getErrorMessage(e)));
+    getMissedRunPolicy()
+      .then(setMissedRunPolicyState)
+      .catch((e) => setScheduleError(getErrorMessage(e)));
   }, []);
 
+  const handleMissedRunPolicy = async (policy: MissedRunPolicy) => {
+    setScheduleError(null);
+    try {
+      setMissedRunPolicyState(await setMissedRunPolicy(policy));
+    } catch (e) {
+      setScheduleError(getErrorMessage(e));
+    }
+  };
+
+  const handleSaveRetention = async () => {
+    setSavingRetention(true);
+    setRetentionError(null);
+    setRetentionSaved(false);
+    try {
+      // Empty fields mean no limit
+      await setHistoryRetention({
+        maxEntries: maxEntries.trim() ? Number(maxEntries) : null,
+        maxAgeDays: maxAgeDays.trim() ? Number(maxAgeDays) : null,
+      });
+      setRetentionSaved(true);
+    } catch (e) {
+      setRetentionError(getErrorMessage(e));
+    } finally {
+      setSavingRetention(false);
+    }
+  };
+
+  const handleRestoreBackup = async (name: string) => {
+    setRestoring(name);
+    setBackupError(null);
+    try {
+      await restoreAppDataBackup(name);
+      // Profiles, tags and history are loaded at startup, so reload everything
+      window.location.reload();
+    } catch (e) {
+      setBackupError(getErrorMessage(e));
+    } finally {
+      setRestoring(null);
+    }
+  };
+
   // Determinar si estamos en modo oscuro
   const isDark =
     theme === "dark" ||
@@ -147,6 +222,146 @@ export function Settings() {
         </CardContent>
       </Card>
 
+      {/* History Retention */}
+      <Card>
+        <CardHeader>
+          <CardTitle className="flex items-center gap-2">
+            <Clock className="h-5 w-5" />
+            History Retention
+          </CardTitle>
+          <CardDescription>
+            Older clone history is deleted automatically. Leave a field empty for no limit.
+          </CardDescription>
+        </CardHeader>
+        <CardContent className="space-y-4">
+          <div className="grid grid-cols-2 gap-4">
+            <div className="space-y-2">
+              <Label htmlFor="max-entries">Keep at most (entries)</Label>
+              <Input
+                id="max-entries"
+                type="number"
+                min={1}
+                value={maxEntries}
+                onChange={(e) => {
+                  setMaxEntries(e.target.value);
+                  setRetentionSaved(false);
+                }}
+                placeholder="Unlimited"
+              />
+            </div>
+            <div className="space-y-2">
+              <Label htmlFor="max-age">Keep for (days)</Label>
+              <Input
+                id="max-age"
+                type="number"
+                min={1}
+                value={maxAgeDays}
+                onChange={(e) => {
+                  setMaxAgeDays(e.target.value);
+                  setRetentionSaved(false);
+                }}
+                placeholder="Forever"
+              />
+            </div>
+          </div>
+          {retentionError && (
+            <div className="p-3 bg-red-500/10 border border-red-500/20 rounded-md flex items-start gap-2">
+              <XCircle className="h-5 w-5 text-red-600 mt-0.5 shrink-0" />
+              <p className="text-xs text-muted-foreground">{retentionError}</p>
+            </div>
+          )}
+          <div className="flex items-center gap-3">
+            <Button onClick={handleSaveRetention} disabled={savingRetention}>
+              {savingRetention && <RefreshCw className="h-4 w-4 mr-2 animate-spin" />}
+              Save
+            </Button>
+            {retentionSaved && (
+              <span className="flex items-center gap-1 text-sm text-muted-foreground">
+                <CheckCircle className="h-4 w-4 text-green-600" />
+                Saved
+              </span>
+            )}
+          </div>
+        </CardContent>
+      </Card>
+
+      {/* Scheduled Operations */}
+      <Card>
+        <CardHeader>
+          <CardTitle className="flex items-center gap-2">
+            <CalendarClock className="h-5 w-5" />
+            Scheduled Operations
+          </CardTitle>
+          <CardDescription>
+            Saved operations only run on schedule while the app is open. Choose what happens to runs missed while it was closed.
+          </CardDescription>
+        </CardHeader>
+        <CardContent className="space-y-4">
+          <div className="flex gap-2">
+            <Button
+              variant={missedRunPolicy === "runOnce" ? "default" : "outline"}
+              onClick={() => handleMissedRunPolicy("runOnce")}
+            >
+              Run once on start
+            </Button>
+            <Button
+              variant={missedRunPolicy === "skip" ? "default" : "outline"}
+              onClick={() => handleMissedRunPolicy("skip")}
+            >
+              Skip missed runs
+            </Button>
+          </div>
+          {scheduleError && (
+            <div className="p-3 bg-red-500/10 border border-red-500/20 rounded-md flex items-start gap-2">
+              <XCircle className="h-5 w-5 text-red-600 mt-0.5 shrink-0" />
+              <p className="text-xs text-muted-foreground">{scheduleError}</p>
+            </div>
+          )}
+        </CardContent>
+      </Card>
+
+      {/* Data Backups */}
+      <Card>
+        <CardHeader>
+          <CardTitle className="flex items-center gap-2">
+            <History className="h-5 w-5" />
+            Data Backups
+          </CardTitle>
+          <CardDescription>
+            Previous versions of your profiles, tags, operations and history
+          </CardDescription>
+        </CardHeader>
+        <CardContent className="space-y-2">
+          {backupError && (
+            <div className="p-3 bg-red-500/10 border border-red-500/20 rounded-md flex items-start gap-2">
+              <XCircle className="h-5 w-5 text-red-600 mt-0.5 shrink-0" />
+              <p className="text-xs text-muted-foreground">{backupError}</p>
+            </div>
+          )}
+          {backups.length === 0 ? (
+            <p className="text-sm text-muted-foreground">No backups yet</p>
+          ) : (
+            backups.map((backup) => (
+              <div key={backup.name} className="flex items-center justify-between p-2 rounded-md border">
+                <div>
+                  <p className="text-sm font-medium">{formatDate(backup.createdAt)}</p>
+                  <p className="text-xs text-muted-foreground">{formatBytes(backup.size)}</p>
+                </div>
+                <Button
+                  variant="outline"
+                  size="sm"
+                  onClick={() => handleRestoreBackup(backup.name)}
+                  disabled={restoring !== null}
+                >
+                  {restoring === backup.name && <RefreshCw className="h-4 w-4 mr-2 animate-spin" />}
+                  Restore
+                </Button>
+              </div>
+            ))
+          )}
+        </CardContent>
+      </Card>
+
       {/* About & Updates */}
       <Card>
         <CardHeader>
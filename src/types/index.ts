@@ -34,10 +34,117 @@ export interface TableInfo {
 export interface CloneOptions {
   sourceId: string
   destinationId: string
+  destinationIds: string[]
+  restoreConcurrency: number
   cleanDestination: boolean
   createBackup: boolean
   cloneType: 'structure' | 'data' | 'both'
+  databases: string[]
   excludeTables: string[]
+  includeSchemas: string[]
+  excludeSchemas: string[]
+  includeTables: string[]
+  excludeTableData: string[]
+  schemaRenames: SchemaRename[]
+  keepOwnership: boolean
+  roleMap: RoleMapping[]
+  rowFilters: TableRowFilter[]
+  subset: SubsetOptions | null
+  masking: MaskingOptions | null
+}
+
+export interface MaskingOptions {
+  rules: MaskingRule[]
+  seed: string
+}
+
+export interface MaskingRule {
+  table: string
+  column: string
+  strategy: MaskStrategy
+}
+
+export type MaskStrategy =
+  | { kind: 'fakeEmail' }
+  | { kind: 'hash' }
+  | { kind: 'null' }
+  | { kind: 'constant'; value: string }
+  | { kind: 'shuffle' }
+  | { kind: 'keepFormat' }
+  | { kind: 'expression'; sql: string }
+
+export type PiiKind =
+  | 'email'
+  | 'phone'
+  | 'creditCard'
+  | 'nationalId'
+  | 'ipAddress'
+  | 'name'
+  | 'address'
+  | 'birthDate'
+  | 'secret'
+
+export interface PiiColumn {
+  schema: string
+  table: string
+  column: string
+  dataType: string
+  kind: PiiKind
+  confidence: number
+  sampledValues: number
+  matchedValues: number
+  suggestedStrategy: MaskStrategy
+}
+
+export interface PiiSkippedTable {
+  schema: string
+  table: string
+  reason: string
+}
+
+export interface PiiScan {
+  columns: PiiColumn[]
+  skippedTables: PiiSkippedTable[]
+}
+
+export type TableSelection = Pick<
+  CloneOptions,
+  | 'includeSchemas'
+  | 'excludeSchemas'
+  | 'includeTables'
+  | 'excludeTables'
+  | 'excludeTableData'
+>
+
+export interface SchemaRename {
+  from: string
+  to: string
+}
+
+export interface RoleMapping {
+  from: string
+  to: string
+}
+
+export interface SubsetOptions {
+  roots: SubsetRoot[]
+  includeReferencing: boolean
+}
+
+export interface SubsetRoot {
+  table: string
+  whereClause: string | null
+  percent: number | null
+}
+
+export interface TableRowCount {
+  table: string
+  rows: number
+}
+
+export interface TableRowFilter {
+  table: string
+  whereClause: string
 }
 
 export interface CloneProgress {
@@ -46,6 +153,53 @@ export interface CloneProgress {
   message: string
   isComplete: boolean
   isError: boolean
+  errorCode: AppErrorCode | null
+  destinationId: string | null
+}
+
+/** Payload of `clone-progress`; `jobId` is the history entry id of the clone */
+export interface CloneProgressEvent extends CloneProgress {
+  jobId: string
+}
+
+/** Payload of `clone-log` */
+export interface CloneLogEvent {
+  jobId: string
+  line: string
+}
+
+export interface ToolInfo {
+  path: string
+  version: string | null
+}
+
+export interface CloneEnvironment {
+  parallelJobs: number | null
+  pgDump: ToolInfo
+  pgRestore: ToolInfo
+  psql: ToolInfo
+  sourceServerVersion: string | null
+  destinationServerVersion: string | null
+}
+
+export interface DestinationCloneResult {
+  destinationId: string
+  destinationName: string
+  status: 'success' | 'error'
+  duration: number
+  errorMessage: string | null
+  errorCode: AppErrorCode | null
+  verifiedTables: number | null
+  serverVersion: string | null
+}
+
+export interface DatabaseCloneResult {
+  database: string
+  status: 'success' | 'error'
+  duration: number
+  errorMessage: string | null
+  errorCode: AppErrorCode | null
+  verifiedTables: number | null
 }
 
 export interface CloneHistoryEntry {
@@ -55,20 +209,79 @@ export interface CloneHistoryEntry {
   destinationId: string
   destinationName: string
   cloneType: 'structure' | 'data' | 'both'
-  status: 'success' | 'error' | 'cancelled'
+  status: 'running' | 'success' | 'error' | 'cancelled' | 'interrupted'
   startedAt: string
   completedAt: string | null
   duration: number | null
   errorMessage: string | null
+  errorCode: AppErrorCode | null
+  verifiedTables: number | null
+  options: CloneOptions | null
+  environment: CloneEnvironment | null
+  copiedRows: TableRowCount[] | null
+  databaseResults: DatabaseCloneResult[] | null
+  destinationResults: DestinationCloneResult[] | null
   logs: string[]
 }
 
+export type ExportFormat = 'json' | 'csv' | 'markdown' | 'html'
+
+export interface HistoryFilter {
+  profileId?: string | null
+  status?: CloneHistoryEntry['status'] | null
+  cloneType?: CloneType | null
+  startedAfter?: string | null
+  startedBefore?: string | null
+  search?: string | null
+  limit?: number | null
+  offset?: number | null
+}
+
+export interface HistoryPage {
+  entries: CloneHistoryEntry[]
+  total: number
+}
+
+export interface HistoryRetention {
+  maxEntries: number | null
+  maxAgeDays: number | null
+}
+
+export interface HistoryPairStats {
+  sourceId: string
+  sourceName: string
+  destinationId: string
+  destinationName: string
+  runs: number
+  successes: number
+  failures: number
+  averageDuration: number | null
+  lastRunAt: string
+}
+
+export type JobStatus = 'queued' | 'running' | 'success' | 'error'
+
+export interface CloneJob {
+  id: string
+  sourceName: string
+  destinationName: string
+  cloneType: CloneType
+  status: JobStatus
+  locks: string[]
+  queuedAt: string
+  startedAt: string | null
+  finishedAt: string | null
+  errorMessage: string | null
+}
+
 export type CloneStage =
+  | 'queued'
   | 'preparing'
   | 'backup'
   | 'cleaning'
   | 'dumping'
   | 'restoring'
+  | 'filtering'
   | 'verifying'
   | 'completed'
   | 'error'
@@ -83,7 +296,27 @@ export interface SavedOperation {
   cleanDestination: boolean
   createBackup: boolean
   cloneType: CloneType
+  masking: MaskingOptions | null
   createdAt: string
+  schedule: OperationSchedule | null
+  lastScheduledAt: string | null
+  options: CloneOptions | null
+}
+
+export type Weekday = 'Mon' | 'Tue' | 'Wed' | 'Thu' | 'Fri' | 'Sat' | 'Sun'
+
+// Times are in the computer's local time zone
+export type OperationSchedule =
+  | { kind: 'cron'; expression: string }
+  | { kind: 'daily'; time: string }
+  | { kind: 'weekly'; day: Weekday; time: string }
+
+export type MissedRunPolicy = 'runOnce' | 'skip'
+
+export interface AppDataBackup {
+  name: string
+  createdAt: string
+  size: number
 }
 
 export interface SchemaProgress {
@@ -117,3 +350,53 @@ export interface SchemaExportOptions {
   includeFunctions: boolean
   includeViews: boolean
 }
+
+export type AppErrorCode =
+  | 'TOOL_NOT_FOUND'
+  | 'CONNECTION_FAILED'
+  | 'AUTH_FAILED'
+  | 'QUERY_FAILED'
+  | 'DUMP_FAILED'
+  | 'RESTORE_FAILED'
+  | 'PROCESS_FAILED'
+  | 'PARTIAL_FAILURE'
+  | 'DESTINATION_BUSY'
+  | 'PROFILE_NOT_FOUND'
+  | 'TAG_NOT_FOUND'
+  | 'SAVED_OPERATION_NOT_FOUND'
+  | 'HISTORY_ENTRY_NOT_FOUND'
+  | 'JOB_NOT_FOUND'
+  | 'INVALID_INPUT'
+  | 'STORAGE_CORRUPT'
+  | 'STORAGE_FAILED'
+  | 'INTERNAL'
+
+export type ConnectionFailureKind =
+  | 'refused'
+  | 'timeout'
+  | 'hostNotFound'
+  | 'tls'
+  | 'databaseNotFound'
+  | 'other'
+
+export interface AppError {
+  code: AppErrorCode
+  message: string
+  kind: ConnectionFailureKind | null
+  stderr: string | null
+}
+
+export interface GlobalsCloneOptions {
+  sourceId: string
+  destinationId: string
+  includeRoles: boolean
+  includeTablespaces: boolean
+  stripPasswords: boolean
+  roleFilter: string[]
+}
+
+export interface GlobalsCloneResult {
+  applied: number
+  skipped: string[]
+  failed: string[]
+}